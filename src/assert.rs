@@ -0,0 +1,122 @@
+//! Inline key assertions for quick CI gates without a schema file.
+//!
+//! Each assertion is `KEY:TYPE` (the key must exist with a value of that
+//! type) or a bare `KEY` (the key must exist). Keys that are not asserted
+//! are ignored.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::parser::{EnvFile, ParseError};
+use crate::schema::{validate, Field, Schema, SchemaError, ValueType};
+
+/// Errors that can occur during assert operation.
+#[derive(Error, Debug)]
+pub enum AssertError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+
+    #[error("invalid assertion '{0}' (expected KEY or KEY:TYPE, e.g. PORT:int)")]
+    InvalidAssertion(String),
+
+    #[error("key '{0}' is asserted more than once")]
+    Duplicate(String),
+}
+
+/// Build a transient schema from `KEY` and `KEY:TYPE` assertions.
+pub fn schema_from_assertions(assertions: &[String]) -> Result<Schema, AssertError> {
+    let mut schema = Schema::default();
+
+    for assertion in assertions {
+        let (key, value_type) = match assertion.rsplit_once(':') {
+            Some((key, type_str)) => (key, ValueType::from_str(type_str, key)?),
+            None => (assertion.as_str(), ValueType::String),
+        };
+        if key.is_empty() {
+            return Err(AssertError::InvalidAssertion(assertion.clone()));
+        }
+        if schema
+            .fields
+            .insert(key.to_string(), Field::new(value_type))
+            .is_some()
+        {
+            return Err(AssertError::Duplicate(key.to_string()));
+        }
+    }
+
+    Ok(schema)
+}
+
+/// Run the assert command.
+pub fn run_assert(path: &Path, assertions: &[String]) -> Result<bool, AssertError> {
+    let schema = schema_from_assertions(assertions)?;
+    let env = EnvFile::from_path(path)?;
+    let result = validate(&schema, &env);
+
+    for key in &result.missing {
+        println!("error: missing required key: {key}");
+    }
+    for (key, expected_type, actual_value) in &result.type_errors {
+        println!(
+            "error: key '{key}' has invalid value '{actual_value}' (expected {})",
+            expected_type.description()
+        );
+    }
+
+    let total = schema.fields.len();
+    if result.is_valid() {
+        println!("✓ {total} assertion(s) passed");
+    } else {
+        println!("✗ {} of {total} assertion(s) failed", result.error_count());
+    }
+
+    Ok(result.is_valid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assertions(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_schema_from_assertions() {
+        let schema = schema_from_assertions(&assertions(&["PORT:int", "API_KEY"])).unwrap();
+
+        assert_eq!(schema.fields["PORT"].value_type, ValueType::Int);
+        assert_eq!(schema.fields["API_KEY"].value_type, ValueType::String);
+    }
+
+    #[test]
+    fn test_schema_from_assertions_rejects_bad_input() {
+        assert!(matches!(
+            schema_from_assertions(&assertions(&["PORT:number"])),
+            Err(AssertError::Schema(SchemaError::InvalidType(..)))
+        ));
+        assert!(matches!(
+            schema_from_assertions(&assertions(&[":int"])),
+            Err(AssertError::InvalidAssertion(_))
+        ));
+        assert!(matches!(
+            schema_from_assertions(&assertions(&["PORT:int", "PORT:bool"])),
+            Err(AssertError::Duplicate(key)) if key == "PORT"
+        ));
+    }
+
+    #[test]
+    fn test_assertions_validate() {
+        let schema = schema_from_assertions(&assertions(&["PORT:int", "DEBUG:bool"])).unwrap();
+        let env = EnvFile::from_str("PORT=8080\nDEBUG=yes\nOTHER=1").unwrap();
+        let result = validate(&schema, &env);
+
+        assert_eq!(result.type_errors.len(), 1);
+        assert_eq!(result.type_errors[0].0, "DEBUG");
+        assert!(result.extra.contains(&"OTHER".to_string()));
+    }
+}
@@ -0,0 +1,189 @@
+//! A local cache of shared schemas.
+//!
+//! `envcraft use-schema NAME PATH` copies a schema into
+//! `.envcraft/schemas/NAME.yml`, and `check` accepts `@NAME` in place of a
+//! schema path. The cache is found in the current directory or its nearest
+//! parent that has one; nothing is ever fetched over the network.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::paths;
+use crate::schema::{Schema, SchemaError};
+
+/// Directory holding envcraft's local state.
+pub const STATE_DIR: &str = ".envcraft";
+
+/// Subdirectory of the state directory holding cached schemas.
+const SCHEMAS_DIR: &str = "schemas";
+
+/// Prefix marking a schema argument as the name of a cached schema.
+const NAME_PREFIX: char = '@';
+
+/// Errors that can occur while caching or resolving schemas.
+#[derive(Error, Debug)]
+pub enum CacheError {
+    #[error("invalid schema name '{0}' (use letters, digits, '-', '_', and '.')")]
+    InvalidName(String),
+
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+
+    #[error("failed to write schema cache: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("no cached schema named '{0}' (add it with: envcraft use-schema {0} <PATH>)")]
+    NotFound(String),
+}
+
+/// Check that a name is usable as a cache file name.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Find the nearest existing state directory in `start` or its parents.
+fn find_state_dir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(STATE_DIR))
+        .find(|path| path.is_dir())
+}
+
+/// Get the path a schema with this name is cached at, relative to `start`.
+///
+/// The nearest existing state directory is used; without one, the cache
+/// goes in `start`.
+fn cache_path(start: &Path, name: &str) -> PathBuf {
+    find_state_dir(start)
+        .unwrap_or_else(|| start.join(STATE_DIR))
+        .join(SCHEMAS_DIR)
+        .join(format!("{name}.yml"))
+}
+
+/// Copy a schema into the cache under `name`, replacing any earlier copy.
+///
+/// The schema is parsed first, so an invalid schema is never cached.
+pub fn store(start: &Path, name: &str, schema: &Path) -> Result<PathBuf, CacheError> {
+    if !is_valid_name(name) {
+        return Err(CacheError::InvalidName(name.to_string()));
+    }
+    Schema::from_path(schema)?;
+
+    let target = cache_path(start, name);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(schema, &target)?;
+
+    Ok(target)
+}
+
+/// Check if a schema argument names a cached schema (`@NAME`).
+pub fn is_cached(schema: &Path) -> bool {
+    schema
+        .to_str()
+        .is_some_and(|text| text.starts_with(NAME_PREFIX))
+}
+
+/// Resolve a schema argument: `@NAME` names a cached schema, anything else
+/// is a path and is returned unchanged.
+pub fn resolve(start: &Path, schema: &Path) -> Result<PathBuf, CacheError> {
+    let Some(name) = schema
+        .to_str()
+        .and_then(|text| text.strip_prefix(NAME_PREFIX))
+    else {
+        return Ok(schema.to_path_buf());
+    };
+    if !is_valid_name(name) {
+        return Err(CacheError::InvalidName(name.to_string()));
+    }
+
+    let path = cache_path(start, name);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(CacheError::NotFound(name.to_string()))
+    }
+}
+
+/// Run the use-schema command.
+pub fn run_use_schema(name: &str, schema: &Path) -> Result<bool, CacheError> {
+    let target = store(&std::env::current_dir()?, name, schema)?;
+    println!(
+        "✓ cached {} as @{name} ({})",
+        paths::display(schema),
+        paths::display(&target)
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_store_and_resolve() {
+        let dir = TempDir::new().unwrap();
+        let schema = dir.path().join("shared.yml");
+        fs::write(&schema, "PORT: int\n").unwrap();
+
+        let cached = store(dir.path(), "billing", &schema).unwrap();
+        assert_eq!(
+            cached,
+            dir.path()
+                .join(".envcraft")
+                .join("schemas")
+                .join("billing.yml")
+        );
+
+        let nested = dir.path().join("services").join("api");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(resolve(&nested, Path::new("@billing")).unwrap(), cached);
+    }
+
+    #[test]
+    fn test_resolve_plain_path_unchanged() {
+        let dir = TempDir::new().unwrap();
+
+        assert_eq!(
+            resolve(dir.path(), Path::new("schema.yml")).unwrap(),
+            PathBuf::from("schema.yml")
+        );
+    }
+
+    #[test]
+    fn test_resolve_unknown_name() {
+        let dir = TempDir::new().unwrap();
+
+        assert!(matches!(
+            resolve(dir.path(), Path::new("@billing")),
+            Err(CacheError::NotFound(name)) if name == "billing"
+        ));
+    }
+
+    #[test]
+    fn test_store_rejects_bad_input() {
+        let dir = TempDir::new().unwrap();
+        let invalid = dir.path().join("invalid.yml");
+        fs::write(&invalid, "PORT: nonsense\n").unwrap();
+
+        assert!(matches!(
+            store(dir.path(), "../escape", &invalid),
+            Err(CacheError::InvalidName(_))
+        ));
+        assert!(matches!(
+            store(dir.path(), "billing", &invalid),
+            Err(CacheError::Schema(_))
+        ));
+        assert!(!dir.path().join(".envcraft").exists());
+    }
+}
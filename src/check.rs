@@ -0,0 +1,1049 @@
+//! The check command: validating env files against a schema.
+//!
+//! Handles loading inputs, expanding directories into env files, and
+//! printing validation reports.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::cache::{self, CacheError};
+use crate::edit::{self, EditError};
+use crate::format::render_value;
+use crate::parser::{self, render_key};
+use crate::parser::{EnvFile, EnvLine, InputEncoding, ParseError, ParseOptions};
+use crate::paths;
+use crate::remote::{self, RemoteError};
+use crate::rules::{Rule, Severities, Severity};
+use crate::schema::{
+    apply_defaults, explain, validate_with, Schema, SchemaError, ValidationResult,
+};
+
+/// Errors that can occur during check operation.
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+
+    #[error("env file error: {0}")]
+    Parse(#[from] ParseError),
+
+    #[error("failed to read directory {}: {source}", path.display())]
+    ReadDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("no env files found to check")]
+    NoEnvFiles,
+
+    #[error("--emit-missing needs exactly one env file, found {0}")]
+    EmitMissingNeedsOneFile(usize),
+
+    #[error("{0}")]
+    Remote(#[from] RemoteError),
+
+    #[error("{0}")]
+    Cache(#[from] CacheError),
+
+    #[error("failed to serialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("failed to write report {}: {source}", paths::display(path))]
+    ReportFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("--write-schema cannot update cached schema {}; update the original and run use-schema again", .0.display())]
+    WriteCachedSchema(PathBuf),
+
+    #[error("failed to write schema {}: {source}", paths::display(path))]
+    WriteSchema {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to read from stdin: {0}")]
+    Stdin(io::Error),
+
+    #[error("only one input can be read from stdin (-), found {0}")]
+    StdinTwice(usize),
+
+    #[error("--write-schema cannot update a schema read from stdin")]
+    WriteStdinSchema,
+
+    #[error("--fix can only rewrite local files, not {}", paths::display(.0))]
+    FixNotLocal(PathBuf),
+
+    #[error("failed to fix {}: {source}", paths::display(path))]
+    Fix { path: PathBuf, source: EditError },
+
+    #[error("--fix only rewrites UTF-8 files; convert {} to UTF-8 first", paths::display(.0))]
+    FixNotUtf8(PathBuf),
+
+    #[error("--strict-escapes needs --dialect node or posix; the envcraft dialect keeps every backslash as written")]
+    StrictEscapesNeedsDialect,
+}
+
+/// Path argument that reads the schema or an env file from stdin.
+const STDIN_PATH: &str = "-";
+
+/// Check if a path argument means stdin.
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+/// Read all of stdin.
+fn read_stdin() -> Result<String, CheckError> {
+    io::read_to_string(io::stdin().lock()).map_err(CheckError::Stdin)
+}
+
+/// Read an env file from stdin or a URL, applying the size limit and
+/// encoding of `parse` as for a file on disk.
+fn read_input(path: &Path, parse: &ParseOptions) -> Result<String, CheckError> {
+    let (bytes, name) = match remote::as_url(path) {
+        Some(url) => (remote::fetch(url)?, path),
+        None => {
+            let mut bytes = Vec::new();
+            io::stdin()
+                .lock()
+                .read_to_end(&mut bytes)
+                .map_err(CheckError::Stdin)?;
+            (bytes, Path::new("stdin"))
+        }
+    };
+    Ok(parser::decode_input(bytes, name, parse)?)
+}
+
+/// Where the schema for a check comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaSource {
+    /// A YAML schema file, or `@NAME` for a cached schema
+    File(PathBuf),
+    /// A reference env file whose values determine the expected types
+    Reference(PathBuf),
+}
+
+impl SchemaSource {
+    /// Load the schema from this source.
+    pub fn load(&self, parse: &ParseOptions) -> Result<Schema, CheckError> {
+        match self {
+            SchemaSource::File(path) => load_schema(path),
+            SchemaSource::Reference(path) => Ok(Schema::infer(&load_env(path, parse)?)),
+        }
+    }
+}
+
+/// Load a schema file, resolving `@NAME` to a cached schema and `-` to
+/// stdin.
+fn load_schema(path: &Path) -> Result<Schema, CheckError> {
+    if is_stdin(path) {
+        return Ok(Schema::from_str(&read_stdin()?)?);
+    }
+    let cwd = std::env::current_dir().map_err(CacheError::from)?;
+    Ok(Schema::from_path(&cache::resolve(&cwd, path)?)?)
+}
+
+/// Options controlling the check command.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Only explain how this key is declared and validated
+    pub explain: Option<String>,
+    /// Verbosity level; 1 or more prints progress for each file
+    pub verbose: u8,
+    /// How env files are parsed
+    pub parse: ParseOptions,
+    /// Report every malformed line instead of failing at the first one
+    pub tolerant: bool,
+    /// Stop at the first error instead of reporting every problem
+    pub fail_fast: bool,
+    /// Additional schema files merged over the main schema, in order
+    pub merge_schemas: Vec<PathBuf>,
+    /// Severity of each diagnostic rule
+    pub severities: Severities,
+    /// Print only the missing keys, as lines ready to append to the file
+    pub emit_missing: bool,
+    /// Print the values validation runs against before the report
+    pub print_effective: bool,
+    /// Print only key names in the effective values
+    pub redact: bool,
+    /// Also write a JSON report of every checked file to this path
+    pub report_file: Option<PathBuf>,
+    /// End the output with a `SUMMARY ...` line for scripts
+    pub summary_line: bool,
+    /// Point out missing keys that are defined in a comment
+    pub detect_commented: bool,
+    /// Append extra keys to the schema file, typed by their values
+    pub write_schema: bool,
+    /// Env file whose values replace or add to those of every checked file
+    pub values_from: Option<PathBuf>,
+    /// Rewrite values with a fixable type error in place before validating
+    pub fix: bool,
+}
+
+/// A reported diagnostic in the JSON report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportedDiagnostic {
+    /// Rule that produced the diagnostic
+    pub rule: Rule,
+    /// Severity after applying overrides; never `Allow`
+    pub severity: Severity,
+    /// Human-readable description, as printed
+    pub message: String,
+}
+
+/// The outcome of checking one file, as written to the JSON report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    /// Path of the checked file, as displayed in output
+    pub path: String,
+    /// Whether the file passed (no error-level diagnostics)
+    pub passed: bool,
+    /// Reported diagnostics, errors first; allowed rules are left out
+    pub diagnostics: Vec<ReportedDiagnostic>,
+    /// Problem counts for the summary line
+    #[serde(skip)]
+    counts: SummaryCounts,
+    /// Keys not declared in the schema, with their values
+    #[serde(skip)]
+    extra: Vec<(String, String)>,
+}
+
+/// Problem counts printed by `--summary-line`, whatever their severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct SummaryCounts {
+    missing: usize,
+    type_errors: usize,
+    extra: usize,
+}
+
+impl SummaryCounts {
+    /// Count the problems in a validation result.
+    fn of(result: &ValidationResult) -> Self {
+        SummaryCounts {
+            missing: result.missing.len(),
+            type_errors: result.type_errors.len(),
+            extra: result.extra.len(),
+        }
+    }
+}
+
+/// Render the line printed by `--summary-line`, totalled over all files.
+///
+/// The format is kept stable so scripts can match it:
+/// `SUMMARY missing=1 type_errors=2 extra=0 valid=false`.
+fn summary_line(files: &[FileReport]) -> String {
+    let total = files
+        .iter()
+        .fold(SummaryCounts::default(), |total, file| SummaryCounts {
+            missing: total.missing + file.counts.missing,
+            type_errors: total.type_errors + file.counts.type_errors,
+            extra: total.extra + file.counts.extra,
+        });
+    format!(
+        "SUMMARY missing={} type_errors={} extra={} valid={}",
+        total.missing,
+        total.type_errors,
+        total.extra,
+        files.iter().all(|file| file.passed)
+    )
+}
+
+/// The JSON report written by `--report-file`.
+#[derive(Debug, Serialize)]
+struct Report {
+    passed: bool,
+    files: Vec<FileReport>,
+}
+
+/// Write the JSON report of the checked files, if one was asked for.
+fn write_report(options: &CheckOptions, files: Vec<FileReport>) -> Result<(), CheckError> {
+    let Some(path) = &options.report_file else {
+        return Ok(());
+    };
+    let report = Report {
+        passed: files.iter().all(|file| file.passed),
+        files,
+    };
+
+    let json = serde_json::to_string_pretty(&report)? + "\n";
+    fs::write(path, json).map_err(|source| CheckError::ReportFile {
+        path: path.clone(),
+        source,
+    })
+}
+
+/// Load an env file from disk, from stdin when given `-`, or over HTTP when
+/// given a URL.
+fn load_env(path: &Path, parse: &ParseOptions) -> Result<EnvFile, CheckError> {
+    if is_stdin(path) || remote::as_url(path).is_some() {
+        return Ok(EnvFile::from_str_with(&read_input(path, parse)?, parse)?);
+    }
+    Ok(EnvFile::from_path_with(path, parse)?)
+}
+
+/// Load an env file like `load_env`, collecting malformed lines as
+/// diagnostics instead of failing at the first one.
+fn load_env_tolerant(
+    path: &Path,
+    parse: &ParseOptions,
+) -> Result<(EnvFile, Vec<(Rule, String)>), CheckError> {
+    let partial = if is_stdin(path) || remote::as_url(path).is_some() {
+        EnvFile::from_str_tolerant(&read_input(path, parse)?, parse)?
+    } else {
+        EnvFile::from_path_tolerant(path, parse)?
+    };
+    let invalid = partial
+        .errors
+        .iter()
+        .map(|error| (Rule::InvalidLine, error.to_string()))
+        .collect();
+    Ok((partial.env, invalid))
+}
+
+/// Check if a file name looks like an env file (`.env`, `.env.*`, `*.env`).
+fn is_env_file_name(name: &str) -> bool {
+    name == ".env" || name.starts_with(".env.") || name.ends_with(".env")
+}
+
+/// Expand directory arguments into the env files they contain.
+///
+/// Files given directly are kept as-is; directory contents are sorted by
+/// name for deterministic processing order.
+pub fn expand_env_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, CheckError> {
+    let mut expanded = Vec::new();
+
+    for path in paths {
+        if !path.is_dir() {
+            expanded.push(path.clone());
+            continue;
+        }
+
+        let read_dir_error = |source| CheckError::ReadDir {
+            path: path.clone(),
+            source,
+        };
+        let mut found = Vec::new();
+        for entry in fs::read_dir(path).map_err(read_dir_error)? {
+            let entry_path = entry.map_err(read_dir_error)?.path();
+            let is_env = entry_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_env_file_name);
+            if is_env && entry_path.is_file() {
+                found.push(entry_path);
+            }
+        }
+        found.sort();
+        expanded.extend(found);
+    }
+
+    Ok(expanded)
+}
+
+/// Describe which schema file declared a key, when several were merged.
+fn origin_note(schema: &Schema, key: &str) -> String {
+    schema
+        .origin(key)
+        .map(|path| format!(" (declared in {})", path.display()))
+        .unwrap_or_default()
+}
+
+/// Describe every problem in a validation result, tagged with its rule.
+///
+/// Missing keys listed in `commented` are reported as present but commented
+/// out.
+fn diagnostics(
+    schema: &Schema,
+    result: &ValidationResult,
+    commented: &BTreeSet<String>,
+) -> Vec<(Rule, String)> {
+    let mut diagnostics = Vec::new();
+
+    for key in &result.missing {
+        let note = if commented.contains(key) {
+            " (present but commented out)"
+        } else {
+            ""
+        };
+        diagnostics.push((
+            Rule::MissingKey,
+            format!(
+                "missing required key: {key}{note}{}",
+                origin_note(schema, key)
+            ),
+        ));
+    }
+
+    for (key, expected_type, actual_value) in &result.type_errors {
+        diagnostics.push((
+            Rule::InvalidValue,
+            format!(
+                "key '{key}' has invalid value '{actual_value}' (expected {}){}",
+                expected_type.description(),
+                origin_note(schema, key)
+            ),
+        ));
+    }
+
+    for (key, value) in &result.forbidden {
+        diagnostics.push((
+            Rule::PlaceholderValue,
+            format!(
+                "key '{key}' has placeholder value '{value}'{}",
+                origin_note(schema, key)
+            ),
+        ));
+    }
+
+    for group in &result.partial_groups {
+        diagnostics.push((
+            Rule::PartialGroup,
+            format!(
+                "group '{}' must be set in full or not at all: {} set, {} missing",
+                group.name,
+                group.present.join(", "),
+                group.missing.join(", ")
+            ),
+        ));
+    }
+
+    for (canonical, alias) in &result.aliased {
+        diagnostics.push((
+            Rule::DeprecatedKey,
+            format!("deprecated key '{alias}' used, rename to '{canonical}'"),
+        ));
+    }
+
+    for key in &result.extra {
+        diagnostics.push((Rule::ExtraKey, format!("extra key not in schema: {key}")));
+    }
+
+    diagnostics
+}
+
+/// Describe each key defined more than once with differing values, naming
+/// the line whose value is the one validated.
+///
+/// Repeating a key with the same value is harmless and not reported. When a
+/// later `#include` overrides every definition, the included value is the
+/// one validated.
+fn duplicate_definitions(env: &EnvFile) -> Vec<(Rule, String)> {
+    let mut definitions: BTreeMap<&str, Vec<(Option<usize>, &str)>> = BTreeMap::new();
+    for line in &env.lines {
+        if let EnvLine::KeyValue {
+            key, value, line, ..
+        } = line
+        {
+            definitions
+                .entry(key)
+                .or_default()
+                .push((*line, value.as_str()));
+        }
+    }
+
+    definitions
+        .into_iter()
+        .filter(|(_, defined)| defined.iter().any(|(_, value)| *value != defined[0].1))
+        .map(|(key, defined)| {
+            let numbers: Vec<String> = defined
+                .iter()
+                .filter_map(|(line, _)| line.map(|line| line.to_string()))
+                .collect();
+            let (last_line, last_value) = defined[defined.len() - 1];
+            let validated = match last_line {
+                Some(line) if env.get(key).map(String::as_str) == Some(last_value) => {
+                    format!("the value from line {line} is validated")
+                }
+                _ => "an included value is validated".to_string(),
+            };
+            (
+                Rule::DuplicateKey,
+                format!(
+                    "key '{key}' is defined {} times with differing values (lines {}); {validated}",
+                    defined.len(),
+                    numbers.join(", ")
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Count the diagnostics reported as errors.
+fn error_count(diagnostics: &[(Rule, String)], severities: &Severities) -> usize {
+    diagnostics
+        .iter()
+        .filter(|(rule, _)| severities.of(*rule) == Severity::Error)
+        .count()
+}
+
+/// Keep the diagnostics that are reported, with their severities, errors
+/// first.
+fn reported(diagnostics: &[(Rule, String)], severities: &Severities) -> Vec<ReportedDiagnostic> {
+    let mut reported = Vec::new();
+
+    for level in [Severity::Error, Severity::Warning] {
+        for (rule, message) in diagnostics {
+            if severities.of(*rule) == level {
+                reported.push(ReportedDiagnostic {
+                    rule: *rule,
+                    severity: level,
+                    message: message.clone(),
+                });
+            }
+        }
+    }
+
+    reported
+}
+
+/// Print the validation report for a single file.
+///
+/// Errors are listed before warnings; allowed rules are not printed.
+fn print_report(diagnostics: &[(Rule, String)], severities: &Severities) {
+    let mut warning_count = 0;
+
+    for diagnostic in reported(diagnostics, severities) {
+        if diagnostic.severity == Severity::Warning {
+            warning_count += 1;
+            println!("warning: {}", diagnostic.message);
+        } else {
+            println!("error: {}", diagnostic.message);
+        }
+    }
+
+    // Summary
+    let error_count = error_count(diagnostics, severities);
+    if error_count == 0 {
+        if warning_count == 0 {
+            println!("✓ validation passed");
+        } else {
+            println!("✓ validation passed with {warning_count} warning(s)");
+        }
+    } else {
+        println!("✗ validation failed with {error_count} error(s)");
+    }
+}
+
+/// Render the keys the file is missing as `KEY=` lines.
+///
+/// Values are left empty for the user to fill in, so nothing from the file
+/// or the schema ends up in the output. A field's description is written
+/// as a comment above its key.
+fn missing_lines(schema: &Schema, result: &ValidationResult) -> String {
+    let mut output = String::new();
+
+    for key in &result.missing {
+        if let Some(description) = schema
+            .fields
+            .get(key)
+            .and_then(|field| field.description.as_deref())
+        {
+            output.push_str(&format!("# {description}\n"));
+        }
+        output.push_str(&format!("{key}=\n"));
+    }
+
+    output
+}
+
+/// Overlay the values of `values` on `file`: keys both define take the
+/// value from `values`, and keys only `values` defines are added after the
+/// rest.
+fn overlay(file: &EnvFile, values: &EnvFile) -> EnvFile {
+    let mut overlaid = file.clone();
+
+    for line in &mut overlaid.lines {
+        if let EnvLine::KeyValue {
+            key, value, raw, ..
+        } = line
+        {
+            if let Some(new_value) = values.get(key) {
+                value.clone_from(new_value);
+                raw.clone_from(new_value);
+            }
+        }
+    }
+    for (key, value) in &values.entries {
+        if !file.contains_key(key) {
+            overlaid.lines.push(EnvLine::KeyValue {
+                key: key.clone(),
+                quoted_key: false,
+                value: value.clone(),
+                raw: value.clone(),
+                comment: None,
+                line: None,
+            });
+        }
+    }
+    overlaid.entries.extend(values.entries.clone());
+
+    overlaid
+}
+
+/// Render the effective values as `KEY=VALUE` lines, sorted by key.
+///
+/// Keys filled in from a schema default are marked with a `# default`
+/// comment. With `redact`, only key names are shown.
+fn effective_lines(env: &EnvFile, effective: &EnvFile, redact: bool) -> Vec<String> {
+    effective
+        .entries
+        .iter()
+        .map(|(key, value)| {
+            let mut line = render_key(key).into_owned();
+            if !redact {
+                line.push_str(&format!("={}", render_value(value)));
+            }
+            if !env.contains_key(key) {
+                line.push_str("  # default");
+            }
+            line
+        })
+        .collect()
+}
+
+/// Rewrite the values of `file` that have a fixable type error, returning
+/// each change as (key, old value, new value).
+///
+/// Only the file's own values are fixed: defaults, `--values-from`
+/// overlays, and keys read from an `#include`d file are left out. Every
+/// other line stays byte-identical.
+fn fix_file(
+    schema: &Schema,
+    file: &EnvFile,
+    env_path: &Path,
+    parse: &ParseOptions,
+) -> Result<Vec<(String, String, String)>, CheckError> {
+    let mut fixes: Vec<(String, String, String)> = validate_with(schema, file, false)
+        .type_errors
+        .into_iter()
+        .filter_map(|(key, value_type, value)| {
+            let fixed = value_type.fix(&value)?;
+            Some((key, value, fixed))
+        })
+        .collect();
+    if fixes.is_empty() {
+        return Ok(fixes);
+    }
+    if is_stdin(env_path) || remote::as_url(env_path).is_some() {
+        return Err(CheckError::FixNotLocal(env_path.to_path_buf()));
+    }
+    // Writing the fixes back would silently transcode the file to UTF-8
+    if parse.encoding != InputEncoding::Utf8 {
+        return Err(CheckError::FixNotUtf8(env_path.to_path_buf()));
+    }
+
+    let fix_error = |source| CheckError::Fix {
+        path: env_path.to_path_buf(),
+        source,
+    };
+    let (mut content, bom) = edit::read_file(env_path).map_err(fix_error)?;
+    // Parsing the text alone leaves includes unresolved
+    let own = EnvFile::from_str_with(&content, parse).map_err(|e| fix_error(e.into()))?;
+    fixes.retain(|(key, _, _)| own.contains_key(key));
+    if fixes.is_empty() {
+        return Ok(fixes);
+    }
+
+    for (key, _, fixed) in &fixes {
+        content = edit::set(&content, key, fixed).map_err(fix_error)?;
+    }
+    edit::write_file(env_path, &content, bom).map_err(fix_error)?;
+
+    Ok(fixes)
+}
+
+/// Check a single env file, printing its report when `print` is set.
+///
+/// With `values`, its values are overlaid on the file before validation.
+fn check_file(
+    schema: &Schema,
+    values: Option<&EnvFile>,
+    env_path: &Path,
+    options: &CheckOptions,
+    print: bool,
+) -> Result<FileReport, CheckError> {
+    let report = |passed: bool, diagnostics: Vec<ReportedDiagnostic>| FileReport {
+        path: paths::display(env_path),
+        passed,
+        diagnostics,
+        counts: SummaryCounts::default(),
+        extra: Vec::new(),
+    };
+
+    let load = || {
+        if options.tolerant {
+            load_env_tolerant(env_path, &options.parse)
+        } else {
+            Ok((load_env(env_path, &options.parse)?, Vec::new()))
+        }
+    };
+    let (mut file, mut invalid_lines) = load()?;
+    if options.fix {
+        let fixes = fix_file(schema, &file, env_path, &options.parse)?;
+        for (key, old, new) in &fixes {
+            println!("fixed {key} in {}: {old} → {new}", paths::display(env_path));
+        }
+        if !fixes.is_empty() {
+            (file, invalid_lines) = load()?;
+        }
+    }
+    let overlaid = values.map(|values| overlay(&file, values));
+    let env = apply_defaults(schema, overlaid.as_ref().unwrap_or(&file));
+
+    if print && options.print_effective {
+        let before_defaults = overlaid.as_ref().unwrap_or(&file);
+        for line in effective_lines(before_defaults, &env, options.redact) {
+            println!("{line}");
+        }
+        println!();
+    }
+
+    if let Some(key) = &options.explain {
+        return match explain(schema, &env, key) {
+            Some(explanation) => {
+                if print {
+                    for line in explanation.render() {
+                        println!("{line}");
+                    }
+                }
+                Ok(report(explanation.passed(), Vec::new()))
+            }
+            None => {
+                if print {
+                    println!("key '{key}' is not declared in schema");
+                }
+                Ok(report(false, Vec::new()))
+            }
+        };
+    }
+
+    let result = validate_with(schema, &env, options.fail_fast);
+    if options.emit_missing {
+        print!("{}", missing_lines(schema, &result));
+        return Ok(report(result.missing.is_empty(), Vec::new()));
+    }
+
+    let mut diagnostics = invalid_lines;
+    diagnostics.extend(duplicate_definitions(&file));
+    let commented = if options.detect_commented {
+        file.commented_keys()
+    } else {
+        BTreeSet::new()
+    };
+    diagnostics.extend(self::diagnostics(schema, &result, &commented));
+    if print {
+        print_report(&diagnostics, &options.severities);
+    }
+
+    Ok(FileReport {
+        counts: SummaryCounts::of(&result),
+        extra: result
+            .extra
+            .iter()
+            .map(|key| (key.clone(), env.get(key).cloned().unwrap_or_default()))
+            .collect(),
+        ..report(
+            error_count(&diagnostics, &options.severities) == 0,
+            reported(&diagnostics, &options.severities),
+        )
+    })
+}
+
+/// Append the extra keys of every checked file to the schema file, with
+/// types inferred from their values, and say which were added.
+///
+/// When files disagree, the first file's value decides the type.
+fn write_schema(source: &SchemaSource, reports: &[FileReport]) -> Result<(), CheckError> {
+    let SchemaSource::File(path) = source else {
+        unreachable!("clap rejects --write-schema with --reference");
+    };
+    if is_stdin(path) {
+        return Err(CheckError::WriteStdinSchema);
+    }
+    if cache::is_cached(path) {
+        return Err(CheckError::WriteCachedSchema(path.clone()));
+    }
+
+    let extra: Vec<(String, String)> = reports
+        .iter()
+        .flat_map(|report| report.extra.iter().cloned())
+        .collect();
+    let content = fs::read_to_string(path).map_err(SchemaError::from)?;
+    let (updated, added) = Schema::append_inferred(&content, &extra)?;
+    if added.is_empty() {
+        println!("schema {} already declares every key", paths::display(path));
+        return Ok(());
+    }
+
+    fs::write(path, updated).map_err(|source| CheckError::WriteSchema {
+        path: path.clone(),
+        source,
+    })?;
+    println!(
+        "added {} key(s) to schema {}: {}",
+        added.len(),
+        paths::display(path),
+        added.join(", ")
+    );
+    Ok(())
+}
+
+/// Run the check command.
+///
+/// A single file prints its full report. With several files (or a
+/// directory), only an aggregate summary is printed unless `verbose` is set,
+/// in which case each file is announced and reported as it is processed.
+/// With `fail_fast`, checking stops at the first file that fails.
+/// With `report_file`, the outcome for every checked file is also written
+/// there as JSON. With `values_from`, that file's values are overlaid on
+/// every checked file. Any one input, schema or env file, may be `-` to read it
+/// from stdin.
+pub fn run_check(
+    source: &SchemaSource,
+    env_paths: &[PathBuf],
+    options: &CheckOptions,
+) -> Result<bool, CheckError> {
+    let source_path = match source {
+        SchemaSource::File(path) | SchemaSource::Reference(path) => path,
+    };
+    let stdin_inputs = std::iter::once(source_path)
+        .chain(&options.merge_schemas)
+        .chain(&options.values_from)
+        .chain(env_paths)
+        .filter(|path| is_stdin(path))
+        .count();
+    if stdin_inputs > 1 {
+        return Err(CheckError::StdinTwice(stdin_inputs));
+    }
+    if options.parse.strict_escapes && options.parse.dialect.double_quoted_escapes().is_none() {
+        return Err(CheckError::StrictEscapesNeedsDialect);
+    }
+
+    let mut schema = source.load(&options.parse)?;
+    for path in &options.merge_schemas {
+        schema.merge(load_schema(path)?);
+    }
+    let env_paths = expand_env_paths(env_paths)?;
+    let values = options
+        .values_from
+        .as_deref()
+        .map(|path| load_env(path, &options.parse))
+        .transpose()?;
+
+    match env_paths.as_slice() {
+        [] => return Err(CheckError::NoEnvFiles),
+        [env_path] => {
+            let report = check_file(&schema, values.as_ref(), env_path, options, true)?;
+            let passed = report.passed;
+            let reports = vec![report];
+            if options.summary_line {
+                println!("{}", summary_line(&reports));
+            }
+            if options.write_schema {
+                write_schema(source, &reports)?;
+            }
+            write_report(options, reports)?;
+            return Ok(passed);
+        }
+        paths if options.emit_missing => {
+            return Err(CheckError::EmitMissingNeedsOneFile(paths.len()));
+        }
+        _ => {}
+    }
+
+    let verbose = options.verbose > 0;
+    let mut checked = 0;
+    let mut failed = 0;
+    let mut reports = Vec::new();
+
+    for env_path in &env_paths {
+        checked += 1;
+        if verbose {
+            println!("checking {}", paths::display(env_path));
+        }
+        let report = check_file(&schema, values.as_ref(), env_path, options, verbose)?;
+        if !report.passed {
+            failed += 1;
+        }
+        reports.push(report);
+        if verbose {
+            println!();
+        }
+        if options.fail_fast && failed > 0 {
+            println!("stopped at first failure: {}", paths::display(env_path));
+            break;
+        }
+    }
+
+    println!("{checked} files, {failed} failed");
+    if options.summary_line {
+        println!("{}", summary_line(&reports));
+    }
+    if options.write_schema {
+        write_schema(source, &reports)?;
+    }
+    write_report(options, reports)?;
+
+    Ok(failed == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::validate;
+
+    #[test]
+    fn test_is_env_file_name() {
+        assert!(is_env_file_name(".env"));
+        assert!(is_env_file_name(".env.production"));
+        assert!(is_env_file_name("staging.env"));
+        assert!(!is_env_file_name("schema.yml"));
+        assert!(!is_env_file_name(".envrc"));
+    }
+
+    #[test]
+    fn test_duplicate_definitions() {
+        let env = EnvFile::from_str("PORT=80\nHOST=a\n# note\nPORT=8080\nHOST=a\n").unwrap();
+
+        assert_eq!(
+            duplicate_definitions(&env),
+            vec![(
+                Rule::DuplicateKey,
+                "key 'PORT' is defined 2 times with differing values (lines 1, 4); the value from line 4 is validated".to_string()
+            )]
+        );
+        assert!(duplicate_definitions(&EnvFile::from_str("A=1\nB=2").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_expand_env_paths_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in ["b.env", ".env", "schema.yml", ".env.local"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        fs::create_dir(dir.path().join("nested.env")).unwrap();
+
+        let expanded = expand_env_paths(&[dir.path().to_path_buf()]).unwrap();
+        let names: Vec<_> = expanded
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+
+        assert_eq!(names, vec![".env", ".env.local", "b.env"]);
+    }
+
+    #[test]
+    fn test_reference_source_infers_types() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let reference = dir.path().join("reference.env");
+        fs::write(&reference, "PORT=8080\nDEBUG=true\n").unwrap();
+
+        let schema = SchemaSource::Reference(reference)
+            .load(&ParseOptions::default())
+            .unwrap();
+
+        let matching = EnvFile::from_str("PORT=3000\nDEBUG=false").unwrap();
+        assert!(validate(&schema, &matching).is_valid());
+
+        let missing = EnvFile::from_str("PORT=3000").unwrap();
+        assert_eq!(validate(&schema, &missing).missing, vec!["DEBUG"]);
+
+        let mismatched = EnvFile::from_str("PORT=http\nDEBUG=false").unwrap();
+        assert_eq!(validate(&schema, &mismatched).type_errors[0].0, "PORT");
+    }
+
+    #[test]
+    fn test_overlay_takes_values_and_adds_keys() {
+        let file = EnvFile::from_str("# app\nAPI_KEY=changeme\nPORT=80\n").unwrap();
+        let values = EnvFile::from_str("API_KEY=s3cret\nDB_PASSWORD=hunter2\n").unwrap();
+        let overlaid = overlay(&file, &values);
+
+        assert_eq!(overlaid.get("API_KEY"), Some(&"s3cret".to_string()));
+        assert_eq!(overlaid.get("PORT"), Some(&"80".to_string()));
+        assert_eq!(overlaid.get("DB_PASSWORD"), Some(&"hunter2".to_string()));
+        assert_eq!(overlaid.lines.len(), 4);
+        assert!(matches!(
+            &overlaid.lines[1],
+            EnvLine::KeyValue { value, line: Some(2), .. } if value == "s3cret"
+        ));
+    }
+
+    #[test]
+    fn test_run_check_rejects_two_stdin_inputs() {
+        let source = SchemaSource::File(PathBuf::from("-"));
+        let error = run_check(&source, &[PathBuf::from("-")], &CheckOptions::default());
+
+        assert!(matches!(error, Err(CheckError::StdinTwice(2))));
+    }
+
+    #[test]
+    fn test_expand_env_paths_keeps_files() {
+        let paths = vec![PathBuf::from("a.env"), PathBuf::from("missing.env")];
+
+        assert_eq!(expand_env_paths(&paths).unwrap(), paths);
+    }
+
+    #[test]
+    fn test_diagnostics_commented_key() {
+        let schema = Schema::from_str("PORT: int\nHOST: string\n").unwrap();
+        let env = EnvFile::from_str("# PORT=8080\n").unwrap();
+        let result = validate(&schema, &env);
+
+        let messages: Vec<String> = diagnostics(&schema, &result, &env.commented_keys())
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "missing required key: HOST",
+                "missing required key: PORT (present but commented out)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_lines_only_missing_keys() {
+        let schema = Schema::from_str(
+            "PORT: int\nAPI_KEY: {type: string, description: Key for the billing API}\nDEBUG: bool\n",
+        )
+        .unwrap();
+        let env = EnvFile::from_str("PORT=8080\n").unwrap();
+        let result = validate(&schema, &env);
+
+        assert_eq!(
+            missing_lines(&schema, &result),
+            "# Key for the billing API\nAPI_KEY=\nDEBUG=\n"
+        );
+    }
+
+    #[test]
+    fn test_effective_lines_mark_defaults() {
+        let schema = Schema::from_str("PORT: {type: int, default: 8080}\nNAME: string\n").unwrap();
+        let file = EnvFile::from_str("NAME=my app\n").unwrap();
+        let effective = apply_defaults(&schema, &file);
+
+        assert_eq!(
+            effective_lines(&file, &effective, false),
+            vec!["NAME=my app", "PORT=8080  # default"]
+        );
+        assert_eq!(
+            effective_lines(&file, &effective, true),
+            vec!["NAME", "PORT  # default"]
+        );
+    }
+
+    #[test]
+    fn test_missing_lines_complete_file() {
+        let schema = Schema::from_str("PORT: int\n").unwrap();
+        let env = EnvFile::from_str("PORT=oops\n").unwrap();
+
+        assert_eq!(missing_lines(&schema, &validate(&schema, &env)), "");
+    }
+}
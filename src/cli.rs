@@ -2,9 +2,17 @@
 //!
 //! Uses clap with derive macros for a clean, type-safe CLI structure.
 
+use std::env;
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+
+use crate::config::{self, Config, ConfigError};
+use crate::diff::MaskStyle;
+use crate::filter::KeyFilter;
+use crate::format::{ExportStyle, KeyCase, OnDuplicate, SortOrder};
+use crate::parser::{self, Dialect, InputEncoding};
+use crate::rules::{self, Rule, Severity};
 
 /// envcraft - Precise tools for .env files
 ///
@@ -23,49 +31,385 @@ pub struct Cli {
 
 impl Cli {
     /// Parse command-line arguments and return the CLI structure.
-    pub fn parse_args() -> Self {
-        Self::parse()
+    ///
+    /// Defaults from a `.envcraft.toml` in the current directory or a parent
+    /// are applied first, so explicit arguments override them.
+    pub fn parse_args() -> Result<Self, ConfigError> {
+        let mut command = Self::command();
+        if let Some(path) = env::current_dir().ok().as_deref().and_then(config::find) {
+            command = Config::from_path(&path)?.apply(command)?;
+        }
+
+        Ok(Self::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit()))
     }
 }
 
 /// Available subcommands for envcraft.
 #[derive(Subcommand, Debug)]
 pub enum Commands {
+    /// Check keys and value types without a schema file
+    ///
+    /// Each assertion is KEY (the key must exist) or KEY:TYPE (the key must
+    /// exist with a value of that type). Other keys are ignored.
+    Assert {
+        /// Path to the .env file to check
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Assertions such as PORT:int, DEBUG:bool, or API_KEY
+        #[arg(value_name = "KEY[:TYPE]", required = true)]
+        assertions: Vec<String>,
+    },
+
     /// Validate a .env file against a YAML schema
     ///
     /// The schema file defines expected keys and their types.
-    /// Supported types: string, int, bool
+    /// Supported types: string, int, bool, list, bytesize, date, datetime,
+    /// base64, hex, host, semver, json
     Check {
-        /// Path to the YAML schema file
-        #[arg(value_name = "SCHEMA")]
-        schema: PathBuf,
+        /// Path to the YAML schema file, @NAME for a cached schema, or - for stdin (omit when using --reference)
+        #[arg(value_name = "SCHEMA", required_unless_present = "reference")]
+        schema: Option<PathBuf>,
+
+        /// Paths to .env files or directories of .env files to validate (- reads one from stdin)
+        #[arg(value_name = "ENVFILE", required_unless_present = "reference")]
+        envfiles: Vec<PathBuf>,
+
+        /// Infer expected types from a reference .env file instead of a schema
+        #[arg(long, value_name = "REFERENCE")]
+        reference: Option<PathBuf>,
+
+        /// Merge another schema file over the main one (repeatable)
+        #[arg(long, value_name = "SCHEMA")]
+        merge_schema: Vec<PathBuf>,
+
+        /// Take values from this env file (e.g. secrets) over those of each
+        /// checked file, adding keys it alone defines
+        #[arg(long, value_name = "FILE")]
+        values_from: Option<PathBuf>,
+
+        /// Rewrite values with an unambiguous type error in place (e.g. a
+        /// bool written as yes becomes true) before validating
+        #[arg(long, default_value_t = false, conflicts_with_all = ["explain", "emit_missing"])]
+        fix: bool,
+
+        /// Print each file as it is processed (when checking several files)
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+
+        /// Print everything the schema says about KEY and how it validated
+        #[arg(long, value_name = "KEY")]
+        explain: Option<String>,
+
+        /// Parse env files the way this loader does
+        #[arg(long, value_enum, default_value_t = Dialect::Envcraft)]
+        dialect: Dialect,
+
+        /// Stop at the first error instead of reporting every problem
+        #[arg(long, default_value_t = false)]
+        fail_fast: bool,
+
+        /// Accept lines holding only a key name, read as KEY= (empty value)
+        #[arg(long, default_value_t = false)]
+        allow_bare_keys: bool,
+
+        /// Join a line ending in `\` with the next line
+        #[arg(long, default_value_t = false)]
+        line_continuation: bool,
+
+        /// Read the keys of files named by `#include <path>` lines
+        #[arg(long, default_value_t = false)]
+        resolve_includes: bool,
+
+        /// Reject backslash escapes the dialect does not recognize inside
+        /// double quotes (e.g. `\q`) instead of keeping them as written;
+        /// needs `--dialect node` or `posix`
+        #[arg(long, default_value_t = false)]
+        strict_escapes: bool,
+
+        /// Reject values with a quote at one end but not the other (e.g.
+        /// `KEY="unterminated`) instead of keeping the quote as written
+        #[arg(long, default_value_t = false)]
+        strict_quotes: bool,
+
+        /// Abort when an env file defines more than N keys
+        #[arg(long, value_name = "N")]
+        max_keys: Option<usize>,
+
+        /// Abort before reading an env file larger than SIZE (e.g. 1048576, 10MB)
+        #[arg(long, value_name = "SIZE", value_parser = parser::parse_size_limit)]
+        max_file_size: Option<u64>,
+
+        /// Character encoding of the env files; they are transcoded to UTF-8
+        #[arg(long, value_enum, value_name = "ENCODING", default_value_t = InputEncoding::Utf8)]
+        input_encoding: InputEncoding,
+
+        /// Report every malformed line and check the keys that did parse
+        #[arg(long, default_value_t = false)]
+        tolerant: bool,
+
+        /// Report missing keys defined in a comment (e.g. `# PORT=8080`) as
+        /// present but commented out
+        #[arg(long, default_value_t = false)]
+        detect_commented: bool,
+
+        /// Append keys missing from the schema to the schema file, with
+        /// types inferred from their values
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["reference", "explain", "emit_missing"]
+        )]
+        write_schema: bool,
+
+        /// Print only the missing keys as KEY= lines, ready to append to the file
+        #[arg(long, default_value_t = false, conflicts_with_all = ["explain", "fail_fast"])]
+        emit_missing: bool,
+
+        /// Print the values validated, including schema defaults, before the report
+        #[arg(long, default_value_t = false, conflicts_with = "emit_missing")]
+        print_effective: bool,
+
+        /// Hide values in --print-effective output (show only key names)
+        #[arg(long, default_value_t = false, requires = "print_effective")]
+        redact: bool,
+
+        /// Also write a JSON report of the results to PATH
+        #[arg(long, value_name = "PATH")]
+        report_file: Option<PathBuf>,
+
+        /// End with a line like `SUMMARY missing=1 type_errors=0 extra=2 valid=false`
+        #[arg(long, default_value_t = false, conflicts_with_all = ["explain", "emit_missing"])]
+        summary_line: bool,
+
+        #[command(flatten)]
+        severity: SeverityArgs,
+    },
+
+    /// Check that keys have the same value in every .env file
+    ///
+    /// Each --key is compared across all files. A key missing from any
+    /// file counts as a difference.
+    CheckConsistency {
+        /// Paths to the .env files to compare
+        #[arg(value_name = "ENVFILE", num_args = 2.., required = true)]
+        files: Vec<PathBuf>,
+
+        /// Key that must match across all files (repeatable)
+        #[arg(long = "key", value_name = "KEY", required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Convert between a .env file and JSON
+    ///
+    /// With --to json or docker-env the input is a .env file; with --to env
+    /// the input is a JSON object, and with --to json-schema an envcraft
+    /// schema. Values are always strings in JSON output,
+    /// which is compact with sorted keys unless --pretty is given.
+    Convert {
+        /// Path to the input file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        to: ConvertFormat,
+
+        /// Map keys split on SEP to nested JSON objects (e.g. --nest-on .)
+        #[arg(
+            long,
+            value_name = "SEP",
+            value_parser = clap::builder::NonEmptyStringValueParser::new()
+        )]
+        nest_on: Option<String>,
+
+        /// With --to json or json-schema, indent output for reading instead
+        /// of one line
+        #[arg(long, default_value_t = false)]
+        pretty: bool,
+
+        /// Parse .env input the way this loader does
+        #[arg(long, value_enum, default_value_t = Dialect::Envcraft)]
+        dialect: Dialect,
 
-        /// Path to the .env file to validate
-        #[arg(value_name = "ENVFILE")]
-        envfile: PathBuf,
+        /// Only include keys selected by EXPR, e.g.
+        /// 'startswith("DB_") and not endswith("_SECRET")'
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<KeyFilter>,
     },
 
     /// Show semantic differences between two .env files
     ///
     /// Output shows added (+), removed (-), and changed (~) keys.
-    /// Results are sorted alphabetically by key name.
+    /// Results are sorted alphabetically by key name. With --env, FILE1 is
+    /// compared against the current process environment instead. With
+    /// --matrix, any number of files are compared at once.
     Diff {
-        /// Path to the first .env file
+        /// Path to the first .env file (or <REV>:<PATH> with --git)
         #[arg(value_name = "FILE1")]
         file1: PathBuf,
 
-        /// Path to the second .env file
-        #[arg(value_name = "FILE2")]
-        file2: PathBuf,
+        /// Path to the second .env file (or <REV>:<PATH> with --git)
+        #[arg(value_name = "FILE2", required_unless_present = "env")]
+        file2: Option<PathBuf>,
+
+        /// Further files to compare with --matrix
+        #[arg(value_name = "FILE", requires = "matrix")]
+        more_files: Vec<PathBuf>,
+
+        /// Compare FILE1 against the current process environment
+        #[arg(long, default_value_t = false, conflicts_with = "file2")]
+        env: bool,
+
+        /// Compare every file at once, as a table of each key's value per file
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = [
+                "env", "report_file", "type_aware", "fold_case_values", "format", "as_patch",
+                "context", "show_unchanged", "unified", "sort",
+            ]
+        )]
+        matrix: bool,
+
+        /// With --env, ignore environment variables FILE1 does not define
+        #[arg(long, default_value_t = false, requires = "env")]
+        file_keys_only: bool,
+
+        /// Also write a JSON report of the differences to PATH
+        #[arg(long, value_name = "PATH")]
+        report_file: Option<PathBuf>,
 
         /// Hide values in output (show only key names)
-        #[arg(long, default_value_t = false)]
+        #[arg(long, default_value_t = false, group = "redacting")]
         redact: bool,
+
+        /// Show a short SHA-256 fingerprint instead of each value
+        #[arg(long, default_value_t = false, conflicts_with = "redact")]
+        redact_hash: bool,
+
+        /// Redact only keys matching a glob PATTERN (e.g. '*_SECRET'),
+        /// showing the rest; repeatable
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            conflicts_with = "redact",
+            group = "redacting"
+        )]
+        redact_key: Vec<String>,
+
+        /// How --redact and --redact-key show values: left out, a fixed run
+        /// of mask characters, or one mask character per value character
+        #[arg(
+            long,
+            value_enum,
+            value_name = "STYLE",
+            default_value_t = MaskStyle::Hide,
+            requires = "redacting",
+            conflicts_with = "redact_hash"
+        )]
+        mask_style: MaskStyle,
+
+        /// Character used by --mask-style fixed and length
+        #[arg(long, value_name = "C", default_value_t = '*', requires = "mask_style")]
+        mask_char: char,
+
+        /// Compare values as written, including surrounding quotes
+        #[arg(long, default_value_t = false)]
+        raw: bool,
+
+        /// Read <REV>:<PATH> arguments from git (e.g. HEAD~1:.env)
+        #[arg(long, default_value_t = false)]
+        git: bool,
+
+        /// YAML schema describing key types
+        #[arg(long, value_name = "SCHEMA")]
+        schema: Option<PathBuf>,
+
+        /// Compare schema-typed values by meaning (e.g. 8080 equals 08080)
+        #[arg(
+            long,
+            default_value_t = false,
+            requires = "schema",
+            conflicts_with = "raw"
+        )]
+        type_aware: bool,
+
+        /// Compare values of bool-typed keys ignoring case (TRUE equals
+        /// true); other keys stay case-sensitive
+        #[arg(
+            long,
+            default_value_t = false,
+            requires = "schema",
+            conflicts_with_all = ["raw", "type_aware"]
+        )]
+        fold_case_values: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+
+        /// Print envcraft set/unset commands that turn FILE1 into FILE2
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["redact", "redact_hash", "redact_key", "raw", "format"]
+        )]
+        as_patch: bool,
+
+        /// Show up to N unchanged neighboring keys around each difference
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 0,
+            conflicts_with = "as_patch"
+        )]
+        context: usize,
+
+        /// Also list unchanged keys, marked with =
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = ["as_patch", "context"]
+        )]
+        show_unchanged: bool,
+
+        /// Print a unified diff of both files as `format` would write them,
+        /// with N lines of context (default 3)
+        #[arg(
+            long,
+            value_name = "N",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "3",
+            conflicts_with_all = [
+                "report_file", "redact", "redact_hash", "redact_key", "raw",
+                "type_aware", "fold_case_values", "format", "as_patch", "context", "show_unchanged",
+                "sort", "filter",
+            ]
+        )]
+        unified: Option<usize>,
+
+        /// Key order: byte order, or case-insensitive keeping the line order
+        /// of keys that tie
+        #[arg(
+            long,
+            value_enum,
+            value_name = "ORDER",
+            default_value_t = SortOrder::Key,
+            conflicts_with = "as_patch"
+        )]
+        sort: SortOrder,
+
+        /// Only include keys selected by EXPR, e.g.
+        /// 'startswith("DB_") and not endswith("_SECRET")'
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<KeyFilter>,
     },
 
     /// Normalize and format a .env file
     ///
-    /// Applies consistent formatting: trims whitespace, uppercases keys,
+    /// Applies consistent formatting: trims whitespace, normalizes key case,
     /// normalizes to KEY=VALUE format, and sorts alphabetically.
     /// Comments are preserved.
     Format {
@@ -76,7 +420,331 @@ pub enum Commands {
         /// Modify the file in place instead of printing to stdout
         #[arg(long, default_value_t = false)]
         in_place: bool,
+
+        /// Exit with an error if the file is not already formatted
+        #[arg(long, default_value_t = false, conflicts_with = "in_place")]
+        check: bool,
+
+        /// With --check, compare only KEY=VALUE lines and their order,
+        /// ignoring comments and blank lines
+        #[arg(long, default_value_t = false, requires = "check")]
+        ignore_comments: bool,
+
+        /// Copy the original file to a backup before modifying it in place
+        #[arg(long, default_value_t = false, requires = "in_place")]
+        backup: bool,
+
+        /// Suffix appended to the file name for the backup copy
+        #[arg(
+            long,
+            value_name = "SUFFIX",
+            default_value = ".bak",
+            requires = "backup"
+        )]
+        backup_suffix: String,
+
+        /// Align inline comments to a common column
+        #[arg(long, default_value_t = false)]
+        align_comments: bool,
+
+        /// Sort the elements of comma-separated values of keys declared as
+        /// `list` in --schema
+        #[arg(long, default_value_t = false)]
+        sort_values: bool,
+
+        /// Separate groups of keys sharing a prefix (up to the first `_`)
+        /// with a blank line
+        #[arg(long, default_value_t = false)]
+        group_by_prefix: bool,
+
+        /// Keep a single blank line wherever one separated entries
+        #[arg(long, default_value_t = false)]
+        preserve_blank_lines: bool,
+
+        /// Blank lines between consecutive entries: 0 (dense) or 1
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 0,
+            value_parser = clap::value_parser!(u8).range(0..=1)
+        )]
+        blank_lines: u8,
+
+        /// Remove blank lines at the end of the file
+        #[arg(long, default_value_t = false)]
+        no_trailing_blank: bool,
+
+        /// Case of unquoted keys (quoted keys always keep theirs)
+        #[arg(long, value_enum, value_name = "CASE", default_value_t = KeyCase::Upper)]
+        normalize_keys: KeyCase,
+
+        /// Keep only the first or last definition of a repeated key, or fail
+        /// (by default every definition is kept)
+        #[arg(long, value_enum, value_name = "POLICY")]
+        on_duplicate: Option<OnDuplicate>,
+
+        /// Key order: byte order, or case-insensitive keeping the line order
+        /// of keys that tie
+        #[arg(long, value_enum, value_name = "ORDER", default_value_t = SortOrder::Key)]
+        sort: SortOrder,
+
+        /// Keep each entry's `export` prefix as written, add it to every
+        /// entry, or strip it from every entry
+        #[arg(long, value_enum, value_name = "MODE", default_value_t = ExportStyle::Preserve)]
+        export: ExportStyle,
+
+        /// YAML schema; string-typed values are never normalized and only
+        /// list-typed values are sorted
+        #[arg(long, value_name = "SCHEMA")]
+        schema: Option<PathBuf>,
+    },
+
+    /// Print a stable hash of a .env file's keys and values
+    ///
+    /// Comments, blank lines, quoting, whitespace, and key order do not
+    /// affect the hash, so semantically equal files hash identically.
+    Hash {
+        /// Path to the .env file to hash
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+
+    /// Create a starter schema.yml and .env.example
+    ///
+    /// Existing files are never overwritten unless --force is given.
+    /// With --schema, nothing is written; an env file template for that
+    /// schema is printed instead.
+    Init {
+        /// Directory to create the files in
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+
+        /// Overwrite existing files
+        #[arg(long, default_value_t = false, conflicts_with = "schema")]
+        force: bool,
+
+        /// Print a `KEY=` line for each key of this schema, filled with its
+        /// default, instead of creating files
+        #[arg(long, value_name = "SCHEMA", conflicts_with = "dir")]
+        schema: Option<PathBuf>,
+
+        /// Print only the keys that have a default
+        #[arg(long, default_value_t = false, requires = "schema")]
+        defaults_only: bool,
+    },
+
+    /// List the key names of a .env file
+    ///
+    /// Keys are printed sorted and deduplicated, one per line.
+    /// Values are never shown.
+    Keys {
+        /// Path to the .env file to inspect
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Only include keys selected by EXPR, e.g.
+        /// 'startswith("DB_") and not endswith("_SECRET")'
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<KeyFilter>,
+    },
+
+    /// Report style problems in a .env file
+    ///
+    /// Rules inspect the raw file content, including problems that
+    /// formatting would silently fix.
+    Lint {
+        /// Path to the .env file to lint
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Report KEY=VALUE lines longer than N characters
+        #[arg(long, value_name = "N")]
+        max_line_length: Option<usize>,
+
+        /// Parse the file and report every line that fails to parse
+        #[arg(long, default_value_t = false)]
+        tolerant: bool,
+
+        #[command(flatten)]
+        severity: SeverityArgs,
+    },
+
+    /// Rename a key in a .env file
+    ///
+    /// The key keeps its value, position, and preceding comments.
+    /// All other lines are left untouched.
+    Rename {
+        /// Path to the .env file to modify
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Current key name
+        #[arg(value_name = "OLD_NAME")]
+        old_name: String,
+
+        /// New key name
+        #[arg(value_name = "NEW_NAME")]
+        new_name: String,
+
+        /// Overwrite NEW_NAME if it already exists
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Print the lines that would change instead of writing the file
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
     },
+
+    /// Print the effective config of layered .env files
+    ///
+    /// Merges .env, .env.local, .env.<MODE>, and .env.<MODE>.local from DIR,
+    /// each overriding the ones before it, like dotenv-flow.
+    Resolve {
+        /// Directory holding the .env files
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+
+        /// Mode whose .env.<MODE> files are layered on top, e.g. production
+        #[arg(long, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        mode: Option<String>,
+    },
+
+    /// Set a key's value in a .env file
+    ///
+    /// Existing definitions are updated in place; a new key is appended.
+    /// All other lines are left untouched.
+    Set {
+        /// Path to the .env file to modify
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Key to set
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// New value, or `-` to read it from stdin
+        #[arg(value_name = "VALUE", allow_hyphen_values = true)]
+        value: String,
+
+        /// Print the lines that would change instead of writing the file
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+
+    /// Print summary statistics for a .env file
+    ///
+    /// Reports the number of keys, comments, blank lines,
+    /// duplicate keys, and empty values.
+    Stats {
+        /// Path to the .env file to inspect
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Remove a key from a .env file
+    ///
+    /// Every definition of the key is removed.
+    /// All other lines are left untouched.
+    Unset {
+        /// Path to the .env file to modify
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Key to remove
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Also remove the comment lines directly above each definition
+        #[arg(long, default_value_t = false)]
+        with_comments: bool,
+
+        /// Fail if the key is not defined instead of printing a notice
+        #[arg(long, default_value_t = false)]
+        strict: bool,
+
+        /// Print the lines that would change instead of writing the file
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+
+    /// Cache a schema locally under a name for use as `check @NAME`
+    ///
+    /// The schema is validated and copied to .envcraft/schemas/NAME.yml in
+    /// the nearest directory that has a .envcraft directory, or the current
+    /// one. Caching the same name again replaces the copy.
+    UseSchema {
+        /// Name to cache the schema under
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Path to the YAML schema file to cache
+        #[arg(value_name = "SCHEMA")]
+        schema: PathBuf,
+    },
+}
+
+/// Options adjusting how severely each diagnostic rule is reported.
+#[derive(Args, Debug, Clone, Default)]
+pub struct SeverityArgs {
+    /// Set a rule's level: allow, warning, or error (e.g. extra-key=error; repeatable)
+    #[arg(
+        short = 'W',
+        long = "severity",
+        value_name = "RULE=LEVEL",
+        value_parser = rules::parse_override
+    )]
+    pub overrides: Vec<(Rule, Severity)>,
+
+    /// Report all warnings as errors (per-rule levels still apply)
+    #[arg(long, default_value_t = false)]
+    pub treat_warnings_as_errors: bool,
+}
+
+impl SeverityArgs {
+    /// Get the resulting severity of each rule.
+    pub fn severities(&self) -> rules::Severities {
+        rules::Severities::new(&self.overrides, self.treat_warnings_as_errors)
+    }
+}
+
+/// Output format for commands that support machine-readable output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON document
+    Json,
+}
+
+/// Target format for the convert command.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertFormat {
+    /// JSON object (input is a .env file)
+    Json,
+    /// .env file (input is a JSON object)
+    Env,
+    /// Lines for `docker run --env-file` (input is a .env file)
+    DockerEnv,
+    /// JSON Schema document (input is an envcraft schema)
+    JsonSchema,
+}
+
+/// Output format for the diff command.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffFormat {
+    /// Human-readable text with a summary line
+    #[default]
+    Text,
+    /// One JSON object per line for each difference
+    Jsonl,
 }
 
 #[cfg(test)]
@@ -0,0 +1,329 @@
+//! Default option values from a `.envcraft.toml` file.
+//!
+//! The file is looked up in the current directory and then its parents.
+//! Each table names a command and maps long option names to values:
+//!
+//! ```toml
+//! [diff]
+//! redact = true
+//! format = "jsonl"
+//! ```
+//!
+//! The values become the defaults of those options, so explicit
+//! command-line arguments take precedence over the file, which takes
+//! precedence over the built-in defaults. A flag enabled in the file is
+//! switched off with `--flag=false`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::builder::Resettable;
+use clap::{ArgAction, Command};
+use thiserror::Error;
+
+use crate::paths;
+
+/// File name of the config file.
+pub const CONFIG_FILE: &str = ".envcraft.toml";
+
+/// Errors that can occur while loading or applying a config file.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read {}: {source}", paths::display(path))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid config file {}: {source}", paths::display(path))]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("{}: unknown command '{command}'", paths::display(path))]
+    UnknownCommand { path: PathBuf, command: String },
+
+    #[error(
+        "{}: unknown option '{option}' for command '{command}'",
+        paths::display(path)
+    )]
+    UnknownOption {
+        path: PathBuf,
+        command: String,
+        option: String,
+    },
+
+    #[error(
+        "{}: invalid value for option '{option}' of command '{command}'{reason}",
+        paths::display(path)
+    )]
+    InvalidValue {
+        path: PathBuf,
+        command: String,
+        option: String,
+        reason: String,
+    },
+}
+
+/// Default option values for each command, read from a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Where the config was read from, for error messages
+    path: PathBuf,
+    /// Option values keyed by command name, then by long option name
+    commands: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+/// Find the config file in `start` or the nearest parent directory.
+pub fn find(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+impl Config {
+    /// Parse config file content; `path` is only used in error messages.
+    pub fn from_str(content: &str, path: &Path) -> Result<Self, ConfigError> {
+        let commands = toml::from_str(content).map_err(|source| ConfigError::Toml {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(Config {
+            path: path.to_path_buf(),
+            commands,
+        })
+    }
+
+    /// Load a config file from disk.
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::IoError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_str(&content, path)
+    }
+
+    /// Set the configured values as defaults of the matching options.
+    ///
+    /// Every command and option must exist and every value must be accepted
+    /// by the option, so mistakes in the file are reported up front.
+    pub fn apply(&self, mut cli: Command) -> Result<Command, ConfigError> {
+        for (command, options) in &self.commands {
+            let Some(subcommand) = cli.find_subcommand(command).cloned() else {
+                return Err(ConfigError::UnknownCommand {
+                    path: self.path.clone(),
+                    command: command.clone(),
+                });
+            };
+
+            for (option, value) in options {
+                let invalid = |reason: String| ConfigError::InvalidValue {
+                    path: self.path.clone(),
+                    command: command.clone(),
+                    option: option.clone(),
+                    reason,
+                };
+                let arg = subcommand
+                    .get_arguments()
+                    .find(|arg| arg.get_long() == Some(option.as_str()))
+                    .ok_or_else(|| ConfigError::UnknownOption {
+                        path: self.path.clone(),
+                        command: command.clone(),
+                        option: option.clone(),
+                    })?;
+
+                let values = match (arg.get_action(), value) {
+                    (ArgAction::SetTrue, toml::Value::Boolean(flag)) => vec![flag.to_string()],
+                    (ArgAction::SetTrue, _) => {
+                        return Err(invalid(" (expected true or false)".into()));
+                    }
+                    (ArgAction::Count, toml::Value::Integer(count))
+                        if u8::try_from(*count).is_ok() =>
+                    {
+                        vec![count.to_string()]
+                    }
+                    (ArgAction::Count, _) => {
+                        return Err(invalid(" (expected a count from 0 to 255)".into()));
+                    }
+                    _ => scalar_values(value).ok_or_else(|| {
+                        invalid(" (expected a string, number, boolean, or list of them)".into())
+                    })?,
+                };
+
+                // Run the values through the option's own parser so a bad
+                // value is reported here rather than when the default is used
+                if arg.get_action().takes_values() {
+                    let probe = values.iter().map(|text| format!("--{option}={text}"));
+                    subcommand
+                        .clone()
+                        .no_binary_name(true)
+                        .mut_args(|arg| {
+                            arg.required(false)
+                                .required_unless_present(Resettable::Reset)
+                        })
+                        .try_get_matches_from(probe)
+                        .map_err(|e| invalid(format!(" ({})", clap_reason(&e))))?;
+                }
+
+                // A flag only sets `true`, so a configured flag also takes an
+                // optional value for `--flag=false` to switch it back off
+                let flag = matches!(arg.get_action(), ArgAction::SetTrue);
+                let id = arg.get_id().clone();
+                cli = cli.mut_subcommand(command, |sub| {
+                    sub.mut_arg(id, |arg| {
+                        let arg = arg.default_values(values);
+                        if flag {
+                            arg.action(ArgAction::Set)
+                                .num_args(0..=1)
+                                .require_equals(true)
+                                .default_missing_value("true")
+                        } else {
+                            arg
+                        }
+                    })
+                });
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+/// Get the first line of a clap error without its `error: ` prefix.
+fn clap_reason(error: &clap::Error) -> String {
+    let rendered = error.render().to_string();
+    let line = rendered.lines().next().unwrap_or_default();
+    line.trim_start_matches("error: ").to_string()
+}
+
+/// Render a config value as option values: scalars give one value, arrays
+/// one per element. Tables and nested arrays are not option values.
+fn scalar_values(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(text) => Some(vec![text.clone()]),
+        toml::Value::Integer(number) => Some(vec![number.to_string()]),
+        toml::Value::Float(number) => Some(vec![number.to_string()]),
+        toml::Value::Boolean(flag) => Some(vec![flag.to_string()]),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                toml::Value::Array(_) => None,
+                item => scalar_values(item)?.pop(),
+            })
+            .collect(),
+        toml::Value::Table(_) | toml::Value::Datetime(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+    use crate::cli::{Cli, Commands, DiffFormat};
+
+    /// Parse arguments with the given config applied.
+    fn parse(config: &str, args: &[&str]) -> Result<Cli, ConfigError> {
+        let config = Config::from_str(config, Path::new(CONFIG_FILE))?;
+        let matches = config.apply(Cli::command())?.get_matches_from(args);
+        Ok(Cli::from_arg_matches(&matches).unwrap())
+    }
+
+    #[test]
+    fn test_config_sets_defaults() {
+        let cli = parse(
+            "[diff]\nredact = true\nformat = \"jsonl\"\n",
+            &["envcraft", "diff", "a.env", "b.env"],
+        )
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Diff {
+                redact: true,
+                format: DiffFormat::Jsonl,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let cli = parse(
+            "[diff]\nformat = \"jsonl\"\n",
+            &["envcraft", "diff", "a.env", "b.env", "--format", "text"],
+        )
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Diff {
+                format: DiffFormat::Text,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_switches_off_configured_flag() {
+        let config = "[diff]\nredact = true\n";
+
+        let cli = parse(
+            config,
+            &["envcraft", "diff", "a.env", "b.env", "--redact=false"],
+        )
+        .unwrap();
+        assert!(matches!(cli.command, Commands::Diff { redact: false, .. }));
+
+        // A bare flag still turns it on and is not followed by a value
+        let cli = parse(config, &["envcraft", "diff", "--redact", "a.env", "b.env"]).unwrap();
+        let Commands::Diff { redact, file2, .. } = cli.command else {
+            panic!("expected diff command");
+        };
+        assert!(redact);
+        assert_eq!(file2, Some(PathBuf::from("b.env")));
+    }
+
+    #[test]
+    fn test_config_list_values() {
+        let cli = parse(
+            "[check]\nmerge-schema = [\"a.yml\", \"b.yml\"]\n",
+            &["envcraft", "check", "schema.yml", ".env"],
+        )
+        .unwrap();
+
+        let Commands::Check { merge_schema, .. } = cli.command else {
+            panic!("expected check command");
+        };
+        assert_eq!(
+            merge_schema,
+            vec![PathBuf::from("a.yml"), PathBuf::from("b.yml")]
+        );
+    }
+
+    #[test]
+    fn test_config_rejects_mistakes() {
+        let args = ["envcraft", "diff", "a.env", "b.env"];
+
+        assert!(matches!(
+            parse("[difff]\nraw = true\n", &args),
+            Err(ConfigError::UnknownCommand { command, .. }) if command == "difff"
+        ));
+        assert!(matches!(
+            parse("[diff]\nredcat = true\n", &args),
+            Err(ConfigError::UnknownOption { option, .. }) if option == "redcat"
+        ));
+        assert!(matches!(
+            parse("[diff]\nformat = \"xml\"\n", &args),
+            Err(ConfigError::InvalidValue { option, .. }) if option == "format"
+        ));
+        assert!(matches!(
+            parse("diff = 1\n", &args),
+            Err(ConfigError::Toml { .. })
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! Cross-file consistency checks for shared keys.
+//!
+//! Some keys, like a cluster ID, must hold the same value in every service's
+//! env file. Each listed key is compared across all files; a key that is
+//! missing from a file counts as a divergence.
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::parser::{EnvFile, ParseError};
+use crate::paths;
+
+/// Errors that can occur during check-consistency operation.
+#[derive(Error, Debug)]
+pub enum ConsistencyError {
+    #[error("failed to parse {}: {source}", paths::display(path))]
+    ParseError { path: PathBuf, source: ParseError },
+}
+
+/// The values a key has across files, when they don't all match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Key that differs
+    pub key: String,
+    /// Value in each file, in the order the files were given (`None` if missing)
+    pub values: Vec<Option<String>>,
+}
+
+/// Compare each key across the env files and collect the ones that differ.
+pub fn find_divergences(envs: &[EnvFile], keys: &[String]) -> Vec<Divergence> {
+    keys.iter()
+        .filter_map(|key| {
+            let values: Vec<Option<String>> =
+                envs.iter().map(|env| env.get(key).cloned()).collect();
+            let consistent = values[0].is_some() && values.iter().all(|value| *value == values[0]);
+
+            (!consistent).then(|| Divergence {
+                key: key.clone(),
+                values,
+            })
+        })
+        .collect()
+}
+
+/// Run the check-consistency command.
+pub fn run_check_consistency(files: &[PathBuf], keys: &[String]) -> Result<bool, ConsistencyError> {
+    let envs = files
+        .iter()
+        .map(|path| load(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let divergences = find_divergences(&envs, keys);
+
+    for divergence in &divergences {
+        println!("error: {} differs across files:", divergence.key);
+        for (path, value) in files.iter().zip(&divergence.values) {
+            match value {
+                Some(value) => println!("  {}: {value}", paths::display(path)),
+                None => println!("  {}: (missing)", paths::display(path)),
+            }
+        }
+    }
+
+    if divergences.is_empty() {
+        println!(
+            "✓ {} key(s) consistent across {} files",
+            keys.len(),
+            files.len()
+        );
+    } else {
+        println!(
+            "✗ {} of {} key(s) inconsistent",
+            divergences.len(),
+            keys.len()
+        );
+    }
+
+    Ok(divergences.is_empty())
+}
+
+/// Parse an env file, naming it in any error.
+fn load(path: &Path) -> Result<EnvFile, ConsistencyError> {
+    EnvFile::from_path(path).map_err(|source| ConsistencyError::ParseError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envs(contents: &[&str]) -> Vec<EnvFile> {
+        contents
+            .iter()
+            .map(|content| EnvFile::from_str(content).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_values() {
+        let envs = envs(&["CLUSTER_ID=prod\nPORT=1", "CLUSTER_ID=prod\nPORT=2"]);
+
+        assert!(find_divergences(&envs, &["CLUSTER_ID".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_divergent_and_missing_values() {
+        let envs = envs(&["CLUSTER_ID=prod\nREGION=eu", "CLUSTER_ID=staging"]);
+        let keys = vec!["CLUSTER_ID".to_string(), "REGION".to_string()];
+        let divergences = find_divergences(&envs, &keys);
+
+        assert_eq!(
+            divergences,
+            vec![
+                Divergence {
+                    key: "CLUSTER_ID".to_string(),
+                    values: vec![Some("prod".to_string()), Some("staging".to_string())],
+                },
+                Divergence {
+                    key: "REGION".to_string(),
+                    values: vec![Some("eu".to_string()), None],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_everywhere_is_a_divergence() {
+        let envs = envs(&["A=1", "A=1"]);
+
+        assert_eq!(find_divergences(&envs, &["B".to_string()]).len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Conversion between .env files and JSON.
+//!
+//! Env files become a JSON object of string values; JSON objects become
+//! `KEY=VALUE` lines. With a nesting separator, dotted keys map to nested
+//! JSON objects and back. Env files can also be exported in the literal
+//! format of `docker run --env-file`, and schemas as JSON Schema.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::cli::ConvertFormat;
+use crate::filter::KeyFilter;
+use crate::format::render_value;
+use crate::parser::{render_key, Dialect, EnvFile, ParseError, ParseOptions};
+use crate::schema::{Schema, SchemaError};
+use crate::schema_export;
+
+/// Errors that can occur during convert operation.
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("failed to read file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("JSON root must be an object")]
+    RootNotObject,
+
+    #[error("key '{0}' is both a value and a nested group")]
+    Conflict(String),
+
+    #[error("key '{key}' has unsupported JSON value ({kind}){hint}")]
+    Unsupported {
+        key: String,
+        kind: &'static str,
+        hint: &'static str,
+    },
+}
+
+/// Convert an env file into a JSON object.
+///
+/// With `nest_on`, keys are split on the separator and the parts become
+/// nested objects, e.g. `DB.HOST` becomes `{"DB": {"HOST": ...}}`.
+pub fn to_json(env: &EnvFile, nest_on: Option<&str>) -> Result<Value, ConvertError> {
+    let mut root = Map::new();
+
+    for (key, value) in &env.entries {
+        let Some(separator) = nest_on else {
+            root.insert(key.clone(), Value::String(value.clone()));
+            continue;
+        };
+
+        let parts: Vec<&str> = key.split(separator).collect();
+        let (last, groups) = parts.split_last().expect("split yields at least one part");
+        let mut object = &mut root;
+        for (depth, group) in groups.iter().enumerate() {
+            let entry = object
+                .entry(group.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            object = entry
+                .as_object_mut()
+                .ok_or_else(|| ConvertError::Conflict(parts[..=depth].join(separator)))?;
+        }
+        if object.contains_key(*last) {
+            return Err(ConvertError::Conflict(key.clone()));
+        }
+        object.insert(last.to_string(), Value::String(value.clone()));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Convert a JSON object into env key-value pairs.
+///
+/// Strings, numbers, and bools become values. Nested objects are flattened
+/// by joining keys with `nest_on`, and are rejected without it.
+pub fn from_json(
+    json: &Value,
+    nest_on: Option<&str>,
+) -> Result<BTreeMap<String, String>, ConvertError> {
+    let root = json.as_object().ok_or(ConvertError::RootNotObject)?;
+    let mut entries = BTreeMap::new();
+    flatten(root, "", nest_on, &mut entries)?;
+    Ok(entries)
+}
+
+/// Add the values of a JSON object to `entries`, prefixing keys with `prefix`.
+fn flatten(
+    object: &Map<String, Value>,
+    prefix: &str,
+    nest_on: Option<&str>,
+    entries: &mut BTreeMap<String, String>,
+) -> Result<(), ConvertError> {
+    for (name, value) in object {
+        let key = format!("{prefix}{name}");
+        let unsupported = |kind, hint| ConvertError::Unsupported {
+            key: key.clone(),
+            kind,
+            hint,
+        };
+
+        let text = match value {
+            Value::String(text) => text.clone(),
+            Value::Number(number) => number.to_string(),
+            Value::Bool(flag) => flag.to_string(),
+            Value::Object(nested) => match nest_on {
+                Some(separator) => {
+                    flatten(nested, &format!("{key}{separator}"), nest_on, entries)?;
+                    continue;
+                }
+                None => return Err(unsupported("object", " (use --nest-on to flatten)")),
+            },
+            Value::Array(_) => return Err(unsupported("array", "")),
+            Value::Null => return Err(unsupported("null", "")),
+        };
+
+        if entries.insert(key.clone(), text).is_some() {
+            return Err(ConvertError::Conflict(key));
+        }
+    }
+
+    Ok(())
+}
+
+/// Render key-value pairs as env file lines, quoting values that need it.
+pub fn render_env(entries: &BTreeMap<String, String>) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", render_key(key), render_value(value)))
+        .collect()
+}
+
+/// Render env values as `docker run --env-file` lines, along with a warning
+/// for each entry that format cannot represent.
+///
+/// Docker takes everything after the first `=` literally, with no quote
+/// removal, escapes, or interpolation, so values are written unquoted and
+/// the container sees exactly the parsed value. Values with line breaks and
+/// keys containing `=` or whitespace cannot be written and are left out.
+pub fn render_docker_env(entries: &BTreeMap<String, String>) -> (String, Vec<String>) {
+    let mut output = String::new();
+    let mut warnings = Vec::new();
+
+    for (key, value) in entries {
+        if key.contains('=') || key.contains(char::is_whitespace) {
+            warnings.push(format!(
+                "skipping {key}: docker env files cannot hold keys with '=' or whitespace"
+            ));
+        } else if value.contains(['\n', '\r']) {
+            warnings.push(format!(
+                "skipping {key}: docker env files cannot hold multiline values"
+            ));
+        } else {
+            output.push_str(&format!("{key}={value}\n"));
+        }
+    }
+
+    (output, warnings)
+}
+
+/// Render a JSON value on one line, or indented with `pretty`.
+///
+/// Object keys always come out sorted, so output is stable across runs.
+pub fn render_json(json: &Value, pretty: bool) -> Result<String, serde_json::Error> {
+    if pretty {
+        serde_json::to_string_pretty(json)
+    } else {
+        serde_json::to_string(json)
+    }
+}
+
+/// Run the convert command.
+///
+/// .env input is parsed with `dialect`. With `filter`, only the keys it
+/// selects are converted; with `--to env`, keys are matched after nested
+/// objects are flattened, and with `--to json-schema`, declared keys and
+/// key patterns are matched.
+pub fn run_convert(
+    path: &Path,
+    to: ConvertFormat,
+    nest_on: Option<&str>,
+    pretty: bool,
+    dialect: Dialect,
+    filter: Option<&KeyFilter>,
+) -> Result<bool, ConvertError> {
+    let selected = |key: &String| filter.is_none_or(|filter| filter.matches(key));
+    let parse = ParseOptions {
+        dialect,
+        ..Default::default()
+    };
+
+    match to {
+        ConvertFormat::Json => {
+            let mut env = EnvFile::from_path_with(path, &parse)?;
+            env.entries.retain(|key, _| selected(key));
+            let json = to_json(&env, nest_on)?;
+            println!("{}", render_json(&json, pretty)?);
+        }
+        ConvertFormat::Env => {
+            let content = fs::read_to_string(path)?;
+            let json: Value = serde_json::from_str(&content)?;
+            let mut entries = from_json(&json, nest_on)?;
+            entries.retain(|key, _| selected(key));
+            print!("{}", render_env(&entries));
+        }
+        ConvertFormat::DockerEnv => {
+            let mut env = EnvFile::from_path_with(path, &parse)?;
+            env.entries.retain(|key, _| selected(key));
+            let (output, warnings) = render_docker_env(&env.entries);
+            for warning in warnings {
+                eprintln!("warning: {warning}");
+            }
+            print!("{output}");
+        }
+        ConvertFormat::JsonSchema => {
+            let mut schema = Schema::from_path(path)?;
+            schema.fields.retain(|key, _| selected(key));
+            schema.patterns.retain(|pattern, _| selected(pattern));
+            for group in &mut schema.groups {
+                group.keys.retain(|key| selected(key));
+            }
+            let document = schema_export::to_json_schema(&schema);
+            println!("{}", render_json(&document, pretty)?);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json_flat() {
+        let env = EnvFile::from_str("DB.HOST=localhost\nPORT=8080").unwrap();
+        let json = to_json(&env, None).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({"DB.HOST": "localhost", "PORT": "8080"})
+        );
+    }
+
+    #[test]
+    fn test_to_json_nested() {
+        let env = EnvFile::from_str("DB.HOST=localhost\nDB.PORT=5432\nNAME=app").unwrap();
+        let json = to_json(&env, Some(".")).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({"DB": {"HOST": "localhost", "PORT": "5432"}, "NAME": "app"})
+        );
+    }
+
+    #[test]
+    fn test_to_json_nested_conflict() {
+        let env = EnvFile::from_str("DB=1\nDB.HOST=localhost").unwrap();
+        let result = to_json(&env, Some("."));
+
+        assert!(matches!(result, Err(ConvertError::Conflict(key)) if key == "DB"));
+    }
+
+    #[test]
+    fn test_nested_round_trip() {
+        let content = "DB.HOST=localhost\nDB.POOL.SIZE=5\nNAME=app\n";
+        let env = EnvFile::from_str(content).unwrap();
+        let json = to_json(&env, Some(".")).unwrap();
+        let entries = from_json(&json, Some(".")).unwrap();
+
+        assert_eq!(entries, env.entries);
+        assert_eq!(render_env(&entries), content);
+    }
+
+    #[test]
+    fn test_render_json_compact_and_pretty() {
+        let env = EnvFile::from_str("ZONE=a\nDB.PORT=5432\nAPP=web\nDB.HOST=db").unwrap();
+        let json = to_json(&env, Some(".")).unwrap();
+
+        assert_eq!(
+            render_json(&json, false).unwrap(),
+            r#"{"APP":"web","DB":{"HOST":"db","PORT":"5432"},"ZONE":"a"}"#
+        );
+        assert_eq!(
+            render_json(&json, true).unwrap(),
+            "{\n  \"APP\": \"web\",\n  \"DB\": {\n    \"HOST\": \"db\",\n    \"PORT\": \"5432\"\n  },\n  \"ZONE\": \"a\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_from_json_nested_requires_separator() {
+        let json = serde_json::json!({"DB": {"HOST": "localhost"}});
+        let result = from_json(&json, None);
+
+        assert!(matches!(
+            result,
+            Err(ConvertError::Unsupported { key, kind: "object", .. }) if key == "DB"
+        ));
+    }
+
+    #[test]
+    fn test_render_docker_env_writes_values_literally() {
+        let env = EnvFile::from_str("PLAIN=value\nQUOTED=\"a b\"\nPADDED=' x '\nEMPTY=\n").unwrap();
+        let (output, warnings) = render_docker_env(&env.entries);
+
+        assert_eq!(output, "EMPTY=\nPADDED= x \nPLAIN=value\nQUOTED=a b\n");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_render_docker_env_warns_on_multiline() {
+        let entries = BTreeMap::from([
+            ("CERT".to_string(), "line one\nline two".to_string()),
+            ("PORT".to_string(), "8080".to_string()),
+            ("WEIRD=KEY".to_string(), "x".to_string()),
+        ]);
+        let (output, warnings) = render_docker_env(&entries);
+
+        assert_eq!(output, "PORT=8080\n");
+        assert_eq!(
+            warnings,
+            vec![
+                "skipping CERT: docker env files cannot hold multiline values",
+                "skipping WEIRD=KEY: docker env files cannot hold keys with '=' or whitespace",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_env_quotes_when_needed() {
+        let entries = BTreeMap::from([
+            ("PADDED".to_string(), " x ".to_string()),
+            ("PLAIN".to_string(), "a b".to_string()),
+        ]);
+
+        assert_eq!(render_env(&entries), "PADDED=\" x \"\nPLAIN=a b\n");
+    }
+}
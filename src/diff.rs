@@ -2,22 +2,204 @@
 //!
 //! Provides deterministic comparison showing added, removed, and changed keys.
 
-use std::collections::BTreeSet;
-use std::path::Path;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
-use crate::parser::{EnvFile, ParseError};
+use crate::cli::DiffFormat;
+use crate::consistency;
+use crate::filter::{self, KeyFilter};
+use crate::format::{self, FormatOptions, SortOrder};
+use crate::git::{self, GitError};
+use crate::parser::{self, EnvFile, EnvLine, ParseError, ParseOptions};
+use crate::paths;
+use crate::remote::{self, RemoteError};
+use crate::schema::{Schema, SchemaError};
+use crate::unified;
 
 /// Errors that can occur during diff operation.
 #[derive(Error, Debug)]
 pub enum DiffError {
     #[error("failed to parse env file: {0}")]
     ParseError(#[from] ParseError),
+
+    #[error("{0}")]
+    Git(#[from] GitError),
+
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+
+    #[error("failed to serialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Remote(#[from] RemoteError),
+
+    #[error("failed to write report {}: {source}", paths::display(path))]
+    ReportFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Options controlling how files are loaded, compared, and printed.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// How values are hidden in output
+    pub redact: Redaction,
+    /// Glob patterns of keys to redact; when given, other keys are shown
+    pub redact_keys: Vec<String>,
+    /// Compare values as written, including quotes
+    pub raw: bool,
+    /// Allow `<rev>:<path>` arguments that are read from git
+    pub git: bool,
+    /// Schema describing key types
+    pub schema: Option<PathBuf>,
+    /// Compare values of schema-typed keys by their typed interpretation
+    pub type_aware: bool,
+    /// Compare values of bool-typed keys case-insensitively
+    pub fold_case_values: bool,
+    /// How differences are printed
+    pub format: DiffFormat,
+    /// Print `envcraft set`/`unset` commands instead of differences
+    pub as_patch: bool,
+    /// Number of unchanged neighboring keys shown around each difference
+    pub context: usize,
+    /// List unchanged keys alongside the differences
+    pub show_unchanged: bool,
+    /// Print a unified diff of the formatted files with this many lines of
+    /// context instead of differences by key
+    pub unified: Option<usize>,
+    /// How keys are ordered in output
+    pub sort: SortOrder,
+    /// Only compare the keys this filter selects
+    pub filter: Option<KeyFilter>,
+    /// When comparing against the environment, ignore variables the file
+    /// does not define
+    pub file_keys_only: bool,
+    /// Also write a JSON report of the differences to this path
+    pub report_file: Option<PathBuf>,
+}
+
+impl DiffOptions {
+    /// Get how the value of a key is shown.
+    ///
+    /// With `redact_keys`, only keys matching a pattern are redacted:
+    /// fingerprinted or masked when `redact` says so, hidden otherwise.
+    pub fn redaction(&self, key: &str) -> Redaction {
+        if self.redact_keys.is_empty() {
+            return self.redact;
+        }
+        let sensitive = self
+            .redact_keys
+            .iter()
+            .any(|pattern| filter::glob_matches(pattern, key));
+        match (sensitive, self.redact) {
+            (false, _) => Redaction::Off,
+            (true, Redaction::Off) => Redaction::Hide,
+            (true, redact) => redact,
+        }
+    }
+}
+
+/// The JSON report written by `--report-file`.
+#[derive(Debug, Serialize)]
+struct DiffReport {
+    /// The first file, as displayed in output
+    file1: String,
+    /// The second file, or `None` when comparing against the environment
+    file2: Option<String>,
+    /// Number of differences
+    count: usize,
+    /// Each difference, shaped like a `--format jsonl` line
+    differences: Vec<serde_json::Value>,
+}
+
+/// How values are shown in diff output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Redaction {
+    /// Show values as they are
+    #[default]
+    Off,
+    /// Show only key names
+    Hide,
+    /// Show a short SHA-256 fingerprint in place of each value
+    Hash,
+    /// Show a run of `fill` characters in place of each value: one per
+    /// character of the value with `keep_length`, a fixed run otherwise
+    Mask { fill: char, keep_length: bool },
+}
+
+/// Number of mask characters shown for every value by `--mask-style fixed`.
+pub const FIXED_MASK_WIDTH: usize = 8;
+
+impl Redaction {
+    /// Get what is shown in place of a value, or `None` when the value is
+    /// shown as it is or not at all.
+    fn replacement(self, value: &str) -> Option<String> {
+        match self {
+            Redaction::Off | Redaction::Hide => None,
+            Redaction::Hash => Some(fingerprint(value)),
+            Redaction::Mask { fill, keep_length } => {
+                let width = if keep_length {
+                    value.chars().count()
+                } else {
+                    FIXED_MASK_WIDTH
+                };
+                Some(fill.to_string().repeat(width))
+            }
+        }
+    }
+}
+
+/// How `--redact` and `--redact-key` show the values they redact.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MaskStyle {
+    /// Leave values out, showing only key names
+    #[default]
+    Hide,
+    /// Show a fixed run of mask characters, hiding the value's length
+    Fixed,
+    /// Show one mask character per character of the value
+    Length,
+}
+
+impl MaskStyle {
+    /// Get the redaction for this style, masking with `fill`.
+    pub fn redaction(self, fill: char) -> Redaction {
+        match self {
+            MaskStyle::Hide => Redaction::Hide,
+            MaskStyle::Fixed => Redaction::Mask {
+                fill,
+                keep_length: false,
+            },
+            MaskStyle::Length => Redaction::Mask {
+                fill,
+                keep_length: true,
+            },
+        }
+    }
+}
+
+/// Compute a short, stable fingerprint of a value: the first 8 hex digits
+/// of its SHA-256 hash.
+pub fn fingerprint(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let hex: String = digest[..4]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256:{hex}")
 }
 
 /// A single difference entry.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
 pub enum DiffEntry {
     /// Key only exists in the second file
     Added { key: String, value: String },
@@ -41,12 +223,44 @@ impl DiffEntry {
         }
     }
 
+    /// Get a copy of this entry with each value replaced by `replace`.
+    fn with_values(&self, replace: impl Fn(&str) -> String) -> DiffEntry {
+        match self {
+            DiffEntry::Added { key, value } => DiffEntry::Added {
+                key: key.clone(),
+                value: replace(value),
+            },
+            DiffEntry::Removed { key, value } => DiffEntry::Removed {
+                key: key.clone(),
+                value: replace(value),
+            },
+            DiffEntry::Changed {
+                key,
+                old_value,
+                new_value,
+            } => DiffEntry::Changed {
+                key: key.clone(),
+                old_value: replace(old_value),
+                new_value: replace(new_value),
+            },
+        }
+    }
+
     /// Format this entry for display.
-    pub fn format(&self, redact: bool) -> String {
+    pub fn format(&self, redact: Redaction) -> String {
+        if matches!(redact, Redaction::Hash | Redaction::Mask { .. }) {
+            return self
+                .with_values(|value| redact.replacement(value).unwrap_or_default())
+                .format(Redaction::Off);
+        }
+
+        let redact = redact == Redaction::Hide;
         match self {
             DiffEntry::Added { key, value } => {
                 if redact {
                     format!("+ {key}")
+                } else if value.contains('\n') {
+                    multiline_block('+', key, value.lines().map(|line| ('+', line)))
                 } else {
                     format!("+ {key}={value}")
                 }
@@ -54,6 +268,8 @@ impl DiffEntry {
             DiffEntry::Removed { key, value } => {
                 if redact {
                     format!("- {key}")
+                } else if value.contains('\n') {
+                    multiline_block('-', key, value.lines().map(|line| ('-', line)))
                 } else {
                     format!("- {key}={value}")
                 }
@@ -65,12 +281,79 @@ impl DiffEntry {
             } => {
                 if redact {
                     format!("~ {key}")
+                } else if old_value.contains('\n') || new_value.contains('\n') {
+                    multiline_block('~', key, line_diff(old_value, new_value))
                 } else {
                     format!("~ {key}: {old_value} → {new_value}")
                 }
             }
         }
     }
+
+    /// Format this entry as a single-line JSON object.
+    ///
+    /// When values are hidden, only the change kind and key are included.
+    pub fn to_json_line(&self, redact: Redaction) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.to_json_value(redact)?)
+    }
+
+    /// Convert this entry into the JSON object printed by `to_json_line`.
+    fn to_json_value(&self, redact: Redaction) -> Result<serde_json::Value, serde_json::Error> {
+        let mut value = match redact {
+            Redaction::Hash | Redaction::Mask { .. } => serde_json::to_value(
+                self.with_values(|value| redact.replacement(value).unwrap_or_default()),
+            )?,
+            Redaction::Off | Redaction::Hide => serde_json::to_value(self)?,
+        };
+        if redact == Redaction::Hide {
+            if let Some(object) = value.as_object_mut() {
+                object.retain(|field, _| field == "change" || field == "key");
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Quote a word for a POSIX shell, leaving plain words unquoted.
+fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c));
+
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Build shell commands that turn the first file into the second.
+///
+/// Added and changed keys become `envcraft set`, removed keys become
+/// `envcraft unset`, all applied to `target`.
+pub fn patch(result: &DiffResult, target: &str) -> Vec<String> {
+    let target = shell_quote(target);
+
+    result
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            DiffEntry::Added { key, value }
+            | DiffEntry::Changed {
+                key,
+                new_value: value,
+                ..
+            } => format!(
+                "envcraft set {target} {} {}",
+                shell_quote(key),
+                shell_quote(value)
+            ),
+            DiffEntry::Removed { key, .. } => {
+                format!("envcraft unset {target} {}", shell_quote(key))
+            }
+        })
+        .collect()
 }
 
 /// Result of comparing two env files.
@@ -78,6 +361,10 @@ impl DiffEntry {
 pub struct DiffResult {
     /// All differences, sorted alphabetically by key
     pub entries: Vec<DiffEntry>,
+    /// Keys whose values match in both files, with the second file's value
+    pub unchanged: BTreeMap<String, String>,
+    /// Position of each key under `SortOrder::Stable`, once applied
+    ranks: Option<BTreeMap<String, usize>>,
 }
 
 impl DiffResult {
@@ -90,67 +377,560 @@ impl DiffResult {
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    /// Order keys case-insensitively; keys that tie keep the order they
+    /// first appear in, reading `file1` and then `file2` line by line.
+    pub fn sort_stable(&mut self, file1: &EnvFile, file2: &EnvFile) {
+        let mut keys: Vec<&str> = Vec::new();
+        let defined = [file1, file2].into_iter().flat_map(|file| {
+            file.lines
+                .iter()
+                .filter_map(|line| match line {
+                    EnvLine::KeyValue { key, .. } => Some(key),
+                    _ => None,
+                })
+                .chain(file.keys())
+        });
+        for key in defined {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        keys.sort_by_key(|key| SortOrder::Stable.sort_key(key));
+
+        let ranks: BTreeMap<String, usize> = keys
+            .into_iter()
+            .enumerate()
+            .map(|(rank, key)| (key.to_string(), rank))
+            .collect();
+        self.entries.sort_by_key(|entry| ranks[entry.key()]);
+        self.ranks = Some(ranks);
+    }
+
+    /// Get what a key sorts by in output: its rank once `sort_stable` has
+    /// been applied, and otherwise the key itself.
+    fn rank<'a>(&self, key: &'a str) -> (usize, &'a str) {
+        let rank = self.ranks.as_ref().map_or(0, |ranks| ranks[key]);
+        (rank, key)
+    }
 }
 
 /// Compare two env files and return their differences.
 pub fn diff(file1: &EnvFile, file2: &EnvFile) -> DiffResult {
+    diff_maps(&file1.entries, &file2.entries, |_, a, b| a == b)
+}
+
+/// Compare two env files using raw value text, so quoting differences count.
+pub fn diff_raw(file1: &EnvFile, file2: &EnvFile) -> DiffResult {
+    diff_maps(&file1.raw_entries(), &file2.raw_entries(), |_, a, b| a == b)
+}
+
+/// Compare two env files, interpreting values of schema-typed keys by type.
+///
+/// Keys not declared in the schema compare as plain strings.
+pub fn diff_typed(file1: &EnvFile, file2: &EnvFile, schema: &Schema) -> DiffResult {
+    diff_maps(&file1.entries, &file2.entries, |key, a, b| {
+        match schema.field(key) {
+            Some(field) => field.value_type.equivalent(a, b),
+            None => a == b,
+        }
+    })
+}
+
+/// Compare two env files, ignoring case in values of keys whose schema type
+/// folds case (bools). Every other key compares exactly.
+pub fn diff_folded(file1: &EnvFile, file2: &EnvFile, schema: &Schema) -> DiffResult {
+    diff_maps(&file1.entries, &file2.entries, |key, a, b| {
+        match schema.field(key) {
+            Some(field) if field.value_type.folds_case() => a.eq_ignore_ascii_case(b),
+            _ => a == b,
+        }
+    })
+}
+
+/// Compare two key-value maps and return their differences.
+///
+/// `equal` decides whether the values of a key present in both maps match.
+/// Both maps are walked once in key order, merge-join style, so entries come
+/// out sorted without collecting or sorting the keys first.
+fn diff_maps(
+    map1: &BTreeMap<String, String>,
+    map2: &BTreeMap<String, String>,
+    equal: impl Fn(&str, &str, &str) -> bool,
+) -> DiffResult {
     let mut entries = Vec::new();
+    let mut unchanged = BTreeMap::new();
+    let mut iter1 = map1.iter().peekable();
+    let mut iter2 = map2.iter().peekable();
 
-    // Collect all keys from both files
-    let keys1: BTreeSet<&String> = file1.keys().collect();
-    let keys2: BTreeSet<&String> = file2.keys().collect();
+    loop {
+        let order = match (iter1.peek(), iter2.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((key1, _)), Some((key2, _))) => key1.cmp(key2),
+        };
 
-    // Find removed keys (in file1 but not file2)
-    for key in keys1.difference(&keys2) {
-        let value = file1.get(key).unwrap().clone();
-        entries.push(DiffEntry::Removed {
-            key: (*key).clone(),
-            value,
-        });
+        match order {
+            // Only in file1: removed
+            Ordering::Less => {
+                let (key, value) = iter1.next().expect("peeked");
+                entries.push(DiffEntry::Removed {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+            // Only in file2: added
+            Ordering::Greater => {
+                let (key, value) = iter2.next().expect("peeked");
+                entries.push(DiffEntry::Added {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+            Ordering::Equal => {
+                let (key, value1) = iter1.next().expect("peeked");
+                let (_, value2) = iter2.next().expect("peeked");
+                if equal(key, value1, value2) {
+                    unchanged.insert(key.clone(), value2.clone());
+                } else {
+                    entries.push(DiffEntry::Changed {
+                        key: key.clone(),
+                        old_value: value1.clone(),
+                        new_value: value2.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    DiffResult {
+        entries,
+        unchanged,
+        ranks: None,
     }
+}
+
+/// Render a multiline value as a block: a `KEY:` header with the change
+/// marker, then one indented line per value line, each prefixed with `+`,
+/// `-`, or a space for lines both values share.
+fn multiline_block<'a>(
+    marker: char,
+    key: &str,
+    lines: impl IntoIterator<Item = (char, &'a str)>,
+) -> String {
+    let mut block = format!("{marker} {key}:");
+    for (line_marker, line) in lines {
+        block.push_str(&format!("\n  {line_marker} {line}"));
+    }
+    block
+}
+
+/// Compare two values line by line.
+///
+/// Returns every line of both values in order, marked `-` for lines only in
+/// `old`, `+` for lines only in `new`, and a space for lines in both, using
+/// a longest common subsequence so shared lines line up.
+fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
 
-    // Find added keys (in file2 but not file1)
-    for key in keys2.difference(&keys1) {
-        let value = file2.get(key).unwrap().clone();
-        entries.push(DiffEntry::Added {
-            key: (*key).clone(),
-            value,
+    // common[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Render the lines that differ between two texts as `- line` and
+/// `+ line`, in order. Lines both texts share are left out.
+pub fn line_changes(old: &str, new: &str) -> Vec<String> {
+    line_diff(old, new)
+        .into_iter()
+        .filter(|(marker, _)| *marker != ' ')
+        .map(|(marker, line)| format!("{marker} {line}"))
+        .collect()
+}
+
+/// A line of text diff output when context is shown.
+#[derive(Debug, PartialEq, Eq)]
+enum DiffLine<'a> {
+    /// A difference
+    Change(&'a DiffEntry),
+    /// An unchanged key shown for context
+    Context { key: &'a str, value: &'a str },
+    /// An unchanged key listed by `--show-unchanged`
+    Unchanged { key: &'a str, value: &'a str },
+    /// Unchanged keys left out between two shown lines
+    Gap,
+}
+
+impl<'a> DiffLine<'a> {
+    /// Get the key this line shows, if any.
+    fn key(&self) -> Option<&'a str> {
+        match self {
+            DiffLine::Change(entry) => Some(entry.key()),
+            DiffLine::Context { key, .. } | DiffLine::Unchanged { key, .. } => Some(key),
+            DiffLine::Gap => None,
+        }
+    }
+
+    /// Format this line for display; context lines are indented by two
+    /// spaces and unchanged lines are marked with `=`.
+    fn format(&self, redact: Redaction) -> String {
+        match self {
+            DiffLine::Change(entry) => entry.format(redact),
+            DiffLine::Unchanged { key, .. } if redact == Redaction::Hide => format!("= {key}"),
+            DiffLine::Unchanged { key, value } => match redact.replacement(value) {
+                Some(shown) => format!("= {key}={shown}"),
+                None => format!("= {key}={value}"),
+            },
+            DiffLine::Context { key, .. } if redact == Redaction::Hide => format!("  {key}"),
+            DiffLine::Context { key, value } => match redact.replacement(value) {
+                Some(shown) => format!("  {key}={shown}"),
+                None => format!("  {key}={value}"),
+            },
+            DiffLine::Gap => "  ...".to_string(),
+        }
+    }
+}
+
+/// Interleave differences with up to `context` unchanged keys on either side
+/// of each, in key order. With context, runs of omitted keys are marked
+/// with a gap.
+fn with_context(result: &DiffResult, context: usize) -> Vec<DiffLine<'_>> {
+    let mut keys: Vec<(&str, Option<&DiffEntry>)> = result
+        .entries
+        .iter()
+        .map(|entry| (entry.key(), Some(entry)))
+        .chain(result.unchanged.keys().map(|key| (key.as_str(), None)))
+        .collect();
+    keys.sort_by_key(|(key, _)| result.rank(key));
+
+    let unchanged: Vec<usize> = (0..keys.len()).filter(|&i| keys[i].1.is_none()).collect();
+    let mut shown = vec![false; keys.len()];
+    for (i, (_, change)) in keys.iter().enumerate() {
+        if change.is_none() {
+            continue;
+        }
+        shown[i] = true;
+        let split = unchanged.partition_point(|&j| j < i);
+        for &j in unchanged[..split].iter().rev().take(context) {
+            shown[j] = true;
+        }
+        for &j in unchanged[split..].iter().take(context) {
+            shown[j] = true;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut previous = None;
+    for (i, (key, change)) in keys.iter().enumerate().filter(|&(i, _)| shown[i]) {
+        if context > 0 && previous.is_some_and(|previous| i > previous + 1) {
+            lines.push(DiffLine::Gap);
+        }
+        previous = Some(i);
+        lines.push(match change {
+            Some(entry) => DiffLine::Change(entry),
+            None => DiffLine::Context {
+                key,
+                value: &result.unchanged[*key],
+            },
         });
     }
 
-    // Find changed keys (in both but different values)
-    for key in keys1.intersection(&keys2) {
-        let value1 = file1.get(key).unwrap();
-        let value2 = file2.get(key).unwrap();
+    lines
+}
 
-        if value1 != value2 {
-            entries.push(DiffEntry::Changed {
-                key: (*key).clone(),
-                old_value: value1.clone(),
-                new_value: value2.clone(),
-            });
+/// List every key in order: differences alongside unchanged keys.
+fn with_unchanged(result: &DiffResult) -> Vec<DiffLine<'_>> {
+    let mut lines: Vec<DiffLine> = result
+        .entries
+        .iter()
+        .map(DiffLine::Change)
+        .chain(
+            result
+                .unchanged
+                .iter()
+                .map(|(key, value)| DiffLine::Unchanged { key, value }),
+        )
+        .collect();
+    lines.sort_by_key(|line| result.rank(line.key().unwrap_or_default()));
+    lines
+}
+
+/// Render the cell for a key's value in one file of a matrix.
+fn matrix_cell(value: Option<&String>, redact: Redaction) -> String {
+    match (value, redact) {
+        (None, _) => "(unset)".to_string(),
+        (Some(_), Redaction::Hide) => "set".to_string(),
+        (Some(value), Redaction::Off) => value.replace('\n', "\\n"),
+        (Some(value), redact) => redact.replacement(value).unwrap_or_default(),
+    }
+}
+
+/// Compare any number of files as a table with one row per key defined in
+/// any of them and one column per file, sorted by key.
+///
+/// Rows are marked `=` when every file defines the key with the same value
+/// and `~` otherwise. `redaction` decides how each key's values are shown.
+/// Returns the lines and the number of `~` rows.
+pub fn matrix(
+    names: &[String],
+    envs: &[EnvFile],
+    redaction: impl Fn(&str) -> Redaction,
+) -> (Vec<String>, usize) {
+    let keys: Vec<String> = envs
+        .iter()
+        .flat_map(|env| env.keys().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let inconsistent: BTreeSet<String> = consistency::find_divergences(envs, &keys)
+        .into_iter()
+        .map(|divergence| divergence.key)
+        .collect();
+
+    let mut rows = vec![std::iter::once("KEY".to_string())
+        .chain(names.iter().cloned())
+        .collect::<Vec<_>>()];
+    for key in &keys {
+        rows.push(
+            std::iter::once(key.clone())
+                .chain(
+                    envs.iter()
+                        .map(|env| matrix_cell(env.get(key), redaction(key))),
+                )
+                .collect(),
+        );
+    }
+
+    let columns = rows[0].len();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let lines = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let marker = match i {
+                0 => ' ',
+                _ if inconsistent.contains(&row[0]) => '~',
+                _ => '=',
+            };
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            format!("{marker} {}", cells.join("  "))
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+
+    (lines, inconsistent.len())
+}
+
+/// Load an env file from disk, from git when given a `<rev>:<path>` spec,
+/// or over HTTP when given a URL.
+///
+/// Paths that exist on disk always win, so a file name containing `:` is
+/// never mistaken for a revision.
+fn load(path: &Path, git: bool) -> Result<EnvFile, DiffError> {
+    if let Some(url) = remote::as_url(path) {
+        let content = parser::decode_input(remote::fetch(url)?, path, &ParseOptions::default())?;
+        return Ok(EnvFile::from_str(&content)?);
+    }
+    if git && !path.exists() {
+        if let Some(spec) = path.to_str().filter(|s| git::parse_spec(s).is_some()) {
+            let content = git::show(spec)?;
+            return Ok(EnvFile::from_str(&content)?);
         }
     }
 
-    // Sort by key for deterministic output
-    entries.sort_by(|a, b| a.key().cmp(b.key()));
+    Ok(EnvFile::from_path(path)?)
+}
 
-    DiffResult { entries }
+/// Build a file from environment variables.
+///
+/// With `only`, variables whose names `only` does not define are left out.
+pub fn environment(
+    vars: impl IntoIterator<Item = (String, String)>,
+    only: Option<&EnvFile>,
+) -> EnvFile {
+    EnvFile::from_pairs(
+        vars.into_iter()
+            .filter(|(key, _)| only.is_none_or(|file| file.contains_key(key))),
+    )
+}
+
+/// Run the diff command with `--matrix`, comparing every file at once.
+///
+/// Values are compared as parsed, or as written with `raw`; `filter`
+/// limits the keys shown.
+pub fn run_matrix(paths: &[PathBuf], options: &DiffOptions) -> Result<bool, DiffError> {
+    let envs = paths
+        .iter()
+        .map(|path| {
+            let mut env = load(path, options.git)?;
+            if options.raw {
+                env = EnvFile::from_pairs(env.raw_entries());
+            }
+            if let Some(filter) = &options.filter {
+                env.entries.retain(|key, _| filter.matches(key));
+            }
+            Ok(env)
+        })
+        .collect::<Result<Vec<_>, DiffError>>()?;
+    let names: Vec<String> = paths.iter().map(|path| paths::display(path)).collect();
+
+    let (lines, inconsistent) = matrix(&names, &envs, |key| options.redaction(key));
+    for line in &lines {
+        println!("{line}");
+    }
+    println!();
+    println!(
+        "{inconsistent} of {} key(s) inconsistent across {} files",
+        lines.len() - 1,
+        paths.len()
+    );
+
+    Ok(true)
 }
 
 /// Run the diff command.
-pub fn run_diff(path1: &Path, path2: &Path, redact: bool) -> Result<bool, DiffError> {
-    let file1 = EnvFile::from_path(path1)?;
-    let file2 = EnvFile::from_path(path2)?;
-    let result = diff(&file1, &file2);
+///
+/// Without `path2`, the file is compared against the process environment.
+/// Variables whose name or value is not valid UTF-8 are skipped.
+pub fn run_diff(
+    path1: &Path,
+    path2: Option<&Path>,
+    options: &DiffOptions,
+) -> Result<bool, DiffError> {
+    let file1 = load(path1, options.git)?;
+    let file2 = match path2 {
+        Some(path2) => load(path2, options.git)?,
+        None => {
+            let vars = std::env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            });
+            environment(vars, options.file_keys_only.then_some(&file1))
+        }
+    };
+    if let Some(context) = options.unified {
+        let format = |env| format::format_env(env, &FormatOptions::default(), None);
+        print!(
+            "{}",
+            unified::unified_diff(
+                &paths::display(path1),
+                &path2.map_or("(environment)".to_string(), paths::display),
+                &format(&file1),
+                &format(&file2),
+                context,
+            )
+        );
+        return Ok(true);
+    }
+    let schema = options
+        .schema
+        .as_deref()
+        .map(Schema::from_path)
+        .transpose()?;
 
-    if result.is_empty() {
-        println!("Files are identical");
+    let mut result = match &schema {
+        Some(schema) if options.type_aware => diff_typed(&file1, &file2, schema),
+        Some(schema) if options.fold_case_values => diff_folded(&file1, &file2, schema),
+        _ if options.raw => diff_raw(&file1, &file2),
+        _ => diff(&file1, &file2),
+    };
+    if let Some(filter) = &options.filter {
+        result.entries.retain(|entry| filter.matches(entry.key()));
+        result.unchanged.retain(|key, _| filter.matches(key));
+    }
+    if options.sort == SortOrder::Stable {
+        result.sort_stable(&file1, &file2);
+    }
+
+    if let Some(report_file) = &options.report_file {
+        let report = DiffReport {
+            file1: paths::display(path1),
+            file2: path2.map(paths::display),
+            count: result.len(),
+            differences: result
+                .entries
+                .iter()
+                .map(|entry| entry.to_json_value(options.redaction(entry.key())))
+                .collect::<Result<_, _>>()?,
+        };
+        let json = serde_json::to_string_pretty(&report)? + "\n";
+        fs::write(report_file, json).map_err(|source| DiffError::ReportFile {
+            path: report_file.clone(),
+            source,
+        })?;
+    }
+
+    if options.as_patch {
+        for command in patch(&result, &path1.display().to_string()) {
+            println!("{command}");
+        }
+        return Ok(true);
+    }
+
+    if options.format == DiffFormat::Jsonl {
+        for entry in &result.entries {
+            println!("{}", entry.to_json_line(options.redaction(entry.key()))?);
+        }
         return Ok(true);
     }
 
-    for entry in &result.entries {
-        println!("{}", entry.format(redact));
+    let lines = if options.show_unchanged {
+        with_unchanged(&result)
+    } else {
+        with_context(&result, options.context)
+    };
+    for line in &lines {
+        let redaction = line
+            .key()
+            .map_or(Redaction::Off, |key| options.redaction(key));
+        println!("{}", line.format(redaction));
+    }
+
+    if result.is_empty() {
+        if !lines.is_empty() {
+            println!();
+        }
+        println!("Files are identical");
+        return Ok(true);
     }
 
     println!();
@@ -163,6 +943,174 @@ pub fn run_diff(path1: &Path, path2: &Path, redact: bool) -> Result<bool, DiffEr
 mod tests {
     use super::*;
 
+    /// The set-based comparison `diff_maps` replaced, kept to check that
+    /// the merge join gives the same result.
+    fn diff_maps_by_sets(
+        map1: &BTreeMap<String, String>,
+        map2: &BTreeMap<String, String>,
+    ) -> (Vec<DiffEntry>, BTreeMap<String, String>) {
+        let keys1: BTreeSet<&String> = map1.keys().collect();
+        let keys2: BTreeSet<&String> = map2.keys().collect();
+        let mut entries = Vec::new();
+        let mut unchanged = BTreeMap::new();
+
+        for key in keys1.difference(&keys2) {
+            entries.push(DiffEntry::Removed {
+                key: (*key).clone(),
+                value: map1[*key].clone(),
+            });
+        }
+        for key in keys2.difference(&keys1) {
+            entries.push(DiffEntry::Added {
+                key: (*key).clone(),
+                value: map2[*key].clone(),
+            });
+        }
+        for key in keys1.intersection(&keys2) {
+            let (value1, value2) = (&map1[*key], &map2[*key]);
+            if value1 == value2 {
+                unchanged.insert((*key).clone(), value2.clone());
+            } else {
+                entries.push(DiffEntry::Changed {
+                    key: (*key).clone(),
+                    old_value: value1.clone(),
+                    new_value: value2.clone(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.key().cmp(b.key()));
+
+        (entries, unchanged)
+    }
+
+    #[test]
+    fn test_diff_matches_set_based_diff_on_large_input() {
+        // A simple linear congruential generator keeps the input reproducible
+        let mut state: u64 = 42;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        let mut map1 = BTreeMap::new();
+        let mut map2 = BTreeMap::new();
+        for i in 0..20_000 {
+            let key = format!("KEY_{:05}_{}", next() % 50_000, i % 7);
+            match next() % 4 {
+                0 => {
+                    map1.insert(key, i.to_string());
+                }
+                1 => {
+                    map2.insert(key, i.to_string());
+                }
+                2 => {
+                    map1.insert(key.clone(), i.to_string());
+                    map2.insert(key, i.to_string());
+                }
+                _ => {
+                    map1.insert(key.clone(), i.to_string());
+                    map2.insert(key, (i + 1).to_string());
+                }
+            }
+        }
+
+        let result = diff_maps(&map1, &map2, |_, a, b| a == b);
+        let (entries, unchanged) = diff_maps_by_sets(&map1, &map2);
+
+        assert!(result.len() > 10_000);
+        assert_eq!(result.entries, entries);
+        assert_eq!(result.unchanged, unchanged);
+    }
+
+    #[test]
+    fn test_to_json_line() {
+        let entry = DiffEntry::Changed {
+            key: "PORT".to_string(),
+            old_value: "80".to_string(),
+            new_value: "8080".to_string(),
+        };
+
+        assert_eq!(
+            entry.to_json_line(Redaction::Off).unwrap(),
+            r#"{"change":"changed","key":"PORT","new_value":"8080","old_value":"80"}"#
+        );
+        assert_eq!(
+            entry.to_json_line(Redaction::Hide).unwrap(),
+            r#"{"change":"changed","key":"PORT"}"#
+        );
+    }
+
+    #[test]
+    fn test_diff_against_environment() {
+        let file = EnvFile::from_str("PORT=8080\nDEBUG=false\n").unwrap();
+        let vars = || {
+            [("PORT", "9090"), ("DEBUG", "false"), ("HOME", "/root")]
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+        };
+
+        let result = diff(&file, &environment(vars(), None));
+        assert_eq!(result.len(), 2);
+        assert!(result.entries.contains(&DiffEntry::Added {
+            key: "HOME".to_string(),
+            value: "/root".to_string(),
+        }));
+
+        let result = diff(&file, &environment(vars(), Some(&file)));
+        assert_eq!(
+            result.entries,
+            vec![DiffEntry::Changed {
+                key: "PORT".to_string(),
+                old_value: "8080".to_string(),
+                new_value: "9090".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_format_multiline_change() {
+        let entry = DiffEntry::Changed {
+            key: "TLS_CERT".to_string(),
+            old_value: "-----BEGIN-----\nAAAA\nBBBB\n-----END-----".to_string(),
+            new_value: "-----BEGIN-----\nAAAA\nCCCC\n-----END-----".to_string(),
+        };
+
+        assert_eq!(
+            entry.format(Redaction::Off),
+            "~ TLS_CERT:\n    -----BEGIN-----\n    AAAA\n  - BBBB\n  + CCCC\n    -----END-----"
+        );
+        assert_eq!(entry.format(Redaction::Hide), "~ TLS_CERT");
+        assert!(!entry.format(Redaction::Hash).contains('\n'));
+    }
+
+    #[test]
+    fn test_format_multiline_added() {
+        let entry = DiffEntry::Added {
+            key: "KEY".to_string(),
+            value: "one\ntwo".to_string(),
+        };
+
+        assert_eq!(entry.format(Redaction::Off), "+ KEY:\n  + one\n  + two");
+    }
+
+    #[test]
+    fn test_line_diff_single_to_multiline() {
+        assert_eq!(line_diff("a", "a\nb"), vec![(' ', "a"), ('+', "b")]);
+        assert_eq!(
+            line_diff("a\nb", "c"),
+            vec![('-', "a"), ('-', "b"), ('+', "c")]
+        );
+    }
+
+    #[test]
+    fn test_line_changes() {
+        assert_eq!(
+            line_changes("# a\nA=1\nB=2\n", "A=1\nB=3\n"),
+            vec!["- # a", "- B=2", "+ B=3"]
+        );
+        assert!(line_changes("A=1\n", "A=1\n").is_empty());
+    }
+
     #[test]
     fn test_diff_identical() {
         let env1 = EnvFile::from_str("A=1\nB=2").unwrap();
@@ -227,6 +1175,90 @@ mod tests {
         assert!(matches!(&result.entries[2], DiffEntry::Added { key, .. } if key == "D"));
     }
 
+    #[test]
+    fn test_diff_ignores_quoting_by_default() {
+        let env1 = EnvFile::from_str("SECRET=\"abc\"").unwrap();
+        let env2 = EnvFile::from_str("SECRET=abc").unwrap();
+
+        assert!(diff(&env1, &env2).is_empty());
+    }
+
+    #[test]
+    fn test_diff_raw_detects_quoting() {
+        let env1 = EnvFile::from_str("SECRET=\"abc\"").unwrap();
+        let env2 = EnvFile::from_str("SECRET=abc").unwrap();
+        let result = diff_raw(&env1, &env2);
+
+        assert_eq!(result.len(), 1);
+        assert!(matches!(
+            &result.entries[0],
+            DiffEntry::Changed { key, old_value, new_value }
+            if key == "SECRET" && old_value == "\"abc\"" && new_value == "abc"
+        ));
+    }
+
+    #[test]
+    fn test_diff_unicode_values() {
+        let env1 = EnvFile::from_str("CAFÉ=crème\nCITY=Zürich").unwrap();
+        let env2 = EnvFile::from_str("CAFÉ=crème\nCITY=Genève").unwrap();
+        let result = diff(&env1, &env2);
+
+        assert_eq!(
+            result.entries,
+            vec![DiffEntry::Changed {
+                key: "CITY".to_string(),
+                old_value: "Zürich".to_string(),
+                new_value: "Genève".to_string(),
+            }]
+        );
+        assert_eq!(
+            result.entries[0].format(Redaction::Off),
+            "~ CITY: Zürich → Genève"
+        );
+    }
+
+    #[test]
+    fn test_diff_typed_leading_zero_int() {
+        let schema = Schema::from_str("PORT: int").unwrap();
+        let env1 = EnvFile::from_str("PORT=8080").unwrap();
+        let env2 = EnvFile::from_str("PORT=08080").unwrap();
+
+        assert_eq!(diff(&env1, &env2).len(), 1);
+        assert!(diff_typed(&env1, &env2, &schema).is_empty());
+    }
+
+    #[test]
+    fn test_diff_typed_bool_spelling() {
+        let schema = Schema::from_str("DEBUG: bool").unwrap();
+        let env1 = EnvFile::from_str("DEBUG=TRUE").unwrap();
+        let env2 = EnvFile::from_str("DEBUG=true").unwrap();
+
+        assert!(diff_typed(&env1, &env2, &schema).is_empty());
+    }
+
+    #[test]
+    fn test_diff_folded_only_folds_bools() {
+        let schema = Schema::from_str("DEBUG: bool\nNAME: string\nPORT: int").unwrap();
+        let env1 = EnvFile::from_str("DEBUG=TRUE\nNAME=app\nPORT=80").unwrap();
+        let env2 = EnvFile::from_str("DEBUG=true\nNAME=App\nPORT=080").unwrap();
+        let result = diff_folded(&env1, &env2, &schema);
+
+        let keys: Vec<&str> = result.entries.iter().map(DiffEntry::key).collect();
+        assert_eq!(keys, vec!["NAME", "PORT"]);
+    }
+
+    #[test]
+    fn test_diff_typed_genuine_change() {
+        let schema = Schema::from_str("PORT: int\nDEBUG: bool").unwrap();
+        let env1 = EnvFile::from_str("PORT=8080\nDEBUG=true\nNAME=app").unwrap();
+        let env2 = EnvFile::from_str("PORT=9090\nDEBUG=false\nNAME=App").unwrap();
+        let result = diff_typed(&env1, &env2, &schema);
+
+        // Untyped NAME still compares as a string
+        assert_eq!(result.len(), 3);
+        assert!(matches!(&result.entries[2], DiffEntry::Changed { key, .. } if key == "PORT"));
+    }
+
     #[test]
     fn test_diff_format_normal() {
         let added = DiffEntry::Added {
@@ -243,9 +1275,9 @@ mod tests {
             new_value: "new".to_string(),
         };
 
-        assert_eq!(added.format(false), "+ KEY=value");
-        assert_eq!(removed.format(false), "- KEY=value");
-        assert_eq!(changed.format(false), "~ KEY: old → new");
+        assert_eq!(added.format(Redaction::Off), "+ KEY=value");
+        assert_eq!(removed.format(Redaction::Off), "- KEY=value");
+        assert_eq!(changed.format(Redaction::Off), "~ KEY: old → new");
     }
 
     #[test]
@@ -264,8 +1296,230 @@ mod tests {
             new_value: "new_secret".to_string(),
         };
 
-        assert_eq!(added.format(true), "+ KEY");
-        assert_eq!(removed.format(true), "- KEY");
-        assert_eq!(changed.format(true), "~ KEY");
+        assert_eq!(added.format(Redaction::Hide), "+ KEY");
+        assert_eq!(removed.format(Redaction::Hide), "- KEY");
+        assert_eq!(changed.format(Redaction::Hide), "~ KEY");
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint("secret"), fingerprint("secret"));
+        assert_ne!(fingerprint("secret"), fingerprint("secret2"));
+        assert_eq!(fingerprint(""), "sha256:e3b0c442");
+    }
+
+    #[test]
+    fn test_diff_format_hashed() {
+        let changed = DiffEntry::Changed {
+            key: "KEY".to_string(),
+            old_value: "old_secret".to_string(),
+            new_value: "new_secret".to_string(),
+        };
+
+        assert_eq!(
+            changed.format(Redaction::Hash),
+            format!(
+                "~ KEY: {} → {}",
+                fingerprint("old_secret"),
+                fingerprint("new_secret")
+            )
+        );
+        assert!(!changed.format(Redaction::Hash).contains("secret"));
+    }
+
+    #[test]
+    fn test_diff_format_masked_fixed() {
+        let changed = DiffEntry::Changed {
+            key: "KEY".to_string(),
+            old_value: "ab".to_string(),
+            new_value: "a much longer secret".to_string(),
+        };
+        let added = DiffEntry::Added {
+            key: "NEW".to_string(),
+            value: "line one\nline two".to_string(),
+        };
+        let fixed = MaskStyle::Fixed.redaction('x');
+
+        assert_eq!(changed.format(fixed), "~ KEY: xxxxxxxx → xxxxxxxx");
+        assert_eq!(added.format(fixed), "+ NEW=xxxxxxxx");
+        assert_eq!(
+            DiffLine::Context {
+                key: "OTHER",
+                value: "x"
+            }
+            .format(fixed),
+            "  OTHER=xxxxxxxx"
+        );
+        assert_eq!(
+            changed.to_json_line(fixed).unwrap(),
+            r#"{"change":"changed","key":"KEY","new_value":"xxxxxxxx","old_value":"xxxxxxxx"}"#
+        );
+    }
+
+    #[test]
+    fn test_diff_format_masked_length() {
+        let changed = DiffEntry::Changed {
+            key: "KEY".to_string(),
+            old_value: "ab".to_string(),
+            new_value: "héllo".to_string(),
+        };
+        let removed = DiffEntry::Removed {
+            key: "OLD".to_string(),
+            value: String::new(),
+        };
+        let length = MaskStyle::Length.redaction('*');
+
+        // One mask character per character, not per byte
+        assert_eq!(changed.format(length), "~ KEY: ** → *****");
+        assert_eq!(removed.format(length), "- OLD=");
+        assert_eq!(matrix_cell(Some(&"abc".to_string()), length), "***");
+        assert_eq!(MaskStyle::Hide.redaction('*'), Redaction::Hide);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain-value_1.0"), "plain-value_1.0");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_patch_commands() {
+        let env1 = EnvFile::from_str("KEEP=1\nOLD=x\nCHANGED=a").unwrap();
+        let env2 = EnvFile::from_str("KEEP=1\nCHANGED=b c\nNEW=$HOME").unwrap();
+        let commands = patch(&diff(&env1, &env2), "a.env");
+
+        assert_eq!(
+            commands,
+            vec![
+                "envcraft set a.env CHANGED 'b c'",
+                "envcraft set a.env NEW '$HOME'",
+                "envcraft unset a.env OLD",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_context() {
+        let env1 = EnvFile::from_str("A=1\nB=2\nC=3\nD=4\nE=5\nF=6\nG=7").unwrap();
+        let env2 = EnvFile::from_str("A=1\nB=2\nC=changed\nD=4\nE=5\nF=6\nG=7").unwrap();
+        let result = diff(&env1, &env2);
+        let lines: Vec<String> = with_context(&result, 1)
+            .iter()
+            .map(|line| line.format(Redaction::Off))
+            .collect();
+
+        assert_eq!(lines, vec!["  B=2", "~ C: 3 → changed", "  D=4"]);
+    }
+
+    #[test]
+    fn test_with_context_skips_changes_and_marks_gaps() {
+        let env1 = EnvFile::from_str("A=1\nB=2\nC=3\nD=4\nE=5\nF=6").unwrap();
+        let env2 = EnvFile::from_str("A=x\nB=y\nC=3\nD=4\nE=5\nF=z").unwrap();
+        let result = diff(&env1, &env2);
+        let lines = with_context(&result, 1);
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[2],
+            DiffLine::Context {
+                key: "C",
+                value: "3"
+            }
+        );
+        assert_eq!(lines[3], DiffLine::Gap);
+        assert_eq!(lines[4].format(Redaction::Hide), "  E");
+        assert!(with_context(&result, 0)
+            .iter()
+            .all(|line| matches!(line, DiffLine::Change(_))));
+    }
+
+    #[test]
+    fn test_with_unchanged() {
+        let env1 = EnvFile::from_str("A=1\nB=2\nD=4").unwrap();
+        let env2 = EnvFile::from_str("A=1\nC=3\nD=5").unwrap();
+        let result = diff(&env1, &env2);
+        let lines: Vec<String> = with_unchanged(&result)
+            .iter()
+            .map(|line| line.format(Redaction::Off))
+            .collect();
+
+        assert_eq!(lines, vec!["= A=1", "- B=2", "+ C=3", "~ D: 4 → 5"]);
+        assert_eq!(with_unchanged(&result)[0].format(Redaction::Hide), "= A");
+    }
+
+    #[test]
+    fn test_sort_stable() {
+        let env1 = EnvFile::from_str("b=1\nA=2").unwrap();
+        let env2 = EnvFile::from_str("b=2\nA=2\na=3\nB=4").unwrap();
+        let mut result = diff(&env1, &env2);
+        let keys = |result: &DiffResult| -> Vec<String> {
+            result
+                .entries
+                .iter()
+                .map(|entry| entry.key().to_string())
+                .collect()
+        };
+        assert_eq!(keys(&result), vec!["B", "a", "b"]);
+
+        result.sort_stable(&env1, &env2);
+        assert_eq!(keys(&result), vec!["a", "b", "B"]);
+        let lines: Vec<String> = with_unchanged(&result)
+            .iter()
+            .map(|line| line.format(Redaction::Hide))
+            .collect();
+        assert_eq!(lines, vec!["= A", "+ a", "~ b", "+ B"]);
+    }
+
+    #[test]
+    fn test_matrix() {
+        let envs: Vec<EnvFile> = [
+            "APP=web\nDB=db1\nTRACING=on",
+            "APP=web\nDB=db2",
+            "APP=web\nDB=db2\nTRACING=on",
+        ]
+        .into_iter()
+        .map(|content| EnvFile::from_str(content).unwrap())
+        .collect();
+        let names = ["a.env", "b.env", "c.env"].map(String::from);
+
+        let (lines, inconsistent) = matrix(&names, &envs, |_| Redaction::Off);
+        assert_eq!(
+            lines,
+            vec![
+                "  KEY      a.env  b.env    c.env",
+                "= APP      web    web      web",
+                "~ DB       db1    db2      db2",
+                "~ TRACING  on     (unset)  on",
+            ]
+        );
+        assert_eq!(inconsistent, 2);
+
+        let (lines, _) = matrix(&names[..2], &envs[..2], |_| Redaction::Hide);
+        assert_eq!(lines[2], "~ DB       set    set");
+    }
+
+    #[test]
+    fn test_redaction_per_key() {
+        let mut options = DiffOptions {
+            redact_keys: vec!["*_SECRET".to_string(), "TOKEN".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(options.redaction("DB_SECRET"), Redaction::Hide);
+        assert_eq!(options.redaction("TOKEN"), Redaction::Hide);
+        assert_eq!(options.redaction("PORT"), Redaction::Off);
+
+        options.redact = Redaction::Hash;
+        assert_eq!(options.redaction("DB_SECRET"), Redaction::Hash);
+        assert_eq!(options.redaction("PORT"), Redaction::Off);
+
+        options.redact_keys.clear();
+        assert_eq!(options.redaction("PORT"), Redaction::Hash);
+
+        options.redact = MaskStyle::Length.redaction('*');
+        options.redact_keys = vec!["*_SECRET".to_string()];
+        assert_eq!(options.redaction("DB_SECRET"), options.redact);
+        assert_eq!(options.redaction("PORT"), Redaction::Off);
     }
 }
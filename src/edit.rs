@@ -0,0 +1,445 @@
+//! Line-preserving edits to .env files.
+//!
+//! Edits operate on the parsed line structure and rewrite only the affected
+//! lines, so comments, ordering, and whitespace elsewhere stay byte-identical.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::diff;
+use crate::format::render_value;
+use crate::parser::{self, EnvFile, EnvLine, ParseError};
+use crate::paths;
+
+/// Errors that can occur while editing an env file.
+#[derive(Error, Debug)]
+pub enum EditError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("{0}")]
+    Read(ParseError),
+
+    #[error("failed to write file: {0}")]
+    Write(io::Error),
+
+    #[error("key not found: {0}")]
+    KeyNotFound(String),
+
+    #[error("key already exists: {0} (use --force to overwrite)")]
+    KeyExists(String),
+
+    #[error("invalid key name: {0} (use letters, digits, and underscores)")]
+    InvalidKey(String),
+
+    #[error("value for {0} contains a line break")]
+    MultilineValue(String),
+
+    #[error("failed to read value from stdin: {0}")]
+    Stdin(io::Error),
+}
+
+/// Value argument that makes `set` read the value from stdin.
+const STDIN_VALUE: &str = "-";
+
+/// Split content into lines, keeping each line's terminator.
+///
+/// The result lines up index-for-index with `EnvFile::lines`.
+fn split_lines(content: &str) -> Vec<&str> {
+    content.split_inclusive('\n').collect()
+}
+
+/// Replace the key on a `KEY=VALUE` line, keeping surrounding whitespace.
+fn replace_key(line: &str, old_key: &str, new_key: &str) -> String {
+    let eq_pos = parser::key_separator(line).unwrap_or(0);
+    let prefix = line[..eq_pos].replacen(old_key, new_key, 1);
+    format!("{prefix}{}", &line[eq_pos..])
+}
+
+/// Split a line into its text and its line terminator.
+fn split_ending(line: &str) -> (&str, &str) {
+    let text = line.trim_end_matches(['\r', '\n']);
+    (text, &line[text.len()..])
+}
+
+/// Replace the value on a `KEY=VALUE` line, keeping the key text as written.
+///
+/// An inline comment is kept after a quoted value, so it is still recognized
+/// as a comment. A value containing both quote characters cannot be followed
+/// by a comment, so the comment is dropped.
+fn replace_value(line: &str, value: &str, comment: Option<&str>) -> String {
+    let (text, ending) = split_ending(line);
+    let eq_pos = parser::key_separator(text).map_or(text.len(), |pos| pos + 1);
+    let rest = &text[eq_pos..];
+    let prefix = &text[..eq_pos + rest.len() - rest.trim_start().len()];
+    let quote = ['"', '\''].into_iter().find(|&q| !value.contains(q));
+
+    match (comment, quote) {
+        (Some(comment), Some(q)) => format!("{prefix}{q}{value}{q} {comment}{ending}"),
+        _ => format!("{prefix}{}{ending}", render_value(value)),
+    }
+}
+
+/// Set a key's value, adding the key at the end if it is not defined.
+///
+/// Every definition of an existing key is updated in place; all other lines
+/// stay byte-identical.
+pub fn set(content: &str, key: &str, value: &str) -> Result<String, EditError> {
+    if !parser::is_identifier(key) {
+        return Err(EditError::InvalidKey(key.to_string()));
+    }
+    if value.contains(['\n', '\r']) {
+        return Err(EditError::MultilineValue(key.to_string()));
+    }
+
+    let env = EnvFile::from_str(content)?;
+
+    if !env.contains_key(key) {
+        let mut output = content.to_string();
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&format!("{key}={}\n", render_value(value)));
+        return Ok(output);
+    }
+
+    let mut output = String::new();
+    for (text, line) in split_lines(content).into_iter().zip(&env.lines) {
+        match line {
+            EnvLine::KeyValue {
+                key: line_key,
+                comment,
+                ..
+            } if line_key == key => {
+                output.push_str(&replace_value(text, value, comment.as_deref()));
+            }
+            _ => output.push_str(text),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Remove every definition of a key, leaving all other lines untouched.
+///
+/// With `with_comments`, the comment lines directly above each definition
+/// are removed too; a blank line or another key ends the comment block.
+pub fn unset(content: &str, key: &str, with_comments: bool) -> Result<String, EditError> {
+    let env = EnvFile::from_str(content)?;
+
+    if !env.contains_key(key) {
+        return Err(EditError::KeyNotFound(key.to_string()));
+    }
+
+    let mut removed: Vec<bool> = env
+        .lines
+        .iter()
+        .map(|line| matches!(line, EnvLine::KeyValue { key: line_key, .. } if line_key == key))
+        .collect();
+    if with_comments {
+        for index in 0..removed.len() {
+            if !matches!(&env.lines[index], EnvLine::KeyValue { key: line_key, .. } if line_key == key)
+            {
+                continue;
+            }
+            let mut above = index;
+            while above > 0 && matches!(env.lines[above - 1], EnvLine::Comment(_)) {
+                above -= 1;
+                removed[above] = true;
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for (text, removed) in split_lines(content).into_iter().zip(removed) {
+        if !removed {
+            output.push_str(text);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Rename a key, preserving its value, position, and surrounding comments.
+///
+/// Every definition of `old_key` is renamed. If `new_key` already exists the
+/// rename fails unless `force` is set, in which case the existing `new_key`
+/// lines are removed so the renamed value takes effect.
+pub fn rename(
+    content: &str,
+    old_key: &str,
+    new_key: &str,
+    force: bool,
+) -> Result<String, EditError> {
+    if !parser::is_identifier(new_key) {
+        return Err(EditError::InvalidKey(new_key.to_string()));
+    }
+
+    let env = EnvFile::from_str(content)?;
+
+    if !env.contains_key(old_key) {
+        return Err(EditError::KeyNotFound(old_key.to_string()));
+    }
+    if env.contains_key(new_key) && !force {
+        return Err(EditError::KeyExists(new_key.to_string()));
+    }
+
+    let mut output = String::new();
+    for (text, line) in split_lines(content).into_iter().zip(&env.lines) {
+        match line {
+            EnvLine::KeyValue { key, .. } if key == old_key => {
+                output.push_str(&replace_key(text, old_key, new_key));
+            }
+            EnvLine::KeyValue { key, .. } if key == new_key => {
+                // Only reachable with --force: drop the overwritten definition
+            }
+            _ => output.push_str(text),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Read a file to edit, returning its content without a byte order mark and
+/// whether it had one.
+pub fn read_file(path: &Path) -> Result<(String, bool), EditError> {
+    parser::read_file_keeping_bom(path).map_err(EditError::Read)
+}
+
+/// Write edited content back, restoring the byte order mark the file had.
+pub fn write_file(path: &Path, content: &str, bom: bool) -> Result<(), EditError> {
+    let bom: &[u8] = if bom { parser::UTF8_BOM } else { b"" };
+    fs::write(path, [bom, content.as_bytes()].concat()).map_err(EditError::Write)
+}
+
+/// Print the lines an edit would change, for a dry run.
+fn preview(path: &Path, content: &str, updated: &str) {
+    let changes = diff::line_changes(content, updated);
+    for line in &changes {
+        println!("{line}");
+    }
+
+    if changes.is_empty() {
+        println!("dry run: no changes to {}", paths::display(path));
+    } else {
+        println!("dry run: {} not modified", paths::display(path));
+    }
+}
+
+/// Run the rename command.
+///
+/// With `dry_run`, the lines that would change are printed instead.
+pub fn run_rename(
+    path: &Path,
+    old_key: &str,
+    new_key: &str,
+    force: bool,
+    dry_run: bool,
+) -> Result<bool, EditError> {
+    let (content, bom) = read_file(path)?;
+    let renamed = rename(&content, old_key, new_key, force)?;
+    if dry_run {
+        preview(path, &content, &renamed);
+        return Ok(true);
+    }
+
+    write_file(path, &renamed, bom)?;
+    println!("Renamed {old_key} to {new_key} in {}", paths::display(path));
+
+    Ok(true)
+}
+
+/// Read a value until end of input, dropping a single trailing line break.
+///
+/// Empty input, including a pipe closed without writing, gives an empty value.
+fn read_value(mut reader: impl Read) -> Result<String, EditError> {
+    let mut value = String::new();
+    reader
+        .read_to_string(&mut value)
+        .map_err(EditError::Stdin)?;
+
+    if let Some(stripped) = value.strip_suffix('\n') {
+        let stripped = stripped.strip_suffix('\r').unwrap_or(stripped);
+        value.truncate(stripped.len());
+    }
+    Ok(value)
+}
+
+/// Run the set command.
+///
+/// A value of `-` is read from stdin. With `dry_run`, the lines that would
+/// change are printed instead.
+pub fn run_set(path: &Path, key: &str, value: &str, dry_run: bool) -> Result<bool, EditError> {
+    let value = if value == STDIN_VALUE {
+        read_value(io::stdin().lock())?
+    } else {
+        value.to_string()
+    };
+    let (content, bom) = read_file(path)?;
+    let updated = set(&content, key, &value)?;
+    if dry_run {
+        preview(path, &content, &updated);
+        return Ok(true);
+    }
+
+    write_file(path, &updated, bom)?;
+    println!("Set {key} in {}", paths::display(path));
+
+    Ok(true)
+}
+
+/// Run the unset command.
+///
+/// A key that is not defined leaves the file alone and prints a notice,
+/// unless `strict` is set, in which case it is an error. With `dry_run`,
+/// the lines that would change are printed instead.
+pub fn run_unset(
+    path: &Path,
+    key: &str,
+    with_comments: bool,
+    strict: bool,
+    dry_run: bool,
+) -> Result<bool, EditError> {
+    let (content, bom) = read_file(path)?;
+    let updated = match unset(&content, key, with_comments) {
+        Err(EditError::KeyNotFound(_)) if !strict => {
+            println!(
+                "{key} is not set in {}; nothing to remove",
+                paths::display(path)
+            );
+            return Ok(true);
+        }
+        result => result?,
+    };
+    if dry_run {
+        preview(path, &content, &updated);
+        return Ok(true);
+    }
+
+    write_file(path, &updated, bom)?;
+    println!("Removed {key} from {}", paths::display(path));
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_simple() {
+        let content = "# Header\nA=1\n# About old\nOLD_NAME = \"value\"\nZ=2\n";
+        let renamed = rename(content, "OLD_NAME", "NEW_NAME", false).unwrap();
+
+        assert_eq!(
+            renamed,
+            "# Header\nA=1\n# About old\nNEW_NAME = \"value\"\nZ=2\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_preserves_line_endings() {
+        let content = "OLD=1\r\nOTHER=2";
+        let renamed = rename(content, "OLD", "NEW", false).unwrap();
+
+        assert_eq!(renamed, "NEW=1\r\nOTHER=2");
+    }
+
+    #[test]
+    fn test_rename_missing_key() {
+        let result = rename("A=1\n", "MISSING", "B", false);
+
+        assert!(matches!(result, Err(EditError::KeyNotFound(key)) if key == "MISSING"));
+    }
+
+    #[test]
+    fn test_rename_collision() {
+        let result = rename("OLD=1\nNEW=2\n", "OLD", "NEW", false);
+
+        assert!(matches!(result, Err(EditError::KeyExists(key)) if key == "NEW"));
+    }
+
+    #[test]
+    fn test_rename_force_overwrites() {
+        let content = "OLD=1\nNEW=2\n";
+        let renamed = rename(content, "OLD", "NEW", true).unwrap();
+
+        assert_eq!(renamed, "NEW=1\n");
+        let env = EnvFile::from_str(&renamed).unwrap();
+        assert_eq!(env.get("NEW"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn test_rename_rejects_invalid_key() {
+        for new_key in ["B=C", "NEW KEY", ""] {
+            let result = rename("A=1\nB=2\n", "A", new_key, true);
+
+            assert!(
+                matches!(result, Err(EditError::InvalidKey(ref key)) if key == new_key),
+                "{new_key:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_existing_key() {
+        let content = "# Header\nA=1\r\nB = \"old\" # note\nC=3";
+        let updated = set(content, "B", "new value").unwrap();
+
+        assert_eq!(updated, "# Header\nA=1\r\nB = \"new value\" # note\nC=3");
+        let env = EnvFile::from_str(&updated).unwrap();
+        assert_eq!(env.get("B"), Some(&"new value".to_string()));
+    }
+
+    #[test]
+    fn test_set_new_key_appends() {
+        assert_eq!(set("A=1", "B", "2").unwrap(), "A=1\nB=2\n");
+        assert_eq!(set("", "B", " padded ").unwrap(), "B=\" padded \"\n");
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_input() {
+        assert!(matches!(
+            set("", "BAD KEY", "1"),
+            Err(EditError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            set("", "KEY", "a\nb"),
+            Err(EditError::MultilineValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_value_trims_one_line_break() {
+        assert_eq!(read_value("secret\n".as_bytes()).unwrap(), "secret");
+        assert_eq!(read_value("secret\r\n".as_bytes()).unwrap(), "secret");
+        assert_eq!(read_value("secret".as_bytes()).unwrap(), "secret");
+        assert_eq!(read_value("a\n\n".as_bytes()).unwrap(), "a\n");
+        assert_eq!(read_value("".as_bytes()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_unset_removes_every_definition() {
+        let content = "A=1\nB=2\n# keep\nA=3\n";
+
+        assert_eq!(unset(content, "A", false).unwrap(), "B=2\n# keep\n");
+        assert!(matches!(
+            unset(content, "Z", false),
+            Err(EditError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_unset_with_comments() {
+        let content = "# Header\n\n# Old flag\n# remove me\nOLD=1\nB=2\n# about B\nOLD=2\r\n";
+
+        assert_eq!(unset(content, "OLD", true).unwrap(), "# Header\n\nB=2\n");
+        assert_eq!(
+            unset(content, "OLD", false).unwrap(),
+            "# Header\n\n# Old flag\n# remove me\nB=2\n# about B\n"
+        );
+    }
+}
@@ -4,10 +4,22 @@
 
 use thiserror::Error;
 
+use crate::assert::AssertError;
+use crate::cache::CacheError;
+use crate::check::CheckError;
+use crate::consistency::ConsistencyError;
+use crate::convert::ConvertError;
 use crate::diff::DiffError;
+use crate::edit::EditError;
 use crate::format::FormatError;
+use crate::hash::HashError;
+use crate::init::InitError;
+use crate::keys::KeysError;
+use crate::lint::LintError;
 use crate::parser::ParseError;
+use crate::resolve::ResolveError;
 use crate::schema::SchemaError;
+use crate::stats::StatsError;
 
 /// Top-level error type for envcraft operations.
 #[derive(Error, Debug)]
@@ -23,4 +35,40 @@ pub enum EnvcraftError {
 
     #[error("{0}")]
     Parse(#[from] ParseError),
+
+    #[error("{0}")]
+    Stats(#[from] StatsError),
+
+    #[error("{0}")]
+    Edit(#[from] EditError),
+
+    #[error("{0}")]
+    Lint(#[from] LintError),
+
+    #[error("{0}")]
+    Check(#[from] CheckError),
+
+    #[error("{0}")]
+    Init(#[from] InitError),
+
+    #[error("{0}")]
+    Convert(#[from] ConvertError),
+
+    #[error("{0}")]
+    Keys(#[from] KeysError),
+
+    #[error("{0}")]
+    Assert(#[from] AssertError),
+
+    #[error("{0}")]
+    Resolve(#[from] ResolveError),
+
+    #[error("{0}")]
+    Consistency(#[from] ConsistencyError),
+
+    #[error("{0}")]
+    Hash(#[from] HashError),
+
+    #[error("{0}")]
+    Cache(#[from] CacheError),
 }
@@ -0,0 +1,340 @@
+//! Filter expressions selecting keys by name.
+//!
+//! An expression combines tests on the key name with `and`, `or`, `not`,
+//! and parentheses, e.g. `startswith("DB_") and not endswith("_SECRET")`.
+//! The tests are `startswith`, `endswith`, `contains`, and `matches` (a
+//! regular expression searched anywhere in the name). `not` binds tightest,
+//! then `and`, then `or`. Arguments are single- or double-quoted strings in
+//! which a backslash escapes the next character.
+
+use std::str::FromStr;
+
+use regex::Regex;
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+enum Expr {
+    StartsWith(String),
+    EndsWith(String),
+    Contains(String),
+    Matches(Regex),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Check if a key satisfies this expression.
+    fn eval(&self, key: &str) -> bool {
+        match self {
+            Expr::StartsWith(prefix) => key.starts_with(prefix.as_str()),
+            Expr::EndsWith(suffix) => key.ends_with(suffix.as_str()),
+            Expr::Contains(text) => key.contains(text.as_str()),
+            Expr::Matches(pattern) => pattern.is_match(key),
+            Expr::Not(inner) => !inner.eval(key),
+            Expr::And(left, right) => left.eval(key) && right.eval(key),
+            Expr::Or(left, right) => left.eval(key) || right.eval(key),
+        }
+    }
+}
+
+/// A key filter given with `--filter`.
+#[derive(Debug, Clone)]
+pub struct KeyFilter(Expr);
+
+impl KeyFilter {
+    /// Check if a key is selected by this filter.
+    pub fn matches(&self, key: &str) -> bool {
+        self.0.eval(key)
+    }
+}
+
+impl FromStr for KeyFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { text: s, pos: 0 };
+        let expr = parser.or()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("expected 'and', 'or', or the end of the filter"));
+        }
+
+        Ok(KeyFilter(expr))
+    }
+}
+
+/// Check if a key matches a glob pattern, where `*` stands for any run of
+/// characters and `?` for exactly one.
+pub fn glob_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+    let (mut p, mut k) = (0, 0);
+    // Position of the last `*` seen, and of the key when it was reached
+    let mut star: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, k));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match star {
+                // Let the last `*` absorb one more character and retry
+                Some((star_p, star_k)) => {
+                    star = Some((star_p, star_k + 1));
+                    p = star_p + 1;
+                    k = star_k + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A recursive-descent parser over filter text.
+struct Parser<'a> {
+    text: &'a str,
+    /// Byte offset of the next unread character
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// Describe a syntax error at the current position (1-based column).
+    fn error(&self, message: &str) -> String {
+        let column = self.text[..self.pos].chars().count() + 1;
+        format!("{message} at column {column}")
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.text.len() - self.rest().trim_start().len();
+    }
+
+    /// Read a run of identifier characters, without consuming it.
+    fn peek_word(&mut self) -> &str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    /// Consume `word` if it is the next word.
+    fn keyword(&mut self, word: &str) -> bool {
+        if self.peek_word() == word {
+            self.pos += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume `symbol` if it is the next character, or fail.
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.rest().starts_with(symbol) {
+            self.pos += symbol.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{symbol}'")))
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        if self.rest().starts_with('(') {
+            self.pos += 1;
+            let expr = self.or()?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+
+        let start = self.pos;
+        let name = self.peek_word().to_string();
+        let make: fn(String) -> Result<Expr, String> = match name.as_str() {
+            "startswith" => |arg| Ok(Expr::StartsWith(arg)),
+            "endswith" => |arg| Ok(Expr::EndsWith(arg)),
+            "contains" => |arg| Ok(Expr::Contains(arg)),
+            "matches" => |arg| {
+                Regex::new(&arg)
+                    .map(Expr::Matches)
+                    .map_err(|e| format!("invalid pattern '{arg}': {e}"))
+            },
+            "" => return Err(self.error("expected a test such as startswith(\"DB_\")")),
+            _ => {
+                return Err(self.error(&format!(
+                "unknown test '{name}' (expected one of: startswith, endswith, contains, matches)"
+            )))
+            }
+        };
+        self.pos += name.len();
+        self.expect('(')?;
+        let arg = self.string()?;
+        self.expect(')')?;
+
+        make(arg).map_err(|message| {
+            self.pos = start;
+            self.error(&message)
+        })
+    }
+
+    /// Read a quoted string argument.
+    fn string(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        let mut chars = self.rest().char_indices();
+        let quote = match chars.next() {
+            Some((_, quote @ ('"' | '\''))) => quote,
+            _ => return Err(self.error("expected a quoted string")),
+        };
+
+        let mut value = String::new();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                c => value.push(c),
+            }
+        }
+
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: [&str; 6] = [
+        "DB_HOST",
+        "DB_PASSWORD_SECRET",
+        "DB_PORT",
+        "API_SECRET",
+        "PORT",
+        "LOG_LEVEL",
+    ];
+
+    fn select(filter: &str) -> Vec<&'static str> {
+        let filter: KeyFilter = filter.parse().unwrap();
+        KEYS.into_iter().filter(|key| filter.matches(key)).collect()
+    }
+
+    #[test]
+    fn test_single_tests() {
+        assert_eq!(
+            select(r#"startswith("DB_")"#),
+            ["DB_HOST", "DB_PASSWORD_SECRET", "DB_PORT"]
+        );
+        assert_eq!(
+            select("endswith('_SECRET')"),
+            ["DB_PASSWORD_SECRET", "API_SECRET"]
+        );
+        assert_eq!(select(r#"contains("PORT")"#), ["DB_PORT", "PORT"]);
+        assert_eq!(
+            select(r#"matches("^[A-Z]+_[A-Z]+$")"#),
+            ["DB_HOST", "DB_PORT", "API_SECRET", "LOG_LEVEL"]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert_eq!(
+            select(r#"startswith("DB_") and not endswith("_SECRET")"#),
+            ["DB_HOST", "DB_PORT"]
+        );
+        assert_eq!(
+            select(r#"contains("PORT") or startswith("LOG")"#),
+            ["DB_PORT", "PORT", "LOG_LEVEL"]
+        );
+        // `and` binds tighter than `or`; parentheses override it
+        assert_eq!(
+            select(r#"startswith("API") or startswith("DB") and endswith("PORT")"#),
+            ["DB_PORT", "API_SECRET"]
+        );
+        assert_eq!(
+            select(r#"(startswith("API") or startswith("DB")) and not contains("SECRET")"#),
+            ["DB_HOST", "DB_PORT"]
+        );
+        assert_eq!(select(r#"not not contains("LEVEL")"#), ["LOG_LEVEL"]);
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*_SECRET", "DB_SECRET"));
+        assert!(glob_matches("*_SECRET", "_SECRET"));
+        assert!(!glob_matches("*_SECRET", "DB_SECRET_ID"));
+        assert!(glob_matches("DB_*", "DB_HOST"));
+        assert!(glob_matches("*TOKEN*", "API_TOKEN_V2"));
+        assert!(glob_matches("KEY_?", "KEY_1"));
+        assert!(!glob_matches("KEY_?", "KEY_12"));
+        assert!(glob_matches("PORT", "PORT"));
+        assert!(!glob_matches("PORT", "PORTS"));
+        assert!(glob_matches("*", ""));
+    }
+
+    #[test]
+    fn test_escaped_quote() {
+        let filter: KeyFilter = r#"contains("a\"b")"#.parse().unwrap();
+        assert!(filter.matches("xa\"by"));
+        assert!(!filter.matches("ab"));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = |text: &str| text.parse::<KeyFilter>().unwrap_err();
+
+        assert_eq!(
+            error(r#"startswith("DB_") and"#),
+            "expected a test such as startswith(\"DB_\") at column 22"
+        );
+        assert!(error(r#"beginswith("DB_")"#).starts_with("unknown test 'beginswith'"));
+        assert_eq!(
+            error(r#"contains("DB_"#),
+            "unterminated string at column 10"
+        );
+        assert_eq!(
+            error(r#"contains("A") contains("B")"#),
+            "expected 'and', 'or', or the end of the filter at column 15"
+        );
+        assert!(error(r#"matches("(")"#).starts_with("invalid pattern '('"));
+        assert_eq!(
+            error("contains(A)"),
+            "expected a quoted string at column 10"
+        );
+    }
+}
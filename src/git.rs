@@ -0,0 +1,80 @@
+//! Reading file contents from git revisions.
+//!
+//! Shells out to the `git` executable; no git library is linked.
+
+use std::process::Command;
+
+use thiserror::Error;
+
+/// Errors that can occur while reading from git.
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("failed to run git: {0}")]
+    Unavailable(#[from] std::io::Error),
+
+    #[error("not a git repository (needed to read '{0}')")]
+    NotARepository(String),
+
+    #[error("failed to read '{spec}' from git: {message}")]
+    InvalidRevision { spec: String, message: String },
+
+    #[error("'{0}' is not valid UTF-8; save the file as UTF-8")]
+    NotUtf8(String),
+}
+
+/// Split a `<rev>:<path>` specification into its revision and path.
+pub fn parse_spec(spec: &str) -> Option<(&str, &str)> {
+    let (rev, path) = spec.split_once(':')?;
+
+    if rev.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some((rev, path))
+}
+
+/// Read the content of `<rev>:<path>` using `git show`.
+///
+/// The path is resolved the same way git does: relative to the repository
+/// root, or relative to the current directory when prefixed with `./`.
+pub fn show(spec: &str) -> Result<String, GitError> {
+    let inside = Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()?;
+
+    if !inside.status.success() {
+        return Err(GitError::NotARepository(spec.to_string()));
+    }
+
+    let output = Command::new("git").args(["show", spec]).output()?;
+
+    if !output.status.success() {
+        return Err(GitError::InvalidRevision {
+            spec: spec.to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    String::from_utf8(output.stdout).map_err(|_| GitError::NotUtf8(spec.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(parse_spec("HEAD~1:.env"), Some(("HEAD~1", ".env")));
+        assert_eq!(
+            parse_spec("main:config/app.env"),
+            Some(("main", "config/app.env"))
+        );
+    }
+
+    #[test]
+    fn test_parse_spec_rejects_plain_paths() {
+        assert_eq!(parse_spec(".env"), None);
+        assert_eq!(parse_spec(":.env"), None);
+        assert_eq!(parse_spec("HEAD:"), None);
+    }
+}
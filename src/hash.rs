@@ -0,0 +1,84 @@
+//! Stable fingerprints of an env file's semantic content.
+//!
+//! The hash covers only the effective key-value pairs, so comments, blank
+//! lines, quoting, whitespace, and key order don't change it. Two files that
+//! define the same keys with the same values always hash identically.
+
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::parser::{EnvFile, ParseError};
+
+/// Errors that can occur during hash operation.
+#[derive(Error, Debug)]
+pub enum HashError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+}
+
+/// Hash the effective entries of an env file.
+///
+/// The digest is SHA-256 over the entries sorted by key, each written as the
+/// key and then the value, both preceded by their length in bytes as a
+/// big-endian `u64`. Length prefixes keep `A=bc` and `Ab=c` apart.
+/// The result is `sha256:` followed by 64 lowercase hex digits.
+pub fn content_hash(env: &EnvFile) -> String {
+    let mut hasher = Sha256::new();
+
+    for (key, value) in &env.entries {
+        for part in [key, value] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part.as_bytes());
+        }
+    }
+
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256:{hex}")
+}
+
+/// Run the hash command.
+pub fn run_hash(path: &Path) -> Result<bool, HashError> {
+    let env = EnvFile::from_path(path)?;
+    println!("{}", content_hash(&env));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(content: &str) -> String {
+        content_hash(&EnvFile::from_str(content).unwrap())
+    }
+
+    #[test]
+    fn test_hash_ignores_order_comments_and_quoting() {
+        let a = hash("# config\nPORT=8080\nHOST=localhost\n");
+        let b = hash("HOST=\"localhost\"\n\n  PORT = 8080  \n");
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_hash_changes_with_values() {
+        assert_ne!(hash("PORT=8080\n"), hash("PORT=8081\n"));
+        assert_ne!(hash("A=bc\n"), hash("AB=c\n"));
+        assert_ne!(hash("A=1\n"), hash("A=1\nB=\n"));
+    }
+
+    #[test]
+    fn test_hash_format() {
+        let digest = hash("");
+
+        assert_eq!(
+            digest,
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
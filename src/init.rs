@@ -0,0 +1,145 @@
+//! Scaffolding a starter schema and example env file.
+//!
+//! Writes `schema.yml` and a matching `.env.example` so a new project has
+//! something to validate against right away. With an existing schema, an env
+//! file template for it is printed instead.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::format::render_value;
+use crate::paths;
+use crate::schema::{Schema, SchemaError};
+
+/// Errors that can occur during init operation.
+#[derive(Error, Debug)]
+pub enum InitError {
+    #[error("failed to write file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("{} already exists (use --force to overwrite)", .0.display())]
+    FileExists(PathBuf),
+
+    #[error("{0}")]
+    Schema(#[from] SchemaError),
+}
+
+/// File name of the generated schema.
+const SCHEMA_FILE: &str = "schema.yml";
+
+/// File name of the generated example env file.
+const EXAMPLE_FILE: &str = ".env.example";
+
+/// Starter schema with a few commented example fields.
+const SCHEMA_TEMPLATE: &str = "\
+# envcraft schema: maps each key to its type (string, int, bool, list)
+#
+# A key can also be declared as a mapping, for example:
+#   API_KEY: {type: string, forbid: [changeme, TODO]}
+#   PORT: {type: int, aliases: [HTTP_PORT], description: HTTP listen port}
+
+APP_NAME: string
+PORT: int
+DEBUG: bool
+";
+
+/// Example env file that passes the starter schema.
+const EXAMPLE_TEMPLATE: &str = "\
+# Copy to .env and fill in real values
+APP_NAME=my-app
+PORT=8080
+DEBUG=false
+";
+
+/// Run the init command, creating the starter files in `dir`.
+///
+/// Nothing is written if either file already exists, unless `force` is set.
+pub fn run_init(dir: &Path, force: bool) -> Result<bool, InitError> {
+    let files = [
+        (dir.join(SCHEMA_FILE), SCHEMA_TEMPLATE),
+        (dir.join(EXAMPLE_FILE), EXAMPLE_TEMPLATE),
+    ];
+
+    if !force {
+        if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(InitError::FileExists(path.clone()));
+        }
+    }
+
+    for (path, content) in &files {
+        fs::write(path, content)?;
+        println!("Created {}", paths::display(path));
+    }
+
+    Ok(true)
+}
+
+/// Render an env file template for a schema: a `KEY=` line per key, filled
+/// with the key's default, below its description.
+///
+/// With `defaults_only`, keys without a default are left out.
+fn schema_template(schema: &Schema, defaults_only: bool) -> String {
+    let mut output = String::new();
+
+    for (key, field) in &schema.fields {
+        if defaults_only && field.default.is_none() {
+            continue;
+        }
+        if let Some(description) = &field.description {
+            output.push_str(&format!("# {description}\n"));
+        }
+        let value = field.default.as_deref().map(render_value);
+        output.push_str(&format!("{key}={}\n", value.unwrap_or_default()));
+    }
+
+    output
+}
+
+/// Run the init command for an existing schema, printing an env file
+/// template for it instead of creating the starter files.
+pub fn run_init_from_schema(schema_path: &Path, defaults_only: bool) -> Result<bool, InitError> {
+    let schema = Schema::from_path(schema_path)?;
+    print!("{}", schema_template(&schema, defaults_only));
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::EnvFile;
+    use crate::schema::{validate, Schema};
+
+    #[test]
+    fn test_templates_validate() {
+        let schema = Schema::from_str(SCHEMA_TEMPLATE).unwrap();
+        let env = EnvFile::from_str(EXAMPLE_TEMPLATE).unwrap();
+        let result = validate(&schema, &env);
+
+        assert_eq!(schema.fields.len(), 3);
+        assert!(result.is_valid());
+        assert!(result.extra.is_empty());
+    }
+
+    #[test]
+    fn test_schema_template() {
+        let schema = Schema::from_str(
+            "NAME: string\n\
+             PORT: {type: int, default: 8080, description: HTTP listen port}\n\
+             GREETING: {type: string, default: hello world}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema_template(&schema, false),
+            "GREETING=hello world\nNAME=\n# HTTP listen port\nPORT=8080\n"
+        );
+        // Only keys with a default, each set to it
+        assert_eq!(
+            schema_template(&schema, true),
+            "GREETING=hello world\n# HTTP listen port\nPORT=8080\n"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! A small subset of JSON Schema for JSON-valued env entries.
+//!
+//! A schema field such as `{type: json, schema: {type: object, required:
+//! [enabled]}}` checks the parsed value against these keywords: `type`,
+//! `properties`, `required`, `additionalProperties` (true or false),
+//! `items`, and `enum`. Any other keyword is rejected when the schema is
+//! loaded, so an unsupported rule is never silently ignored.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The JSON value kinds a `type` keyword can name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonType {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    /// A number without a fractional part
+    Integer,
+    String,
+}
+
+impl JsonType {
+    /// Check if a value is of this kind.
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (JsonType::Null, Value::Null)
+            | (JsonType::Boolean, Value::Bool(_))
+            | (JsonType::Object, Value::Object(_))
+            | (JsonType::Array, Value::Array(_))
+            | (JsonType::Number, Value::Number(_))
+            | (JsonType::String, Value::String(_)) => true,
+            (JsonType::Integer, Value::Number(number)) => {
+                number.is_i64()
+                    || number.is_u64()
+                    || number.as_f64().is_some_and(|float| float.fract() == 0.0)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A `type` keyword: one kind or a list of accepted kinds.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+enum TypeKeyword {
+    One(JsonType),
+    Any(Vec<JsonType>),
+}
+
+impl TypeKeyword {
+    /// Check if a value is of any of the named kinds.
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            TypeKeyword::One(kind) => kind.matches(value),
+            TypeKeyword::Any(kinds) => kinds.iter().any(|kind| kind.matches(value)),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(flag: &bool) -> bool {
+    *flag
+}
+
+/// A parsed JSON Schema, limited to the supported keywords.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct JsonSchema {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    kind: Option<TypeKeyword>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, JsonSchema>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    required: Vec<String>,
+    /// Whether object keys not listed in `properties` are accepted
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    additional_properties: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    items: Option<Box<JsonSchema>>,
+    #[serde(rename = "enum", default, skip_serializing_if = "Option::is_none")]
+    allowed: Option<Vec<Value>>,
+}
+
+impl JsonSchema {
+    /// Parse a schema written in YAML, as nested in an envcraft schema file.
+    pub fn from_yaml(value: &serde_yaml::Value) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_value(value.clone())
+    }
+
+    /// Check if a JSON value satisfies this schema.
+    pub fn matches(&self, value: &Value) -> bool {
+        if self.kind.as_ref().is_some_and(|kind| !kind.matches(value)) {
+            return false;
+        }
+        if self
+            .allowed
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(value))
+        {
+            return false;
+        }
+
+        match value {
+            Value::Object(object) => {
+                self.required.iter().all(|key| object.contains_key(key))
+                    && object
+                        .iter()
+                        .all(|(key, item)| match self.properties.get(key) {
+                            Some(schema) => schema.matches(item),
+                            None => self.additional_properties,
+                        })
+            }
+            Value::Array(items) => self
+                .items
+                .as_ref()
+                .is_none_or(|schema| items.iter().all(|item| schema.matches(item))),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(yaml: &str) -> JsonSchema {
+        JsonSchema::from_yaml(&serde_yaml::from_str(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_object_schema() {
+        let flags = schema(
+            "type: object\nrequired: [enabled]\nproperties:\n  enabled: {type: boolean}\n  ratio: {type: number}\nadditionalProperties: false\n",
+        );
+
+        assert!(flags.matches(&json!({"enabled": true})));
+        assert!(flags.matches(&json!({"enabled": false, "ratio": 0.5})));
+        assert!(!flags.matches(&json!({"ratio": 0.5})));
+        assert!(!flags.matches(&json!({"enabled": "yes"})));
+        assert!(!flags.matches(&json!({"enabled": true, "other": 1})));
+        assert!(!flags.matches(&json!([true])));
+    }
+
+    #[test]
+    fn test_array_schema() {
+        let hosts = schema("type: array\nitems: {type: [string, 'null']}\n");
+
+        assert!(hosts.matches(&json!(["a", null])));
+        assert!(hosts.matches(&json!([])));
+        assert!(!hosts.matches(&json!(["a", 1])));
+    }
+
+    #[test]
+    fn test_integer_and_enum() {
+        assert!(schema("type: integer").matches(&json!(3)));
+        assert!(schema("type: integer").matches(&json!(3.0)));
+        assert!(!schema("type: integer").matches(&json!(3.5)));
+
+        let level = schema("enum: [debug, info, 1]");
+        assert!(level.matches(&json!("info")));
+        assert!(level.matches(&json!(1)));
+        assert!(!level.matches(&json!("trace")));
+    }
+
+    #[test]
+    fn test_unsupported_keyword_rejected() {
+        let yaml = serde_yaml::from_str("type: array\nmaxItems: 3\n").unwrap();
+
+        let error = JsonSchema::from_yaml(&yaml).unwrap_err().to_string();
+        assert!(error.contains("unknown field `maxItems`"), "{error}");
+    }
+}
@@ -0,0 +1,67 @@
+//! Listing the key names of a .env file.
+//!
+//! Values are never printed, so the output is safe to use for documentation
+//! or allowlists.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::cli::OutputFormat;
+use crate::filter::KeyFilter;
+use crate::parser::{EnvFile, ParseError};
+
+/// Errors that can occur during keys operation.
+#[derive(Error, Debug)]
+pub enum KeysError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("failed to serialize JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Get the distinct key names of an env file in sorted order.
+pub fn key_names(env: &EnvFile) -> Vec<&str> {
+    env.keys().map(String::as_str).collect()
+}
+
+/// Run the keys command.
+///
+/// With `filter`, only the keys it selects are listed.
+pub fn run_keys(
+    path: &Path,
+    format: OutputFormat,
+    filter: Option<&KeyFilter>,
+) -> Result<bool, KeysError> {
+    let env = EnvFile::from_path(path)?;
+    let mut keys = key_names(&env);
+    if let Some(filter) = filter {
+        keys.retain(|key| filter.matches(key));
+    }
+
+    match format {
+        OutputFormat::Text => {
+            for key in &keys {
+                println!("{key}");
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&keys)?);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_names_sorted_and_deduplicated() {
+        let env = EnvFile::from_str("ZEBRA=1\n# comment\nAPPLE=2\nZEBRA=3\nMIDDLE=").unwrap();
+
+        assert_eq!(key_names(&env), vec!["APPLE", "MIDDLE", "ZEBRA"]);
+    }
+}
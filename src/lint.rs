@@ -0,0 +1,370 @@
+//! Lint rules for .env files.
+//!
+//! Lint rules inspect the raw file content, so they can report problems
+//! that parsing and formatting would otherwise normalize away.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::parser::{self, Dialect, EnvFile, EnvLine, ParseError, ParseOptions};
+use crate::paths;
+use crate::rules::{Rule, Severities, Severity};
+
+/// Errors that can occur during lint operation.
+#[derive(Error, Debug)]
+pub enum LintError {
+    #[error("failed to read env file: {0}")]
+    ParseError(#[from] ParseError),
+}
+
+/// A single problem found by a lint rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// 1-based line number the issue refers to
+    pub line: usize,
+    /// Rule that produced the issue
+    pub rule: Rule,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Options controlling which optional lint rules run.
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    /// Longest allowed `KEY=VALUE` line in characters; `None` disables the check
+    pub max_line_length: Option<usize>,
+    /// Parse the content and report every line that fails to parse
+    pub tolerant: bool,
+}
+
+/// Check whether non-empty content is missing its final newline.
+pub fn missing_final_newline(content: &str) -> bool {
+    !content.is_empty() && !content.ends_with('\n')
+}
+
+/// Find `KEY=VALUE` lines longer than `max` characters.
+///
+/// Lengths are measured on each physical line as written, without its line
+/// terminator, since that is what line-based tools see.
+fn long_lines(content: &str, max: usize) -> Vec<LintIssue> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let length = line.chars().count();
+            if length <= max || line.trim_start().starts_with('#') {
+                return None;
+            }
+            let key = line[..parser::key_separator(line)?].trim();
+            let key = parser::strip_export(key).unwrap_or(key);
+            Some(LintIssue {
+                line: index + 1,
+                rule: Rule::LineTooLong,
+                message: format!("line for {key} is {length} characters long (max {max})"),
+            })
+        })
+        .collect()
+}
+
+/// Find the first `$VAR` or `${VAR}` reference in a value, skipping a `$`
+/// escaped with a backslash and a `$` not followed by a name.
+fn variable_reference(value: &str) -> Option<&str> {
+    let mut backslashes = 0;
+    for (index, c) in value.char_indices() {
+        if c == '$' && backslashes % 2 == 0 {
+            let rest = &value[index + 1..];
+            let (braced, name) = match rest.strip_prefix('{') {
+                Some(name) => (true, name),
+                None => (false, rest),
+            };
+            if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                let end = name
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(name.len());
+                let mut length = 1 + usize::from(braced) + end;
+                if braced && name[end..].starts_with('}') {
+                    length += 1;
+                }
+                return Some(&value[index..index + length]);
+            }
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+    }
+    None
+}
+
+/// Find values that reference another variable, like `http://$HOST/api`.
+///
+/// envcraft reads such values literally, so a reference is likely a value
+/// written for a loader that expands it. Lines are read the way such a
+/// loader reads them, so an `export` prefix is not part of the key and an
+/// inline comment is not part of the value. Single-quoted values are
+/// skipped, as shells keep them literal too.
+fn unexpanded_variables(content: &str) -> Vec<LintIssue> {
+    let options = ParseOptions {
+        dialect: Dialect::Node,
+        ..Default::default()
+    };
+    let env = EnvFile::from_str_tolerant(content, &options)
+        .expect("no key limit is set")
+        .env;
+
+    env.lines
+        .iter()
+        .filter_map(|line| {
+            let EnvLine::KeyValue {
+                key,
+                raw,
+                line: Some(line),
+                ..
+            } = line
+            else {
+                return None;
+            };
+            if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+                return None;
+            }
+            let reference = variable_reference(raw)?;
+            Some(LintIssue {
+                line: *line,
+                rule: Rule::UnexpandedVariable,
+                message: format!("value of {key} contains {reference}, which is not expanded"),
+            })
+        })
+        .collect()
+}
+
+/// Report each line that fails to parse.
+fn invalid_lines(content: &str) -> Vec<LintIssue> {
+    EnvFile::from_str_tolerant(content, &ParseOptions::default())
+        .expect("no key limit is set")
+        .errors
+        .into_iter()
+        .filter_map(|error| match error {
+            ParseError::InvalidLine {
+                line,
+                column,
+                content,
+            } => Some(LintIssue {
+                line,
+                rule: Rule::InvalidLine,
+                message: format!("invalid line format at column {column}: {content}"),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Run all lint rules over raw file content.
+pub fn lint(content: &str, options: &LintOptions) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    if let Some(max) = options.max_line_length {
+        issues.extend(long_lines(content, max));
+    }
+
+    if options.tolerant {
+        issues.extend(invalid_lines(content));
+    }
+
+    issues.extend(unexpanded_variables(content));
+
+    if missing_final_newline(content) {
+        issues.push(LintIssue {
+            line: content.lines().count(),
+            rule: Rule::MissingFinalNewline,
+            message: "missing newline at end of file".to_string(),
+        });
+    }
+
+    issues.sort_by(|a, b| a.line.cmp(&b.line).then(a.rule.cmp(&b.rule)));
+    issues
+}
+
+/// Run the lint command.
+///
+/// Issues are reported with their rule's severity: allowed rules are
+/// skipped, and only errors fail the run.
+pub fn run_lint(
+    path: &Path,
+    options: &LintOptions,
+    severities: &Severities,
+) -> Result<bool, LintError> {
+    let content = parser::read_file(path)?;
+    let mut errors = 0;
+    let mut warnings = 0;
+
+    for issue in lint(&content, options) {
+        let label = match severities.of(issue.rule) {
+            Severity::Allow => continue,
+            Severity::Warning => {
+                warnings += 1;
+                "warning: "
+            }
+            Severity::Error => {
+                errors += 1;
+                ""
+            }
+        };
+        println!(
+            "{}:{}: {label}{} [{}]",
+            paths::display(path),
+            issue.line,
+            issue.message,
+            issue.rule
+        );
+    }
+
+    if errors > 0 {
+        println!("✗ {} issue(s) found", errors + warnings);
+    } else if warnings > 0 {
+        println!("✓ no errors, {warnings} warning(s)");
+    } else {
+        println!("✓ no issues found");
+    }
+
+    Ok(errors == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_missing_final_newline() {
+        let issues = lint("A=1\nB=2", &LintOptions::default());
+
+        assert_eq!(
+            issues,
+            vec![LintIssue {
+                line: 2,
+                rule: Rule::MissingFinalNewline,
+                message: "missing newline at end of file".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_final_newline_present() {
+        assert!(lint("A=1\nB=2\n", &LintOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_empty_file() {
+        assert!(lint("", &LintOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_line_too_long() {
+        let options = LintOptions {
+            max_line_length: Some(10),
+            ..Default::default()
+        };
+        let issues = lint("SHORT=1\nLONG_KEY=value\n# a long comment line\n", &options);
+
+        assert_eq!(
+            issues,
+            vec![LintIssue {
+                line: 2,
+                rule: Rule::LineTooLong,
+                message: "line for LONG_KEY is 14 characters long (max 10)".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_line_length_within_limit() {
+        let options = LintOptions {
+            max_line_length: Some(14),
+            ..Default::default()
+        };
+
+        assert!(lint("LONG_KEY=value\n", &options).is_empty());
+        assert!(lint("LONG_KEY=value and more\n", &LintOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_line_too_long_names_exported_key() {
+        let options = LintOptions {
+            max_line_length: Some(10),
+            ..Default::default()
+        };
+        let issues = lint("export LONG_KEY=value\n", &options);
+
+        assert_eq!(
+            issues[0].message,
+            "line for LONG_KEY is 21 characters long (max 10)"
+        );
+    }
+
+    #[test]
+    fn test_lint_tolerant_reports_every_invalid_line() {
+        let options = LintOptions {
+            tolerant: true,
+            ..Default::default()
+        };
+        let issues = lint("A=1\nBROKEN\n=empty\nB=2\nALSO BROKEN\n", &options);
+
+        let lines: Vec<(usize, Rule)> = issues
+            .iter()
+            .map(|issue| (issue.line, issue.rule))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (2, Rule::InvalidLine),
+                (3, Rule::InvalidLine),
+                (5, Rule::InvalidLine)
+            ]
+        );
+        assert!(lint("BROKEN\n", &LintOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_unexpanded_variable() {
+        let content = "URL=http://$HOST/api\nBRACED=\"${HOST}:80\"\n";
+        let issues = lint(content, &LintOptions::default());
+
+        assert_eq!(
+            issues,
+            vec![
+                LintIssue {
+                    line: 1,
+                    rule: Rule::UnexpandedVariable,
+                    message: "value of URL contains $HOST, which is not expanded".to_string(),
+                },
+                LintIssue {
+                    line: 2,
+                    rule: Rule::UnexpandedVariable,
+                    message: "value of BRACED contains ${HOST}, which is not expanded".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_unexpanded_variable_exported_key() {
+        let issues = lint("export URL=$HOST # upstream\n", &LintOptions::default());
+
+        assert_eq!(
+            issues[0].message,
+            "value of URL contains $HOST, which is not expanded"
+        );
+        assert_eq!(issues.len(), 1);
+    }
+
+    #[test]
+    fn test_lint_literal_dollar_signs() {
+        let content = "ESCAPED=\\$HOST\nALONE=$\nPRICE=$5 each\nQUOTED='$HOST'\n# URL=$HOST\n";
+        assert!(lint(content, &LintOptions::default()).is_empty());
+
+        // Inline comments are not part of the value
+        let content = "A=1 # uses $HOME later\nB=\"x\" # see $HOME\n";
+        assert!(lint(content, &LintOptions::default()).is_empty());
+
+        // An escaped backslash does not escape the `$` after it
+        assert_eq!(variable_reference("a\\\\$B"), Some("$B"));
+        assert_eq!(variable_reference("${OPEN"), Some("${OPEN"));
+    }
+}
@@ -3,12 +3,34 @@
 //! A safe, deterministic CLI tool for validating, comparing, and formatting
 //! environment configuration files.
 
+mod assert;
+mod cache;
+mod check;
 mod cli;
+mod config;
+mod consistency;
+mod convert;
 mod diff;
+mod edit;
 mod error;
+mod filter;
 mod format;
+mod git;
+mod hash;
+mod init;
+mod json_schema;
+mod keys;
+mod lint;
 mod parser;
+mod paths;
+mod remote;
+mod resolve;
+mod rules;
 mod schema;
+mod schema_export;
+mod stats;
+mod typed;
+mod unified;
 
 use std::process::ExitCode;
 
@@ -16,19 +38,267 @@ use cli::{Cli, Commands};
 use error::EnvcraftError;
 
 fn main() -> ExitCode {
-    let cli = Cli::parse_args();
+    let cli = match Cli::parse_args() {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
 
     let result: Result<bool, EnvcraftError> = match cli.command {
-        Commands::Check { schema, envfile } => {
-            schema::run_check(&schema, &envfile).map_err(EnvcraftError::from)
+        Commands::Assert { file, assertions } => {
+            assert::run_assert(&file, &assertions).map_err(EnvcraftError::from)
         }
+        Commands::Check {
+            schema,
+            mut envfiles,
+            reference,
+            merge_schema,
+            values_from,
+            fix,
+            verbose,
+            explain,
+            dialect,
+            fail_fast,
+            allow_bare_keys,
+            line_continuation,
+            resolve_includes,
+            strict_escapes,
+            strict_quotes,
+            max_keys,
+            max_file_size,
+            input_encoding,
+            tolerant,
+            detect_commented,
+            write_schema,
+            emit_missing,
+            print_effective,
+            redact,
+            report_file,
+            summary_line,
+            severity,
+        } => {
+            let options = check::CheckOptions {
+                explain,
+                verbose,
+                parse: parser::ParseOptions {
+                    dialect,
+                    allow_bare_keys,
+                    line_continuation,
+                    includes: resolve_includes,
+                    encoding: input_encoding,
+                    strict_escapes,
+                    strict_quotes,
+                    max_keys,
+                    max_file_size,
+                },
+                tolerant,
+                fail_fast,
+                merge_schemas: merge_schema,
+                severities: severity.severities(),
+                emit_missing,
+                print_effective,
+                redact,
+                report_file,
+                summary_line,
+                detect_commented,
+                write_schema,
+                values_from,
+                fix,
+            };
+            // With --reference there is no schema file, so the first
+            // positional argument is already an env file
+            let source = match (reference, schema) {
+                (Some(reference), schema) => {
+                    envfiles.splice(0..0, schema);
+                    check::SchemaSource::Reference(reference)
+                }
+                (None, Some(schema)) => check::SchemaSource::File(schema),
+                (None, None) => unreachable!("clap requires SCHEMA without --reference"),
+            };
+            check::run_check(&source, &envfiles, &options).map_err(EnvcraftError::from)
+        }
+        Commands::CheckConsistency { files, keys } => {
+            consistency::run_check_consistency(&files, &keys).map_err(EnvcraftError::from)
+        }
+        Commands::Convert {
+            file,
+            to,
+            nest_on,
+            pretty,
+            dialect,
+            filter,
+        } => convert::run_convert(
+            &file,
+            to,
+            nest_on.as_deref(),
+            pretty,
+            dialect,
+            filter.as_ref(),
+        )
+        .map_err(EnvcraftError::from),
         Commands::Diff {
             file1,
             file2,
+            more_files,
+            env: _,
+            matrix,
+            file_keys_only,
+            report_file,
             redact,
-        } => diff::run_diff(&file1, &file2, redact).map_err(EnvcraftError::from),
-        Commands::Format { file, in_place } => {
-            format::run_format(&file, in_place).map_err(EnvcraftError::from)
+            redact_hash,
+            redact_key,
+            mask_style,
+            mask_char,
+            raw,
+            git,
+            schema,
+            type_aware,
+            fold_case_values,
+            format,
+            as_patch,
+            context,
+            show_unchanged,
+            unified,
+            sort,
+            filter,
+        } => {
+            let redact = if redact_hash {
+                diff::Redaction::Hash
+            } else if mask_style != diff::MaskStyle::Hide {
+                mask_style.redaction(mask_char)
+            } else if redact {
+                diff::Redaction::Hide
+            } else {
+                diff::Redaction::Off
+            };
+            let options = diff::DiffOptions {
+                redact,
+                redact_keys: redact_key,
+                raw,
+                git,
+                schema,
+                type_aware,
+                fold_case_values,
+                format,
+                as_patch,
+                context,
+                show_unchanged,
+                unified,
+                sort,
+                filter,
+                file_keys_only,
+                report_file,
+            };
+            if matrix {
+                let paths: Vec<_> = [file1].into_iter().chain(file2).chain(more_files).collect();
+                diff::run_matrix(&paths, &options).map_err(EnvcraftError::from)
+            } else {
+                diff::run_diff(&file1, file2.as_deref(), &options).map_err(EnvcraftError::from)
+            }
+        }
+        Commands::Format {
+            file,
+            in_place,
+            check,
+            ignore_comments,
+            backup,
+            backup_suffix,
+            align_comments,
+            sort_values,
+            group_by_prefix,
+            preserve_blank_lines,
+            blank_lines,
+            no_trailing_blank,
+            normalize_keys,
+            on_duplicate,
+            sort,
+            export,
+            schema,
+        } => {
+            let mode = if check {
+                format::FormatMode::Check { ignore_comments }
+            } else if in_place {
+                format::FormatMode::InPlace {
+                    backup_suffix: backup.then_some(backup_suffix),
+                }
+            } else {
+                format::FormatMode::Stdout
+            };
+            let options = format::FormatOptions {
+                align_comments,
+                sort_values,
+                group_by_prefix,
+                preserve_blank_lines,
+                blank_lines,
+                no_trailing_blank,
+                normalize_keys,
+                on_duplicate,
+                sort,
+                export,
+                schema,
+            };
+            format::run_format(&file, &mode, &options).map_err(EnvcraftError::from)
+        }
+        Commands::Hash { file } => hash::run_hash(&file).map_err(EnvcraftError::from),
+        Commands::Init {
+            dir,
+            force,
+            schema,
+            defaults_only,
+        } => match schema {
+            Some(schema) => init::run_init_from_schema(&schema, defaults_only),
+            None => init::run_init(&dir, force),
+        }
+        .map_err(EnvcraftError::from),
+        Commands::Keys {
+            file,
+            format,
+            filter,
+        } => keys::run_keys(&file, format, filter.as_ref()).map_err(EnvcraftError::from),
+        Commands::Lint {
+            file,
+            max_line_length,
+            tolerant,
+            severity,
+        } => {
+            let options = lint::LintOptions {
+                max_line_length,
+                tolerant,
+            };
+            lint::run_lint(&file, &options, &severity.severities()).map_err(EnvcraftError::from)
+        }
+        Commands::Rename {
+            file,
+            old_name,
+            new_name,
+            force,
+            dry_run,
+        } => edit::run_rename(&file, &old_name, &new_name, force, dry_run)
+            .map_err(EnvcraftError::from),
+        Commands::Resolve { dir, mode } => {
+            resolve::run_resolve(&dir, mode.as_deref()).map_err(EnvcraftError::from)
+        }
+        Commands::Set {
+            file,
+            key,
+            value,
+            dry_run,
+        } => edit::run_set(&file, &key, &value, dry_run).map_err(EnvcraftError::from),
+        Commands::Unset {
+            file,
+            key,
+            with_comments,
+            strict,
+            dry_run,
+        } => edit::run_unset(&file, &key, with_comments, strict, dry_run)
+            .map_err(EnvcraftError::from),
+        Commands::UseSchema { name, schema } => {
+            cache::run_use_schema(&name, &schema).map_err(EnvcraftError::from)
+        }
+        Commands::Stats { file, format } => {
+            stats::run_stats(&file, format).map_err(EnvcraftError::from)
         }
     };
 
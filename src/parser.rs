@@ -3,9 +3,10 @@
 //! Provides deterministic parsing of environment files with support for
 //! comments and standard KEY=VALUE format.
 
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
@@ -15,8 +16,54 @@ pub enum ParseError {
     #[error("failed to read file: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error("invalid line format at line {line}: {content}")]
-    InvalidLine { line: usize, content: String },
+    #[error("invalid line format at line {line}, column {column}: {content}")]
+    InvalidLine {
+        line: usize,
+        /// 1-based character offset where the problem starts
+        column: usize,
+        content: String,
+    },
+
+    #[error("{} is not valid UTF-8 (invalid byte at offset {offset}); save the file as UTF-8", path.display())]
+    NotUtf8 { path: PathBuf, offset: usize },
+
+    #[error("{} is not valid {encoding}", path.display())]
+    InvalidEncoding {
+        path: PathBuf,
+        encoding: &'static str,
+    },
+
+    #[error("unknown escape sequence '\\{escape}' at line {line}, column {column}")]
+    UnknownEscape {
+        line: usize,
+        /// 1-based character offset of the backslash
+        column: usize,
+        escape: char,
+    },
+
+    #[error("unmatched quote {quote} at line {line}, column {column}")]
+    MismatchedQuote {
+        line: usize,
+        /// 1-based character offset of the unmatched quote
+        column: usize,
+        quote: char,
+    },
+
+    #[error("{} is {size} bytes, over the --max-file-size limit of {limit} bytes", path.display())]
+    FileTooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+
+    #[error("more than {limit} keys defined (limit exceeded at line {line}); raise --max-keys if this is expected")]
+    TooManyKeys { limit: usize, line: usize },
+
+    #[error("included file not found at line {line}: {}", path.display())]
+    IncludeNotFound { line: usize, path: PathBuf },
+
+    #[error("include cycle: {0}")]
+    IncludeCycle(String),
 }
 
 /// Represents a parsed line from a .env file.
@@ -27,7 +74,132 @@ pub enum EnvLine {
     /// A blank/empty line
     Blank,
     /// A key-value pair
-    KeyValue { key: String, value: String },
+    KeyValue {
+        key: String,
+        /// Whether the key was written in double quotes (e.g. `"my=key"=1`)
+        quoted_key: bool,
+        value: String,
+        /// Value text as written, before quote stripping
+        raw: String,
+        /// Inline comment following a quoted value (e.g. `# note`)
+        comment: Option<String>,
+        /// 1-based line number of the definition, if it was read from text
+        line: Option<usize>,
+    },
+}
+
+/// A dotenv dialect: the parsing rules of a particular loader.
+///
+/// - `envcraft`: quotes are stripped and nothing else is interpreted.
+/// - `node`: ignores an `export` prefix, ends unquoted values at ` #`, and
+///   expands `\n` / `\r` inside double quotes. No variable expansion.
+/// - `posix`: ignores an `export` prefix, ends unquoted values at ` #`,
+///   processes shell backslash escapes, and expands `$VAR` / `${VAR}`
+///   outside single quotes.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// envcraft's own rules: quotes are stripped, values are otherwise literal
+    #[default]
+    Envcraft,
+    /// The `dotenv` npm package
+    Node,
+    /// A POSIX shell sourcing the file (`set -a; . ./.env`)
+    Posix,
+}
+
+impl Dialect {
+    /// Check if an `export ` prefix before the key is ignored.
+    fn strips_export(&self) -> bool {
+        matches!(self, Dialect::Node | Dialect::Posix)
+    }
+
+    /// Check if whitespace followed by `#` ends an unquoted value.
+    fn unquoted_comments(&self) -> bool {
+        matches!(self, Dialect::Node | Dialect::Posix)
+    }
+
+    /// Check if a backslash escapes a double quote inside double quotes.
+    fn escapes_quotes(&self) -> bool {
+        matches!(self, Dialect::Node | Dialect::Posix)
+    }
+
+    /// Get the characters a backslash may escape inside double quotes, or
+    /// `None` if the dialect processes no escapes.
+    pub fn double_quoted_escapes(&self) -> Option<&'static [char]> {
+        match self {
+            Dialect::Envcraft => None,
+            Dialect::Node => Some(&['n', 'r', '"']),
+            Dialect::Posix => Some(&['\\', '"', '$', '`']),
+        }
+    }
+}
+
+/// The character encoding env files are read in.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// UTF-8; anything else is rejected
+    #[default]
+    #[value(name = "utf-8")]
+    Utf8,
+    /// ISO-8859-1, read as its superset Windows-1252
+    Latin1,
+    /// UTF-16, little-endian unless a byte order mark says otherwise
+    #[value(name = "utf-16")]
+    Utf16,
+}
+
+impl InputEncoding {
+    /// Get the encoding's name as used in messages.
+    fn name(&self) -> &'static str {
+        match self {
+            InputEncoding::Utf8 => "UTF-8",
+            InputEncoding::Latin1 => "Latin-1",
+            InputEncoding::Utf16 => "UTF-16",
+        }
+    }
+}
+
+/// Options controlling how .env content is parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Dialect whose quoting, escaping, and expansion rules apply
+    pub dialect: Dialect,
+    /// Treat a line holding only a key name (no `=`) as `KEY=` with an
+    /// empty value instead of rejecting it
+    pub allow_bare_keys: bool,
+    /// Join a line ending in an unescaped `\` with the line that follows
+    pub line_continuation: bool,
+    /// Resolve `#include <path>` comment lines when parsing from a path
+    pub includes: bool,
+    /// Encoding files are read in; they are transcoded to UTF-8 first
+    pub encoding: InputEncoding,
+    /// Reject backslash escapes the dialect does not recognize inside
+    /// double quotes instead of keeping them literally
+    pub strict_escapes: bool,
+    /// Reject values that open a quote without closing it, or close one
+    /// that was never opened, instead of keeping the quote literally
+    pub strict_quotes: bool,
+    /// Largest number of key definitions a file may hold
+    pub max_keys: Option<usize>,
+    /// Largest size in bytes of a file read from a path
+    pub max_file_size: Option<u64>,
+}
+
+/// Comment prefix that includes another file when `includes` is set.
+const INCLUDE_DIRECTIVE: &str = "#include ";
+
+/// Files being parsed while resolving includes, outermost first: the
+/// canonical path, used to detect cycles, and the path as given.
+type IncludeStack = Vec<(PathBuf, PathBuf)>;
+
+/// An env file parsed in tolerant mode, together with the lines that
+/// failed to parse.
+#[derive(Debug)]
+pub struct PartialEnvFile {
+    /// Everything that parsed; malformed lines are left out
+    pub env: EnvFile,
+    /// One error per malformed line, in file order
+    pub errors: Vec<ParseError>,
 }
 
 /// Represents a fully parsed .env file.
@@ -42,19 +214,149 @@ pub struct EnvFile {
 impl EnvFile {
     /// Parse a .env file from a path.
     pub fn from_path(path: &Path) -> Result<Self, ParseError> {
-        let content = fs::read_to_string(path)?;
-        Self::from_str(&content)
+        Self::from_path_with(path, &ParseOptions::default())
+    }
+
+    /// Parse a .env file from a path with explicit parse options.
+    ///
+    /// With `includes`, each `#include <path>` line is replaced by the keys
+    /// of that file, resolved relative to the including file. Keys defined
+    /// after the directive override included ones, and included keys
+    /// override earlier ones. Only `entries` holds included keys; `lines`
+    /// always describes this file alone.
+    pub fn from_path_with(path: &Path, options: &ParseOptions) -> Result<Self, ParseError> {
+        if options.includes {
+            return Self::from_path_including(path, options, &mut IncludeStack::new());
+        }
+
+        let content = read_limited(path, options)?;
+        Self::from_str_with(&content, options)
+    }
+
+    /// Parse a file whose includes are being resolved, failing on a cycle.
+    fn from_path_including(
+        path: &Path,
+        options: &ParseOptions,
+        stack: &mut IncludeStack,
+    ) -> Result<Self, ParseError> {
+        let content = read_limited(path, options)?;
+        let canonical = fs::canonicalize(path)?;
+
+        if let Some(start) = stack.iter().position(|(seen, _)| *seen == canonical) {
+            let chain: Vec<String> = stack[start..]
+                .iter()
+                .map(|(_, given)| given.display().to_string())
+                .chain([path.display().to_string()])
+                .collect();
+            return Err(ParseError::IncludeCycle(chain.join(" -> ")));
+        }
+
+        stack.push((canonical, path.to_path_buf()));
+        let env = Self::parse(&content, options, Some(stack), None);
+        stack.pop();
+        env
     }
 
     /// Parse a .env file from a string.
     pub fn from_str(content: &str) -> Result<Self, ParseError> {
+        Self::from_str_with(content, &ParseOptions::default())
+    }
+
+    /// Parse a .env file from a string with explicit parse options.
+    ///
+    /// Include directives are left as comments, as there is no file to
+    /// resolve them against.
+    pub fn from_str_with(content: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        Self::parse(content, options, None, None)
+    }
+
+    /// Parse a .env file from a string, collecting malformed lines instead
+    /// of stopping at the first one.
+    ///
+    /// Exceeding `max_keys` still stops parsing with an error.
+    pub fn from_str_tolerant(
+        content: &str,
+        options: &ParseOptions,
+    ) -> Result<PartialEnvFile, ParseError> {
+        let mut errors = Vec::new();
+        let env = Self::parse(content, options, None, Some(&mut errors))?;
+        Ok(PartialEnvFile { env, errors })
+    }
+
+    /// Parse a .env file from a path, collecting malformed lines instead of
+    /// stopping at the first one.
+    ///
+    /// Only lines of this file are collected: a file that cannot be read
+    /// and any problem with an included file are still returned as errors.
+    pub fn from_path_tolerant(
+        path: &Path,
+        options: &ParseOptions,
+    ) -> Result<PartialEnvFile, ParseError> {
+        let content = read_limited(path, options)?;
+        let mut errors = Vec::new();
+        let env = if options.includes {
+            let mut stack = vec![(fs::canonicalize(path)?, path.to_path_buf())];
+            Self::parse(&content, options, Some(&mut stack), Some(&mut errors))?
+        } else {
+            Self::parse(&content, options, None, Some(&mut errors))?
+        };
+        Ok(PartialEnvFile { env, errors })
+    }
+
+    /// Parse content, resolving includes relative to the top of `includes`.
+    ///
+    /// With `errors`, malformed lines are pushed there and skipped;
+    /// otherwise the first one is returned. Going over `max_keys` is always
+    /// returned, before the rest of the content is parsed.
+    fn parse(
+        content: &str,
+        options: &ParseOptions,
+        mut includes: Option<&mut IncludeStack>,
+        mut errors: Option<&mut Vec<ParseError>>,
+    ) -> Result<Self, ParseError> {
         let mut lines = Vec::new();
         let mut entries = BTreeMap::new();
+        let mut definitions = 0;
 
-        for (line_num, line) in content.lines().enumerate() {
-            let parsed = parse_line(line, line_num + 1)?;
+        for (line_num, line) in logical_lines(content, options.line_continuation) {
+            let parsed = match (parse_line(&line, line_num, options, &entries), &mut errors) {
+                (Ok(parsed), _) => parsed,
+                (Err(error), Some(errors)) => {
+                    errors.push(error);
+                    continue;
+                }
+                (Err(error), None) => return Err(error),
+            };
 
-            if let EnvLine::KeyValue { ref key, ref value } = parsed {
+            if let (EnvLine::Comment(text), Some(stack)) = (&parsed, includes.as_deref_mut()) {
+                if let Some(target) = text.trim().strip_prefix(INCLUDE_DIRECTIVE) {
+                    let (_, current) = stack.last().expect("including file is on the stack");
+                    let path = current
+                        .parent()
+                        .unwrap_or(Path::new(""))
+                        .join(target.trim());
+                    if !path.is_file() {
+                        return Err(ParseError::IncludeNotFound {
+                            line: line_num,
+                            path,
+                        });
+                    }
+                    let included = Self::from_path_including(&path, options, stack)?;
+                    entries.extend(included.entries);
+                }
+            }
+
+            if let EnvLine::KeyValue {
+                ref key, ref value, ..
+            } = parsed
+            {
+                definitions += 1;
+                if let Some(limit) = options.max_keys.filter(|&limit| definitions > limit) {
+                    return Err(ParseError::TooManyKeys {
+                        limit,
+                        line: line_num,
+                    });
+                }
                 entries.insert(key.clone(), value.clone());
             }
 
@@ -70,7 +372,6 @@ impl EnvFile {
     }
 
     /// Check if a key exists.
-    #[allow(dead_code)]
     pub fn contains_key(&self, key: &str) -> bool {
         self.entries.contains_key(key)
     }
@@ -79,10 +380,178 @@ impl EnvFile {
     pub fn keys(&self) -> impl Iterator<Item = &String> {
         self.entries.keys()
     }
+
+    /// Build a file from key-value pairs, one `KEY=VALUE` line per pair.
+    ///
+    /// Values are taken literally, so each raw value equals its value.
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (String, String)>) -> Self {
+        let entries: BTreeMap<String, String> = pairs.into_iter().collect();
+        let lines = entries
+            .iter()
+            .map(|(key, value)| EnvLine::KeyValue {
+                key: key.clone(),
+                quoted_key: false,
+                value: value.clone(),
+                raw: value.clone(),
+                comment: None,
+                line: None,
+            })
+            .collect();
+
+        EnvFile { lines, entries }
+    }
+
+    /// Get the keys of definitions that are commented out, such as
+    /// `# PORT=8080` or `#export PORT=8080`.
+    ///
+    /// Only comments whose text before the `=` is a plain key name count, so
+    /// prose like `# Note: set PORT=80` is not mistaken for a definition.
+    pub fn commented_keys(&self) -> BTreeSet<String> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                EnvLine::Comment(text) => {
+                    let text = text.trim_start().trim_start_matches('#').trim_start();
+                    let key = text[..key_separator(text)?].trim_end();
+                    let key = key.strip_prefix("export ").unwrap_or(key).trim_start();
+                    is_identifier(key).then(|| key.to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get key-value pairs using the raw value text (quotes included).
+    ///
+    /// Later definitions of a key override earlier ones, matching `entries`.
+    pub fn raw_entries(&self) -> BTreeMap<String, String> {
+        let mut raw_entries = BTreeMap::new();
+
+        for line in &self.lines {
+            if let EnvLine::KeyValue { key, raw, .. } = line {
+                raw_entries.insert(key.clone(), raw.clone());
+            }
+        }
+
+        raw_entries
+    }
+}
+
+/// Read a file that must be UTF-8 encoded.
+pub fn read_file(path: &Path) -> Result<String, ParseError> {
+    read_file_as(path, InputEncoding::Utf8)
+}
+
+/// Read a file as `options` describe, failing before it is read if it is
+/// larger than `max_file_size`.
+fn read_limited(path: &Path, options: &ParseOptions) -> Result<String, ParseError> {
+    if let Some(limit) = options.max_file_size {
+        let size = fs::metadata(path)?.len();
+        if size > limit {
+            return Err(ParseError::FileTooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit,
+            });
+        }
+    }
+    read_file_as(path, options.encoding)
+}
+
+/// Parse a `--max-file-size` limit: a byte count, optionally with a unit
+/// such as `10MB` or `1MiB`.
+pub fn parse_size_limit(text: &str) -> Result<u64, String> {
+    crate::schema::parse_byte_size(text, true)
+        .ok_or_else(|| format!("expected a size such as 1048576, 10MB, or 1MiB, got '{text}'"))
+}
+
+/// Byte order mark at the start of some UTF-8 files.
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Read a file in the given encoding, transcoding it to UTF-8.
+///
+/// A byte order mark of the encoding is removed, and for UTF-16 it picks the
+/// byte order. Nothing else is sniffed, so a Latin-1 file is never read as
+/// UTF-8. Bytes that are not valid in the encoding are an error rather than
+/// being replaced.
+pub fn read_file_as(path: &Path, encoding: InputEncoding) -> Result<String, ParseError> {
+    decode(fs::read(path)?, encoding, path)
+}
+
+/// Read a UTF-8 file like `read_file`, also telling whether it started with
+/// a byte order mark, so an edit can write the mark back.
+pub fn read_file_keeping_bom(path: &Path) -> Result<(String, bool), ParseError> {
+    let bytes = fs::read(path)?;
+    let bom = bytes.starts_with(UTF8_BOM);
+    Ok((decode(bytes, InputEncoding::Utf8, path)?, bom))
+}
+
+/// Decode input that was not read from a file, such as stdin or a fetched
+/// URL, the way `options` read a file: it may be no larger than
+/// `max_file_size` and is transcoded from `encoding`. `name` stands for the
+/// input in error messages.
+pub fn decode_input(
+    bytes: Vec<u8>,
+    name: &Path,
+    options: &ParseOptions,
+) -> Result<String, ParseError> {
+    if let Some(limit) = options.max_file_size {
+        let size = bytes.len() as u64;
+        if size > limit {
+            return Err(ParseError::FileTooLarge {
+                path: name.to_path_buf(),
+                size,
+                limit,
+            });
+        }
+    }
+    decode(bytes, options.encoding, name)
+}
+
+/// Transcode the bytes of `path` from the given encoding to UTF-8, as
+/// `read_file_as` describes.
+fn decode(mut bytes: Vec<u8>, encoding: InputEncoding, path: &Path) -> Result<String, ParseError> {
+    let (decoder, bom_len) = match encoding {
+        InputEncoding::Utf8 => {
+            let bom_len = if bytes.starts_with(UTF8_BOM) {
+                UTF8_BOM.len()
+            } else {
+                0
+            };
+            bytes.drain(..bom_len);
+            return String::from_utf8(bytes).map_err(|e| ParseError::NotUtf8 {
+                path: path.to_path_buf(),
+                offset: bom_len + e.utf8_error().valid_up_to(),
+            });
+        }
+        InputEncoding::Latin1 => (encoding_rs::WINDOWS_1252, 0),
+        InputEncoding::Utf16 => match encoding_rs::Encoding::for_bom(&bytes) {
+            Some((bom_encoding, bom_len)) if bom_encoding != encoding_rs::UTF_8 => {
+                (bom_encoding, bom_len)
+            }
+            _ => (encoding_rs::UTF_16LE, 0),
+        },
+    };
+    let (content, had_errors) = decoder.decode_without_bom_handling(&bytes[bom_len..]);
+    if had_errors {
+        return Err(ParseError::InvalidEncoding {
+            path: path.to_path_buf(),
+            encoding: encoding.name(),
+        });
+    }
+    Ok(content.into_owned())
 }
 
 /// Parse a single line from a .env file.
-fn parse_line(line: &str, line_num: usize) -> Result<EnvLine, ParseError> {
+///
+/// `defined` holds the keys parsed so far, used for variable expansion.
+fn parse_line(
+    line: &str,
+    line_num: usize,
+    options: &ParseOptions,
+    defined: &BTreeMap<String, String>,
+) -> Result<EnvLine, ParseError> {
+    let dialect = options.dialect;
     let trimmed = line.trim();
 
     // Empty line
@@ -96,31 +565,342 @@ fn parse_line(line: &str, line_num: usize) -> Result<EnvLine, ParseError> {
     }
 
     // Key-value line
-    if let Some(eq_pos) = line.find('=') {
-        let key = line[..eq_pos].trim().to_string();
-        let value = line[eq_pos + 1..].trim().to_string();
+    if let Some(eq_pos) = key_separator(line) {
+        let mut key = line[..eq_pos].trim().to_string();
+        let (raw, comment) = split_inline_comment(line[eq_pos + 1..].trim(), dialect);
+
+        if dialect.strips_export() {
+            if let Some(rest) = strip_export(&key) {
+                key = rest.to_string();
+            }
+        }
+
+        // A quoted key may contain `=`; the quotes are not part of the key
+        let quoted_key = key.len() >= 2 && key.starts_with('"') && key.ends_with('"');
+        if quoted_key {
+            key = key[1..key.len() - 1].to_string();
+        }
 
-        // Validate key is not empty
+        // Validate key is not empty; point at where the key should start
         if key.is_empty() {
             return Err(ParseError::InvalidLine {
                 line: line_num,
+                column: column_of(line, line.len() - line.trim_start().len()),
                 content: line.to_string(),
             });
         }
 
+        if options.strict_escapes {
+            if let Some((offset, escape)) = unknown_escape(&raw, dialect) {
+                let raw_start = eq_pos + 1 + line[eq_pos + 1..].find(&raw).unwrap_or(0);
+                return Err(ParseError::UnknownEscape {
+                    line: line_num,
+                    column: column_of(line, raw_start + offset),
+                    escape,
+                });
+            }
+        }
+
+        if options.strict_quotes {
+            if let Some((offset, quote)) = mismatched_quote(&raw) {
+                let raw_start = eq_pos + 1 + line[eq_pos + 1..].find(&raw).unwrap_or(0);
+                return Err(ParseError::MismatchedQuote {
+                    line: line_num,
+                    column: column_of(line, raw_start + offset),
+                    quote,
+                });
+            }
+        }
+
         // Remove surrounding quotes from value if present
-        let value = strip_quotes(&value);
+        let value = match dialect {
+            Dialect::Envcraft => strip_quotes(&raw),
+            Dialect::Node => unquote_node(&raw),
+            Dialect::Posix => unquote_posix(&raw, defined),
+        };
 
-        return Ok(EnvLine::KeyValue { key, value });
+        return Ok(EnvLine::KeyValue {
+            key,
+            quoted_key,
+            value,
+            raw,
+            comment,
+            line: Some(line_num),
+        });
     }
 
-    // Invalid line (no = sign and not a comment or blank)
+    // Bare key (no = sign), accepted only when asked for
+    if options.allow_bare_keys && is_identifier(trimmed) {
+        return Ok(EnvLine::KeyValue {
+            key: trimmed.to_string(),
+            quoted_key: false,
+            value: String::new(),
+            raw: String::new(),
+            comment: None,
+            line: Some(line_num),
+        });
+    }
+
+    // Invalid line (no = sign and not a comment or blank); the missing `=`
+    // is reported just past the end of the line
     Err(ParseError::InvalidLine {
         line: line_num,
+        column: column_of(line, line.trim_end().len()),
         content: line.to_string(),
     })
 }
 
+/// Get the key after an `export ` prefix, or `None` if there is no prefix.
+pub fn strip_export(key: &str) -> Option<&str> {
+    key.strip_prefix("export")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// Find the `=` that separates the key from the value on a line.
+///
+/// A key written in double quotes, like `"WEIRD=KEY"=value`, may contain
+/// `=`; the separator is the first `=` after the closing quote. Otherwise
+/// it is the first `=` on the line.
+pub fn key_separator(line: &str) -> Option<usize> {
+    let start = line.len() - line.trim_start().len();
+
+    if let Some(quoted) = line[start..].strip_prefix('"') {
+        if let Some(close) = quoted.find('"') {
+            let after = start + 1 + close + 1;
+            let rest = &line[after..];
+            if rest.trim_start().starts_with('=') {
+                return Some(after + rest.len() - rest.trim_start().len());
+            }
+        }
+    }
+
+    line.find('=')
+}
+
+/// Render a key so it parses back unchanged, quoting keys that contain `=`.
+pub fn render_key(key: &str) -> Cow<'_, str> {
+    if key.contains('=') {
+        Cow::Owned(format!("\"{key}\""))
+    } else {
+        Cow::Borrowed(key)
+    }
+}
+
+/// Split content into logical lines, each with its 1-based starting line number.
+///
+/// With `continuation`, a line ending in an odd number of backslashes goes on
+/// to the next line: the last backslash is dropped and the lines are joined.
+/// Pairs of backslashes at the end of a line stand for one literal backslash.
+fn logical_lines(content: &str, continuation: bool) -> Vec<(usize, String)> {
+    let mut logical = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        if !continuation {
+            logical.push((index + 1, line.to_string()));
+            continue;
+        }
+
+        let (start, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        let body = line.trim_end_matches('\\');
+        let backslashes = line.len() - body.len();
+        text.push_str(body);
+        text.push_str(&"\\".repeat(backslashes / 2));
+
+        if backslashes % 2 == 1 {
+            pending = Some((start, text));
+        } else {
+            logical.push((start, text));
+        }
+    }
+
+    logical.extend(pending);
+    logical
+}
+
+/// Check if text is a plain key name: letters, digits, and underscores, not
+/// starting with a digit.
+pub fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Convert a byte offset within a line into a 1-based character column.
+fn column_of(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset].chars().count() + 1
+}
+
+/// Split an inline comment off a value.
+///
+/// A `#` after the closing quote of a quoted value starts a comment. In
+/// unquoted values `#` is part of the value, unless the dialect treats
+/// whitespace followed by `#` as the start of a comment.
+fn split_inline_comment(value: &str, dialect: Dialect) -> (String, Option<String>) {
+    if let Some(quote @ ('"' | '\'')) = value.chars().next() {
+        let escapes = quote == '"' && dialect.escapes_quotes();
+        if let Some(close) = find_closing_quote(value, quote, escapes) {
+            let rest = value[close + 1..].trim_start();
+
+            if rest.starts_with('#') {
+                return (value[..=close].to_string(), Some(rest.to_string()));
+            }
+        }
+    } else if dialect.unquoted_comments() {
+        if let Some(pos) = value.find([' ', '\t']).and_then(|start| {
+            value[start..]
+                .find(|c: char| !c.is_whitespace())
+                .map(|offset| start + offset)
+                .filter(|&pos| value[pos..].starts_with('#'))
+        }) {
+            return (
+                value[..pos].trim_end().to_string(),
+                Some(value[pos..].to_string()),
+            );
+        }
+    }
+
+    (value.to_string(), None)
+}
+
+/// Find the byte index of the quote closing a value that starts with `quote`.
+fn find_closing_quote(value: &str, quote: char, escapes: bool) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if escapes && c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// Find the first escape in a double-quoted value that the dialect does not
+/// recognize, as the byte offset of its backslash and the escaped character.
+fn unknown_escape(raw: &str, dialect: Dialect) -> Option<(usize, char)> {
+    let known = dialect.double_quoted_escapes()?;
+    if !(raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"')) {
+        return None;
+    }
+
+    let mut chars = raw[1..raw.len() - 1].char_indices();
+    while let Some((offset, c)) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if known.contains(&next) => {}
+                Some((_, next)) => return Some((offset + 1, next)),
+                None => {}
+            }
+        }
+    }
+
+    None
+}
+
+/// Find a quote at either end of a value that has no partner at the other
+/// end, as its byte offset and the quote character.
+///
+/// A value ending in a quote is only suspect when it holds no other quote
+/// of that kind, so `say "hi"` is not reported.
+fn mismatched_quote(raw: &str) -> Option<(usize, char)> {
+    let first = raw.chars().next()?;
+    let last = raw.chars().next_back()?;
+
+    if matches!(first, '"' | '\'') && (raw.len() == 1 || last != first) {
+        return Some((0, first));
+    }
+    if matches!(last, '"' | '\'') && last != first && raw.matches(last).count() == 1 {
+        return Some((raw.len() - 1, last));
+    }
+
+    None
+}
+
+/// Unquote a value following the `dotenv` npm package.
+///
+/// Double-quoted values expand `\n` and `\r`; nothing is interpolated.
+fn unquote_node(raw: &str) -> String {
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        return raw[1..raw.len() - 1]
+            .replace("\\n", "\n")
+            .replace("\\r", "\r");
+    }
+
+    strip_quotes(raw)
+}
+
+/// Unquote a value following POSIX shell rules.
+///
+/// Single-quoted values are literal. Double-quoted and unquoted values
+/// process backslash escapes and expand `$VAR` / `${VAR}` from keys defined
+/// earlier in the file; unknown variables expand to an empty string. The
+/// process environment is never consulted, so results stay deterministic.
+fn unquote_posix(raw: &str, defined: &BTreeMap<String, String>) -> String {
+    if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+        return raw[1..raw.len() - 1].to_string();
+    }
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        return expand_posix(&raw[1..raw.len() - 1], defined, true);
+    }
+
+    expand_posix(raw, defined, false)
+}
+
+/// Process shell escapes and variable references in a value.
+fn expand_posix(text: &str, defined: &BTreeMap<String, String>, in_double_quotes: bool) -> String {
+    let mut output = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // Inside double quotes only a few characters can be escaped
+                Some(next) if !in_double_quotes || matches!(next, '\\' | '"' | '$' | '`') => {
+                    output.push(next);
+                }
+                Some(next) => {
+                    output.push('\\');
+                    output.push(next);
+                }
+                None => output.push('\\'),
+            },
+            '$' => {
+                let name: String = if chars.peek() == Some(&'{') {
+                    chars.next();
+                    chars.by_ref().take_while(|&c| c != '}').collect()
+                } else {
+                    let mut name = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if !(c.is_ascii_alphanumeric() || c == '_') {
+                            break;
+                        }
+                        name.push(c);
+                        chars.next();
+                    }
+                    name
+                };
+
+                if name.is_empty() {
+                    output.push('$');
+                } else if let Some(value) = defined.get(&name) {
+                    output.push_str(value);
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
 /// Remove surrounding quotes from a value if they match.
 fn strip_quotes(value: &str) -> String {
     let trimmed = value.trim();
@@ -198,6 +978,35 @@ KEY3=value3
         assert_eq!(env.get("EMPTY"), Some(&"".to_string()));
     }
 
+    #[test]
+    fn test_parse_unicode_keys_and_values() {
+        let content = "CAFÉ=value\nKEY=naïve\nGREETING=\"こんにちは\"\n";
+        let env = EnvFile::from_str(content).unwrap();
+
+        assert_eq!(env.get("CAFÉ"), Some(&"value".to_string()));
+        assert_eq!(env.get("KEY"), Some(&"naïve".to_string()));
+        assert_eq!(env.get("GREETING"), Some(&"こんにちは".to_string()));
+        // Columns count characters, not bytes
+        let error = EnvFile::from_str("CAFÉ=1\nNAÏVE\n").unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::InvalidLine {
+                line: 2,
+                column: 6,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_commented_keys() {
+        let content = "# PORT=8080\n#export HOST=x\n## DEBUG = true\n# Note: set X=1\nNAME=app\n";
+        let env = EnvFile::from_str(content).unwrap();
+
+        let keys: Vec<String> = env.commented_keys().into_iter().collect();
+        assert_eq!(keys, ["DEBUG", "HOST", "PORT"]);
+    }
+
     #[test]
     fn test_parse_preserves_line_structure() {
         let content = "# Comment\nKEY=value\n\n# Another";
@@ -223,6 +1032,377 @@ KEY3=value3
         }
     }
 
+    #[test]
+    fn test_tolerant_collects_every_invalid_line() {
+        let content = "A=1\nBROKEN\nB=2\n=empty\nALSO BROKEN\nC=3\n";
+        let partial = EnvFile::from_str_tolerant(content, &ParseOptions::default()).unwrap();
+
+        let lines: Vec<usize> = partial
+            .errors
+            .iter()
+            .map(|error| match error {
+                ParseError::InvalidLine { line, .. } => *line,
+                other => panic!("Expected InvalidLine error, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+        assert_eq!(partial.env.entries.len(), 3);
+        assert_eq!(partial.env.lines.len(), 3);
+        assert_eq!(partial.env.get("C"), Some(&"3".to_string()));
+    }
+
+    #[test]
+    fn test_tolerant_valid_file_has_no_errors() {
+        let partial =
+            EnvFile::from_str_tolerant("A=1\n# note\n", &ParseOptions::default()).unwrap();
+
+        assert!(partial.errors.is_empty());
+        assert_eq!(partial.env.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_max_keys() {
+        let options = ParseOptions {
+            max_keys: Some(2),
+            ..Default::default()
+        };
+        let env = EnvFile::from_str_with("# header\nA=1\nB=2\n", &options).unwrap();
+        assert_eq!(env.entries.len(), 2);
+
+        let error = EnvFile::from_str_with("A=1\nB=2\n\nC=3\nD=4\n", &options).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::TooManyKeys { limit: 2, line: 4 }
+        ));
+        // Tolerant parsing stops at the limit too
+        assert!(EnvFile::from_str_tolerant("A=1\nB=2\nC=3\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(&path, "PORT=8080\n").unwrap();
+        let limit = |max_file_size| ParseOptions {
+            max_file_size: Some(max_file_size),
+            ..Default::default()
+        };
+
+        assert!(EnvFile::from_path_with(&path, &limit(10)).is_ok());
+        let error = EnvFile::from_path_with(&path, &limit(9)).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::FileTooLarge {
+                size: 10,
+                limit: 9,
+                ..
+            }
+        ));
+        assert!(EnvFile::from_path_tolerant(&path, &limit(9)).is_err());
+    }
+
+    #[test]
+    fn test_parse_size_limit() {
+        assert_eq!(parse_size_limit("4096"), Ok(4096));
+        assert_eq!(parse_size_limit("1MiB"), Ok(1024 * 1024));
+        assert!(parse_size_limit("lots").is_err());
+    }
+
+    #[test]
+    fn test_raw_value_retains_quotes() {
+        let content = "QUOTED=\"abc\"\nPLAIN=abc\n";
+        let env = EnvFile::from_str(content).unwrap();
+        let raw = env.raw_entries();
+
+        assert_eq!(env.get("QUOTED"), Some(&"abc".to_string()));
+        assert_eq!(raw.get("QUOTED"), Some(&"\"abc\"".to_string()));
+        assert_eq!(raw.get("PLAIN"), Some(&"abc".to_string()));
+    }
+
+    #[test]
+    fn test_parse_inline_comment_after_quoted_value() {
+        let content = "KEY=\"value\"  # explained\nSINGLE='a b' #note\n";
+        let env = EnvFile::from_str(content).unwrap();
+
+        assert_eq!(env.get("KEY"), Some(&"value".to_string()));
+        assert_eq!(env.get("SINGLE"), Some(&"a b".to_string()));
+        assert!(matches!(
+            &env.lines[0],
+            EnvLine::KeyValue { raw, comment: Some(comment), .. }
+            if raw == "\"value\"" && comment == "# explained"
+        ));
+    }
+
+    #[test]
+    fn test_parse_hash_in_unquoted_value_kept() {
+        let env = EnvFile::from_str("COLOR=#fff # not a comment").unwrap();
+
+        assert_eq!(env.get("COLOR"), Some(&"#fff # not a comment".to_string()));
+    }
+
+    #[test]
+    fn test_dialect_default_is_literal() {
+        let content = "export A=1\nB=\"x\\ny\"\nC=$B and more # tail\n";
+        let env = EnvFile::from_str(content).unwrap();
+
+        assert_eq!(env.get("export A"), Some(&"1".to_string()));
+        assert_eq!(env.get("B"), Some(&"x\\ny".to_string()));
+        assert_eq!(env.get("C"), Some(&"$B and more # tail".to_string()));
+    }
+
+    #[test]
+    fn test_dialect_node() {
+        let options = ParseOptions {
+            dialect: Dialect::Node,
+            ..Default::default()
+        };
+        let content = "export HOST=localhost\nURL=http://$HOST/api # comment\nPEM=\"a\\nb\"\n";
+        let env = EnvFile::from_str_with(content, &options).unwrap();
+
+        assert_eq!(env.get("HOST"), Some(&"localhost".to_string()));
+        // node does no interpolation
+        assert_eq!(env.get("URL"), Some(&"http://$HOST/api".to_string()));
+        assert_eq!(env.get("PEM"), Some(&"a\nb".to_string()));
+    }
+
+    #[test]
+    fn test_dialect_posix_interpolation() {
+        let options = ParseOptions {
+            dialect: Dialect::Posix,
+            ..Default::default()
+        };
+        let content = "export HOST=localhost\nURL=http://$HOST/api\nBRACED=\"${HOST}:80\"\nLITERAL='$HOST'\nUNKNOWN=x${NOPE}y\n";
+        let env = EnvFile::from_str_with(content, &options).unwrap();
+
+        assert_eq!(env.get("URL"), Some(&"http://localhost/api".to_string()));
+        assert_eq!(env.get("BRACED"), Some(&"localhost:80".to_string()));
+        assert_eq!(env.get("LITERAL"), Some(&"$HOST".to_string()));
+        assert_eq!(env.get("UNKNOWN"), Some(&"xy".to_string()));
+    }
+
+    #[test]
+    fn test_dialect_posix_escapes() {
+        let options = ParseOptions {
+            dialect: Dialect::Posix,
+            ..Default::default()
+        };
+        let content = "PRICE=\"\\$5 \\\"each\\\" \\n\" # note\nPLAIN=a\\ b\n";
+        let env = EnvFile::from_str_with(content, &options).unwrap();
+
+        assert_eq!(env.get("PRICE"), Some(&"$5 \"each\" \\n".to_string()));
+        assert_eq!(env.get("PLAIN"), Some(&"a b".to_string()));
+    }
+
+    #[test]
+    fn test_strict_escapes() {
+        let strict = ParseOptions {
+            dialect: Dialect::Node,
+            strict_escapes: true,
+            ..Default::default()
+        };
+        let env = EnvFile::from_str_with("PEM=\"a\\nb\"\n", &strict).unwrap();
+        assert_eq!(env.get("PEM"), Some(&"a\nb".to_string()));
+
+        let content = "OK=1\nBAD = \"a\\qb\"\n";
+        let error = EnvFile::from_str_with(content, &strict).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::UnknownEscape {
+                line: 2,
+                column: 9,
+                escape: 'q'
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "unknown escape sequence '\\q' at line 2, column 9"
+        );
+
+        // Without the flag the escape passes through
+        let lenient = ParseOptions {
+            dialect: Dialect::Node,
+            ..Default::default()
+        };
+        let env = EnvFile::from_str_with(content, &lenient).unwrap();
+        assert_eq!(env.get("BAD"), Some(&"a\\qb".to_string()));
+    }
+
+    #[test]
+    fn test_strict_escapes_posix() {
+        let strict = ParseOptions {
+            dialect: Dialect::Posix,
+            strict_escapes: true,
+            ..Default::default()
+        };
+        // An escaped backslash is not the start of another escape
+        let env = EnvFile::from_str_with("A=\"\\\\q \\$5\"\nB='\\q'\n", &strict).unwrap();
+        assert_eq!(env.get("A"), Some(&"\\q $5".to_string()));
+        assert_eq!(env.get("B"), Some(&"\\q".to_string()));
+
+        assert!(EnvFile::from_str_with("A=\"\\n\"\n", &strict).is_err());
+    }
+
+    #[test]
+    fn test_strict_quotes_leading_only() {
+        let strict = ParseOptions {
+            strict_quotes: true,
+            ..Default::default()
+        };
+        let error = EnvFile::from_str_with("OK=1\nKEY=\"unterminated\n", &strict).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::MismatchedQuote {
+                line: 2,
+                column: 5,
+                quote: '"'
+            }
+        ));
+        assert_eq!(error.to_string(), "unmatched quote \" at line 2, column 5");
+        assert!(EnvFile::from_str_with("KEY='\n", &strict).is_err());
+        assert!(EnvFile::from_str_with("KEY=\"value'\n", &strict).is_err());
+
+        // Without the flag the quote is kept as written
+        let env = EnvFile::from_str("KEY=\"unterminated\n").unwrap();
+        assert_eq!(env.get("KEY"), Some(&"\"unterminated".to_string()));
+    }
+
+    #[test]
+    fn test_strict_quotes_trailing_only() {
+        let strict = ParseOptions {
+            strict_quotes: true,
+            ..Default::default()
+        };
+        let error = EnvFile::from_str_with("KEY = value'\n", &strict).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::MismatchedQuote {
+                line: 1,
+                column: 12,
+                quote: '\''
+            }
+        ));
+    }
+
+    #[test]
+    fn test_strict_quotes_balanced() {
+        let strict = ParseOptions {
+            strict_quotes: true,
+            ..Default::default()
+        };
+        let content = "A=\"quoted\"\nB='single'\nC=say \"hi\"\nD=it's\nE=plain\nF=\"\"\n";
+        let env = EnvFile::from_str_with(content, &strict).unwrap();
+        assert_eq!(env.get("A"), Some(&"quoted".to_string()));
+        assert_eq!(env.get("C"), Some(&"say \"hi\"".to_string()));
+        assert_eq!(env.get("D"), Some(&"it's".to_string()));
+        assert_eq!(env.get("F"), Some(&String::new()));
+    }
+
+    #[test]
+    fn test_non_utf8_file_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        // "KEY=caf" followed by a Latin-1 encoded 'é'
+        fs::write(&path, b"KEY=caf\xe9\n").unwrap();
+
+        let result = EnvFile::from_path(&path);
+
+        if let Err(ParseError::NotUtf8 { offset, .. }) = &result {
+            assert_eq!(*offset, 7);
+        } else {
+            panic!("Expected NotUtf8 error");
+        }
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("not valid UTF-8"));
+        assert!(message.contains("offset 7"));
+    }
+
+    #[test]
+    fn test_read_latin1_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(&path, b"CITY=M\xfcnchen\nNAME=Ren\xe9\n").unwrap();
+        let options = ParseOptions {
+            encoding: InputEncoding::Latin1,
+            ..Default::default()
+        };
+
+        let env = EnvFile::from_path_with(&path, &options).unwrap();
+        assert_eq!(env.get("CITY"), Some(&"München".to_string()));
+        assert_eq!(env.get("NAME"), Some(&"René".to_string()));
+        assert!(matches!(
+            EnvFile::from_path(&path),
+            Err(ParseError::NotUtf8 { offset: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_file_with_utf8_bom() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(&path, b"\xEF\xBB\xBFPORT=8080\nCITY=M\xC3\xBCnchen\n").unwrap();
+
+        let env = EnvFile::from_path(&path).unwrap();
+        assert_eq!(env.keys().collect::<Vec<_>>(), ["CITY", "PORT"]);
+        assert_eq!(env.get("CITY"), Some(&"München".to_string()));
+
+        // Latin-1 has no byte order mark, so the bytes are read as Latin-1
+        let options = ParseOptions {
+            encoding: InputEncoding::Latin1,
+            ..Default::default()
+        };
+        let env = EnvFile::from_path_with(&path, &options).unwrap();
+        assert_eq!(env.get("CITY"), Some(&"MÃ¼nchen".to_string()));
+        assert!(env.get("PORT").is_none());
+    }
+
+    #[test]
+    fn test_read_utf16_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let le = dir.path().join("le.env");
+        let be = dir.path().join("be.env");
+        let content = "CITY=München\r\n";
+        let mut le_bytes = vec![0xff, 0xfe];
+        let mut be_bytes = vec![0xfe, 0xff];
+        for unit in content.encode_utf16() {
+            le_bytes.extend(unit.to_le_bytes());
+            be_bytes.extend(unit.to_be_bytes());
+        }
+        fs::write(&le, &le_bytes).unwrap();
+        fs::write(&be, &be_bytes).unwrap();
+        let options = ParseOptions {
+            encoding: InputEncoding::Utf16,
+            ..Default::default()
+        };
+
+        for path in [&le, &be] {
+            let env = EnvFile::from_path_with(path, &options).unwrap();
+            assert_eq!(env.get("CITY"), Some(&"München".to_string()));
+        }
+        // Without a byte order mark, little-endian is assumed
+        fs::write(&le, &le_bytes[2..]).unwrap();
+        let env = EnvFile::from_path_with(&le, &options).unwrap();
+        assert_eq!(env.get("CITY"), Some(&"München".to_string()));
+    }
+
+    #[test]
+    fn test_read_invalid_utf16_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(&path, [b'A', 0, b'=', 0, b'1']).unwrap();
+        let options = ParseOptions {
+            encoding: InputEncoding::Utf16,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            EnvFile::from_path_with(&path, &options),
+            Err(ParseError::InvalidEncoding {
+                encoding: "UTF-16",
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_empty_key_rejected() {
         let content = "=value\n";
@@ -230,4 +1410,227 @@ KEY3=value3
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_quoted_key_containing_equals() {
+        let content = "\"WEIRD=KEY\"=value\n\"PLAIN\" = x=y\n";
+        let env = EnvFile::from_str(content).unwrap();
+
+        assert_eq!(env.get("WEIRD=KEY"), Some(&"value".to_string()));
+        assert_eq!(env.get("PLAIN"), Some(&"x=y".to_string()));
+        assert!(!env.contains_key("\"WEIRD"));
+    }
+
+    #[test]
+    fn test_unquoted_key_splits_at_first_equals() {
+        let env = EnvFile::from_str("URL=a=b\nNAME=\"x\"=y\n").unwrap();
+
+        assert_eq!(env.get("URL"), Some(&"a=b".to_string()));
+        assert_eq!(env.get("NAME"), Some(&"\"x\"=y".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_line_column_missing_equals() {
+        let result = EnvFile::from_str("VALID=ok\nNO_EQUALS  \n");
+
+        if let Err(ParseError::InvalidLine { line, column, .. }) = result {
+            assert_eq!(line, 2);
+            assert_eq!(column, 10);
+        } else {
+            panic!("Expected InvalidLine error");
+        }
+    }
+
+    #[test]
+    fn test_invalid_line_column_empty_key() {
+        let result = EnvFile::from_str("=value\n");
+
+        if let Err(ParseError::InvalidLine { column, .. }) = &result {
+            assert_eq!(*column, 1);
+        } else {
+            panic!("Expected InvalidLine error");
+        }
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid line format at line 1, column 1: =value"
+        );
+    }
+
+    #[test]
+    fn test_bare_key_allowed() {
+        let options = ParseOptions {
+            allow_bare_keys: true,
+            ..Default::default()
+        };
+        let env = EnvFile::from_str_with("FEATURE_FLAG\nA=1\n", &options).unwrap();
+
+        assert_eq!(env.get("FEATURE_FLAG"), Some(&String::new()));
+        assert_eq!(env.get("A"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn test_bare_key_rejected_by_default() {
+        let result = EnvFile::from_str("FEATURE_FLAG\n");
+
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidLine { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_bare_key_must_be_identifier() {
+        let options = ParseOptions {
+            allow_bare_keys: true,
+            ..Default::default()
+        };
+
+        assert!(EnvFile::from_str_with("not a key\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let options = ParseOptions {
+            line_continuation: true,
+            ..Default::default()
+        };
+        let content = "CMD=run --verbose \\\n  --color\nNEXT=1\n";
+        let env = EnvFile::from_str_with(content, &options).unwrap();
+
+        assert_eq!(env.get("CMD"), Some(&"run --verbose   --color".to_string()));
+        assert_eq!(env.get("NEXT"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn test_line_continuation_escaped_backslash() {
+        let options = ParseOptions {
+            line_continuation: true,
+            ..Default::default()
+        };
+        let content = "DIR=C:\\temp\\\\\nNEXT=1\n";
+        let env = EnvFile::from_str_with(content, &options).unwrap();
+
+        assert_eq!(env.get("DIR"), Some(&"C:\\temp\\".to_string()));
+        assert_eq!(env.get("NEXT"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn test_line_continuation_off_by_default() {
+        let result = EnvFile::from_str("CMD=run \\\n  --color\n");
+
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidLine { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_line_continuation_error_reports_first_line() {
+        let options = ParseOptions {
+            line_continuation: true,
+            ..Default::default()
+        };
+        let result = EnvFile::from_str_with("A=1\nnot \\\nvalid\n", &options);
+
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidLine { line: 2, .. })
+        ));
+    }
+
+    /// Write files into a temp dir and parse `main.env` with includes resolved.
+    fn parse_with_includes(files: &[(&str, &str)]) -> Result<EnvFile, ParseError> {
+        let dir = tempfile::TempDir::new().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let options = ParseOptions {
+            includes: true,
+            ..Default::default()
+        };
+        EnvFile::from_path_with(&dir.path().join("main.env"), &options)
+    }
+
+    #[test]
+    fn test_include_merges_keys() {
+        let env = parse_with_includes(&[
+            ("main.env", "#include shared/common.env\nAPP=web\n"),
+            ("shared/common.env", "#include db.env\nREGION=eu\n"),
+            ("shared/db.env", "DB_HOST=localhost\n"),
+        ])
+        .unwrap();
+
+        assert_eq!(env.get("APP"), Some(&"web".to_string()));
+        assert_eq!(env.get("REGION"), Some(&"eu".to_string()));
+        assert_eq!(env.get("DB_HOST"), Some(&"localhost".to_string()));
+        assert_eq!(env.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_include_override_precedence() {
+        let env = parse_with_includes(&[
+            ("main.env", "A=main\nB=main\n#include common.env\nB=after\n"),
+            ("common.env", "A=common\nB=common\n"),
+        ])
+        .unwrap();
+
+        assert_eq!(env.get("A"), Some(&"common".to_string()));
+        assert_eq!(env.get("B"), Some(&"after".to_string()));
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let result = parse_with_includes(&[
+            ("main.env", "#include a.env\n"),
+            ("a.env", "#include b.env\n"),
+            ("b.env", "#include a.env\n"),
+        ]);
+
+        match result {
+            Err(ParseError::IncludeCycle(chain)) => assert!(chain.ends_with("a.env")),
+            other => panic!("Expected IncludeCycle error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_include_missing_file() {
+        let result = parse_with_includes(&[("main.env", "A=1\n#include missing.env\n")]);
+
+        assert!(matches!(
+            result,
+            Err(ParseError::IncludeNotFound { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_tolerant_with_includes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("main.env"), "#include common.env\nBROKEN\n").unwrap();
+        fs::write(dir.path().join("common.env"), "A=1\n").unwrap();
+        let options = ParseOptions {
+            includes: true,
+            ..Default::default()
+        };
+
+        let partial = EnvFile::from_path_tolerant(&dir.path().join("main.env"), &options).unwrap();
+
+        assert_eq!(partial.env.get("A"), Some(&"1".to_string()));
+        assert!(matches!(
+            partial.errors.as_slice(),
+            [ParseError::InvalidLine { line: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_include_ignored_by_default() {
+        let env = EnvFile::from_str("#include common.env\nA=1\n").unwrap();
+
+        assert_eq!(env.entries.len(), 1);
+        assert_eq!(
+            env.lines[0],
+            EnvLine::Comment("#include common.env".to_string())
+        );
+    }
 }
@@ -0,0 +1,46 @@
+//! Displaying file paths in command output.
+//!
+//! Absolute paths inside the current directory are shown relative to it, so
+//! output stays short in CI logs. Other paths are shown as given.
+
+use std::env;
+use std::path::Path;
+
+/// Render a path for output, relative to `base` when it lies inside it.
+fn relative_to(path: &Path, base: &Path) -> String {
+    match path.strip_prefix(base) {
+        Ok(relative) if path.is_absolute() && !relative.as_os_str().is_empty() => {
+            relative.display().to_string()
+        }
+        _ => path.display().to_string(),
+    }
+}
+
+/// Render a path for output, relative to the current directory when possible.
+pub fn display(path: &Path) -> String {
+    match env::current_dir() {
+        Ok(cwd) => relative_to(path, &cwd),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_relative_to_strips_base() {
+        let base = Path::new("/work/repo");
+
+        assert_eq!(
+            relative_to(Path::new("/work/repo/config/.env"), base),
+            Path::new("config").join(".env").display().to_string()
+        );
+        assert_eq!(
+            relative_to(Path::new("/elsewhere/.env"), base),
+            "/elsewhere/.env"
+        );
+        assert_eq!(relative_to(Path::new("config/.env"), base), "config/.env");
+    }
+}
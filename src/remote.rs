@@ -0,0 +1,67 @@
+//! Reading env files from `http://` and `https://` URLs.
+//!
+//! Fetching is only compiled in with the `network` feature; without it, URL
+//! arguments are rejected with an error instead of being read as paths.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+/// Errors that can occur while fetching a remote env file.
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[cfg(not(feature = "network"))]
+    #[error("cannot read '{0}': envcraft was built without the `network` feature")]
+    Disabled(String),
+
+    #[cfg(feature = "network")]
+    #[error("failed to fetch '{url}': {source}")]
+    Fetch { url: String, source: ureq::Error },
+}
+
+/// Get the URL for a path argument written as `http://` or `https://`.
+pub fn as_url(path: &Path) -> Option<&str> {
+    path.to_str()
+        .filter(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
+
+/// Fetch the body of a URL as bytes, to be decoded like a file.
+#[cfg(feature = "network")]
+pub fn fetch(url: &str) -> Result<Vec<u8>, RemoteError> {
+    let fetch_error = |source| RemoteError::Fetch {
+        url: url.to_string(),
+        source,
+    };
+
+    ureq::get(url)
+        .call()
+        .map_err(fetch_error)?
+        .body_mut()
+        .read_to_vec()
+        .map_err(fetch_error)
+}
+
+/// Fetch the body of a URL as bytes, to be decoded like a file.
+#[cfg(not(feature = "network"))]
+pub fn fetch(url: &str) -> Result<Vec<u8>, RemoteError> {
+    Err(RemoteError::Disabled(url.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_url() {
+        assert_eq!(
+            as_url(Path::new("https://example.com/.env")),
+            Some("https://example.com/.env")
+        );
+        assert_eq!(
+            as_url(Path::new("http://localhost:8080/app.env")),
+            Some("http://localhost:8080/app.env")
+        );
+        assert_eq!(as_url(Path::new(".env")), None);
+        assert_eq!(as_url(Path::new("HEAD:.env")), None);
+    }
+}
@@ -0,0 +1,128 @@
+//! Layered resolution of `.env` files, following the dotenv-flow convention.
+//!
+//! A directory's `.env`, `.env.local`, `.env.<mode>`, and `.env.<mode>.local`
+//! are merged in that order, so each file overrides the ones before it.
+//! Files that don't exist are skipped.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::convert::render_env;
+use crate::parser::{EnvFile, ParseError};
+use crate::paths;
+
+/// Errors that can occur during resolve operation.
+#[derive(Error, Debug)]
+pub enum ResolveError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("no .env files found in {}", paths::display(.0))]
+    NoEnvFiles(PathBuf),
+}
+
+/// Get the file names to merge, lowest precedence first.
+pub fn layer_names(mode: Option<&str>) -> Vec<String> {
+    let mut names = vec![".env".to_string(), ".env.local".to_string()];
+    if let Some(mode) = mode {
+        names.push(format!(".env.{mode}"));
+        names.push(format!(".env.{mode}.local"));
+    }
+    names
+}
+
+/// Merge the layered env files found in `dir`.
+///
+/// Returns the effective key-value pairs and the files that were read.
+pub fn resolve(
+    dir: &Path,
+    mode: Option<&str>,
+) -> Result<(BTreeMap<String, String>, Vec<PathBuf>), ResolveError> {
+    let mut entries = BTreeMap::new();
+    let mut used = Vec::new();
+
+    for name in layer_names(mode) {
+        let path = dir.join(name);
+        if !path.is_file() {
+            continue;
+        }
+        entries.extend(EnvFile::from_path(&path)?.entries);
+        used.push(path);
+    }
+
+    if used.is_empty() {
+        return Err(ResolveError::NoEnvFiles(dir.to_path_buf()));
+    }
+
+    Ok((entries, used))
+}
+
+/// Run the resolve command, printing the effective config.
+pub fn run_resolve(dir: &Path, mode: Option<&str>) -> Result<bool, ResolveError> {
+    let (entries, _) = resolve(dir, mode)?;
+    print!("{}", render_env(&entries));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_layer_names() {
+        assert_eq!(layer_names(None), vec![".env", ".env.local"]);
+        assert_eq!(
+            layer_names(Some("production")),
+            vec![
+                ".env",
+                ".env.local",
+                ".env.production",
+                ".env.production.local"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_precedence_chain() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let files = [
+            (".env", "A=env\nB=env\nC=env\nD=env\nBASE=1\n"),
+            (".env.local", "B=local\nC=local\nD=local\n"),
+            (".env.production", "C=production\nD=production\n"),
+            (".env.production.local", "D=production.local\n"),
+            (".env.staging", "A=staging\n"),
+        ];
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+
+        let (entries, used) = resolve(dir.path(), Some("production")).unwrap();
+
+        assert_eq!(entries["A"], "env");
+        assert_eq!(entries["B"], "local");
+        assert_eq!(entries["C"], "production");
+        assert_eq!(entries["D"], "production.local");
+        assert_eq!(entries["BASE"], "1");
+        assert_eq!(used.len(), 4);
+    }
+
+    #[test]
+    fn test_resolve_skips_missing_layers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join(".env"), "A=env\n").unwrap();
+        fs::write(dir.path().join(".env.test"), "A=test\n").unwrap();
+
+        let (entries, used) = resolve(dir.path(), Some("test")).unwrap();
+
+        assert_eq!(entries["A"], "test");
+        assert_eq!(used.len(), 2);
+        assert!(matches!(
+            resolve(&dir.path().join("missing"), None),
+            Err(ResolveError::NoEnvFiles(_))
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+//! Rule identifiers and their severities, shared by check and lint.
+//!
+//! Every diagnostic belongs to a rule with a default severity. The severity
+//! of each rule can be overridden on the command line, e.g.
+//! `-W extra-key=error`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// Identifiers for the rules behind check and lint diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// A key required by the schema is missing
+    MissingKey,
+    /// A value does not match its schema type
+    InvalidValue,
+    /// A value is a placeholder the schema forbids
+    PlaceholderValue,
+    /// A deprecated alias is used instead of the canonical key
+    DeprecatedKey,
+    /// A key is not declared in the schema
+    ExtraKey,
+    /// The file does not end with a newline
+    MissingFinalNewline,
+    /// A `KEY=VALUE` line is longer than the configured maximum
+    LineTooLong,
+    /// A line could not be parsed (reported in tolerant mode)
+    InvalidLine,
+    /// A key is defined more than once with differing values
+    DuplicateKey,
+    /// Only some keys of an all-or-none group are set
+    PartialGroup,
+    /// A value contains a `$VAR` reference that is not expanded
+    UnexpandedVariable,
+}
+
+impl Rule {
+    /// Every rule, in the order they are listed in help and errors.
+    pub const ALL: [Rule; 11] = [
+        Rule::MissingKey,
+        Rule::InvalidValue,
+        Rule::PlaceholderValue,
+        Rule::DeprecatedKey,
+        Rule::ExtraKey,
+        Rule::MissingFinalNewline,
+        Rule::LineTooLong,
+        Rule::InvalidLine,
+        Rule::DuplicateKey,
+        Rule::PartialGroup,
+        Rule::UnexpandedVariable,
+    ];
+
+    /// Get the stable rule identifier used in output.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::MissingKey => "missing-key",
+            Rule::InvalidValue => "invalid-value",
+            Rule::PlaceholderValue => "placeholder-value",
+            Rule::DeprecatedKey => "deprecated-key",
+            Rule::ExtraKey => "extra-key",
+            Rule::MissingFinalNewline => "missing-final-newline",
+            Rule::LineTooLong => "line-too-long",
+            Rule::InvalidLine => "invalid-line",
+            Rule::DuplicateKey => "duplicate-key",
+            Rule::PartialGroup => "partial-group",
+            Rule::UnexpandedVariable => "unexpanded-variable",
+        }
+    }
+
+    /// Get the severity used when no override is given.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::DeprecatedKey
+            | Rule::ExtraKey
+            | Rule::DuplicateKey
+            | Rule::UnexpandedVariable => Severity::Warning,
+            Rule::MissingKey
+            | Rule::InvalidValue
+            | Rule::PlaceholderValue
+            | Rule::MissingFinalNewline
+            | Rule::LineTooLong
+            | Rule::InvalidLine
+            | Rule::PartialGroup => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == s)
+            .ok_or_else(|| {
+                let ids: Vec<&str> = Rule::ALL.iter().map(Rule::id).collect();
+                format!("unknown rule '{s}' (expected one of: {})", ids.join(", "))
+            })
+    }
+}
+
+/// How a diagnostic is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Not reported at all
+    Allow,
+    /// Reported, but does not fail the run
+    Warning,
+    /// Reported and fails the run
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Severity::Allow),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown level '{s}' (expected one of: allow, warning, error)"
+            )),
+        }
+    }
+}
+
+/// Parse a `RULE=LEVEL` override, e.g. `extra-key=error`.
+pub fn parse_override(text: &str) -> Result<(Rule, Severity), String> {
+    let (rule, level) = text
+        .split_once('=')
+        .ok_or_else(|| format!("expected RULE=LEVEL, e.g. extra-key=error, got '{text}'"))?;
+    Ok((rule.parse()?, level.parse()?))
+}
+
+/// The severity of each rule after applying overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Severities {
+    /// Per-rule severities that replace the defaults
+    overrides: BTreeMap<Rule, Severity>,
+    /// Report rules that default to warnings as errors
+    warnings_as_errors: bool,
+}
+
+impl Severities {
+    /// Build severities from per-rule overrides; later overrides of the same
+    /// rule win. Per-rule overrides take precedence over `warnings_as_errors`.
+    pub fn new(overrides: &[(Rule, Severity)], warnings_as_errors: bool) -> Self {
+        Self {
+            overrides: overrides.iter().copied().collect(),
+            warnings_as_errors,
+        }
+    }
+
+    /// Get the severity a rule is reported with.
+    pub fn of(&self, rule: Rule) -> Severity {
+        match (self.overrides.get(&rule), rule.default_severity()) {
+            (Some(severity), _) => *severity,
+            (None, Severity::Warning) if self.warnings_as_errors => Severity::Error,
+            (None, severity) => severity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(
+            parse_override("extra-key=error"),
+            Ok((Rule::ExtraKey, Severity::Error))
+        );
+        assert!(parse_override("extra-key").is_err());
+        assert!(parse_override("extra-keys=error").is_err());
+        assert!(parse_override("extra-key=fatal").is_err());
+    }
+
+    #[test]
+    fn test_severities() {
+        let severities = Severities::new(&[(Rule::ExtraKey, Severity::Error)], false);
+
+        assert_eq!(severities.of(Rule::ExtraKey), Severity::Error);
+        assert_eq!(severities.of(Rule::DeprecatedKey), Severity::Warning);
+        assert_eq!(severities.of(Rule::MissingKey), Severity::Error);
+    }
+
+    #[test]
+    fn test_warnings_as_errors_with_override() {
+        let severities = Severities::new(&[(Rule::ExtraKey, Severity::Warning)], true);
+
+        assert_eq!(severities.of(Rule::ExtraKey), Severity::Warning);
+        assert_eq!(severities.of(Rule::DeprecatedKey), Severity::Error);
+    }
+}
@@ -1,15 +1,22 @@
 //! Schema validation for .env files.
 //!
 //! Validates environment files against YAML schema definitions.
-//! Supports string, int, and bool types.
+//! Supports string, int, bool, list, bytesize, date, datetime, base64, hex,
+//! host, semver, and json types.
 
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
 use thiserror::Error;
 
-use crate::parser::{EnvFile, ParseError};
+use crate::filter;
+use crate::json_schema::JsonSchema;
+use crate::parser::{EnvFile, EnvLine, ParseError};
 
 /// Errors that can occur during schema validation.
 #[derive(Error, Debug)]
@@ -20,28 +27,128 @@ pub enum SchemaError {
     #[error("failed to parse schema YAML: {0}")]
     YamlError(#[from] serde_yaml::Error),
 
-    #[error("invalid type '{0}' for key '{1}' (expected: string, int, bool)")]
+    #[error("schema root must be a mapping of key to type, found {0}")]
+    RootNotMapping(&'static str),
+
+    #[error("schema file must contain a single YAML document, found {0}")]
+    MultipleDocuments(usize),
+
+    #[error("invalid type '{0}' for key '{1}' (expected: string, int, bool, list, bytesize, date, datetime, base64, hex, host, semver, json)")]
     InvalidType(String, String),
 
+    #[error("no type specified for key '{0}' (e.g. {0}: string)")]
+    MissingType(String),
+
+    #[error("setting '{0}' for key '{1}' only applies to types: {2}")]
+    InapplicableSetting(&'static str, String, &'static str),
+
+    #[error("unknown setting '{0}' for key '{1}' (expected: {expected})", expected = SPEC_KEYS.join(", "))]
+    UnknownSetting(String, String),
+
+    #[error("invalid setting '{0}' for key '{1}': {2}")]
+    InvalidSetting(&'static str, String, String),
+
+    #[error("invalid schema _version {0} (expected a whole number from 1)")]
+    InvalidVersion(String),
+
+    #[error("schema _version {0} is newer than this envcraft supports (up to {SCHEMA_VERSION}); upgrade envcraft")]
+    UnsupportedVersion(u64),
+
+    #[error("invalid groups: {0}")]
+    InvalidGroups(serde_yaml::Error),
+
+    #[error("group '{0}' lists key '{1}', which the schema does not declare")]
+    UndeclaredGroupKey(String, String),
+
+    #[error("setting '{0}' cannot be used on pattern key '{1}'")]
+    PatternSetting(&'static str, String),
+
+    #[error("invalid default for key '{0}': {1}")]
+    InvalidDefault(String, String),
+
     #[error("env file error: {0}")]
     EnvParseError(#[from] ParseError),
 }
 
+/// Settings accepted in the structured form of a schema field.
+const SPEC_KEYS: &[&str] = &[
+    "type",
+    "aliases",
+    "description",
+    "forbid",
+    "allow_bare",
+    "bytes",
+    "allow",
+    "range",
+    "schema",
+    "default",
+];
+
 /// Supported value types in schema.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValueType {
     String,
     Int,
     Bool,
+    /// Comma-separated list of strings
+    List,
+    /// Number with an SI or IEC size suffix, e.g. `512MB` or `1024KiB`
+    ByteSize {
+        /// Accept bare numbers, read as bytes
+        allow_bare: bool,
+    },
+    /// Calendar date in ISO-8601 form, e.g. `2024-01-15`
+    Date,
+    /// RFC 3339 timestamp with a timezone, e.g. `2024-01-15T10:00:00Z`
+    DateTime,
+    /// Standard base64 with `=` padding, e.g. `aGVsbG8=`
+    Base64 {
+        /// Required length of the decoded data in bytes
+        bytes: Option<usize>,
+    },
+    /// Hex-encoded data with an even number of digits, e.g. `deadBEEF`
+    Hex {
+        /// Required length of the decoded data in bytes
+        bytes: Option<usize>,
+    },
+    /// IPv4 or IPv6 address or hostname, e.g. `10.0.0.1` or `db.internal`
+    Host {
+        /// Accept IP addresses
+        ip: bool,
+        /// Accept hostnames
+        hostname: bool,
+    },
+    /// Semantic version, e.g. `1.2.3` or `2.0.0-rc.1`
+    SemVer {
+        /// Versions that are accepted, e.g. `>=1.0.0, <2.0.0`
+        range: Option<VersionReq>,
+    },
+    /// Inline JSON, e.g. `{"enabled": true}` or `[1, 2]`
+    Json {
+        /// JSON Schema the parsed value must satisfy
+        schema: Option<Box<JsonSchema>>,
+    },
 }
 
 impl ValueType {
     /// Parse a type string into a ValueType.
-    fn from_str(s: &str, key: &str) -> Result<Self, SchemaError> {
+    pub fn from_str(s: &str, key: &str) -> Result<Self, SchemaError> {
         match s.to_lowercase().as_str() {
             "string" => Ok(ValueType::String),
             "int" | "integer" => Ok(ValueType::Int),
             "bool" | "boolean" => Ok(ValueType::Bool),
+            "list" => Ok(ValueType::List),
+            "bytesize" => Ok(ValueType::ByteSize { allow_bare: false }),
+            "date" => Ok(ValueType::Date),
+            "datetime" => Ok(ValueType::DateTime),
+            "base64" => Ok(ValueType::Base64 { bytes: None }),
+            "hex" => Ok(ValueType::Hex { bytes: None }),
+            "host" => Ok(ValueType::Host {
+                ip: true,
+                hostname: true,
+            }),
+            "semver" | "version" => Ok(ValueType::SemVer { range: None }),
+            "json" => Ok(ValueType::Json { schema: None }),
             _ => Err(SchemaError::InvalidType(s.to_string(), key.to_string())),
         }
     }
@@ -49,50 +156,858 @@ impl ValueType {
     /// Validate a value against this type.
     fn validate(&self, value: &str) -> bool {
         match self {
-            ValueType::String => true,
-            ValueType::Int => value.parse::<i64>().is_ok(),
+            ValueType::String | ValueType::List => true,
+            ValueType::Int => parse_int(value).is_some(),
+            ValueType::Bool => parse_bool(value).is_some(),
+            ValueType::ByteSize { allow_bare } => parse_byte_size(value, *allow_bare).is_some(),
+            ValueType::Date => parse_date(value).is_some(),
+            ValueType::DateTime => parse_datetime(value).is_some(),
+            ValueType::Base64 { bytes } => {
+                decoded_base64_len(value).is_some_and(|len| bytes.is_none_or(|n| n == len))
+            }
+            ValueType::Hex { bytes } => {
+                decoded_hex_len(value).is_some_and(|len| bytes.is_none_or(|n| n == len))
+            }
+            ValueType::Host { ip, hostname } => {
+                (*ip && value.parse::<IpAddr>().is_ok()) || (*hostname && is_hostname(value))
+            }
+            ValueType::SemVer { range } => Version::parse(value)
+                .is_ok_and(|version| range.as_ref().is_none_or(|req| req.matches(&version))),
+            ValueType::Json { schema } => serde_json::from_str::<serde_json::Value>(value)
+                .is_ok_and(|json| schema.as_ref().is_none_or(|schema| schema.matches(&json))),
+        }
+    }
+
+    /// Correct a value this type rejects when the intent is unambiguous:
+    /// surrounding whitespace is trimmed, and bools spelled `yes`/`no`,
+    /// `on`/`off`, `y`/`n` or `1`/`0` become `true`/`false`.
+    ///
+    /// Returns `None` when the value is already valid or cannot be fixed.
+    pub fn fix(&self, value: &str) -> Option<String> {
+        if self.validate(value) {
+            return None;
+        }
+
+        let trimmed = value.trim();
+        let fixed = match self {
+            ValueType::Bool => match trimmed.to_ascii_lowercase().as_str() {
+                "yes" | "y" | "on" | "1" => "true",
+                "no" | "n" | "off" | "0" => "false",
+                _ => trimmed,
+            },
+            _ => trimmed,
+        };
+        self.validate(fixed).then(|| fixed.to_string())
+    }
+
+    /// Infer the narrowest type that accepts a value.
+    pub fn infer(value: &str) -> Self {
+        if ValueType::Int.validate(value) {
+            ValueType::Int
+        } else if ValueType::Bool.validate(value) {
+            ValueType::Bool
+        } else {
+            ValueType::String
+        }
+    }
+
+    /// Check if the case of values of this type carries no meaning, so
+    /// `TRUE` and `true` are the same value.
+    pub fn folds_case(&self) -> bool {
+        matches!(self, ValueType::Bool)
+    }
+
+    /// Check if two values are equal once interpreted as this type.
+    ///
+    /// Ints compare numerically and bools by case-insensitive spelling.
+    /// Values that don't parse as the type fall back to exact comparison.
+    pub fn equivalent(&self, a: &str, b: &str) -> bool {
+        match self {
+            ValueType::String | ValueType::List => a == b,
+            ValueType::Int => match (parse_int(a), parse_int(b)) {
+                (Some(x), Some(y)) => x == y,
+                _ => a == b,
+            },
             ValueType::Bool => {
-                let lower = value.to_lowercase();
-                lower == "true" || lower == "false"
+                if self.validate(a) && self.validate(b) {
+                    a.eq_ignore_ascii_case(b)
+                } else {
+                    a == b
+                }
+            }
+            ValueType::ByteSize { allow_bare } => {
+                match (
+                    parse_byte_size(a, *allow_bare),
+                    parse_byte_size(b, *allow_bare),
+                ) {
+                    (Some(x), Some(y)) => x == y,
+                    _ => a == b,
+                }
+            }
+            ValueType::Date | ValueType::SemVer { .. } => a == b,
+            ValueType::Json { .. } => match (
+                serde_json::from_str::<serde_json::Value>(a),
+                serde_json::from_str::<serde_json::Value>(b),
+            ) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => a == b,
+            },
+            ValueType::Hex { .. } if self.validate(a) && self.validate(b) => {
+                a.eq_ignore_ascii_case(b)
             }
+            ValueType::Base64 { .. } | ValueType::Hex { .. } => a == b,
+            ValueType::Host { .. } => match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+                (Ok(x), Ok(y)) => x == y,
+                _ if self.validate(a) && self.validate(b) => a.eq_ignore_ascii_case(b),
+                _ => a == b,
+            },
+            ValueType::DateTime => match (parse_datetime(a), parse_datetime(b)) {
+                (Some(x), Some(y)) => x == y,
+                _ => a == b,
+            },
+        }
+    }
+
+    /// Get the canonical type name as written in a schema.
+    fn name(&self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Int => "int",
+            ValueType::Bool => "bool",
+            ValueType::List => "list",
+            ValueType::ByteSize { .. } => "bytesize",
+            ValueType::Date => "date",
+            ValueType::DateTime => "datetime",
+            ValueType::Base64 { .. } => "base64",
+            ValueType::Hex { .. } => "hex",
+            ValueType::Host { .. } => "host",
+            ValueType::SemVer { .. } => "semver",
+            ValueType::Json { .. } => "json",
         }
     }
 
     /// Get a human-readable description of valid values.
-    fn description(&self) -> &'static str {
+    pub fn description(&self) -> String {
         match self {
-            ValueType::String => "any string",
-            ValueType::Int => "an integer (e.g., 42, -10)",
-            ValueType::Bool => "true or false",
+            ValueType::String => "any string".to_string(),
+            ValueType::Int => "an integer (e.g., 42, -10)".to_string(),
+            ValueType::Bool => "true or false".to_string(),
+            ValueType::List => "a comma-separated list".to_string(),
+            ValueType::ByteSize { allow_bare: false } => {
+                "a byte size (e.g., 512MB, 1.5GB, 1024KiB)".to_string()
+            }
+            ValueType::ByteSize { allow_bare: true } => {
+                "a byte size (e.g., 512MB, 1.5GB, 1024KiB) or a number of bytes".to_string()
+            }
+            ValueType::Date => "a date (e.g., 2024-01-15)".to_string(),
+            ValueType::DateTime => {
+                "a timestamp with timezone (e.g., 2024-01-15T10:00:00Z)".to_string()
+            }
+            ValueType::Base64 { bytes: None } => "base64 data (e.g., aGVsbG8=)".to_string(),
+            ValueType::Base64 { bytes: Some(n) } => format!("base64 data decoding to {n} bytes"),
+            ValueType::Hex { bytes: None } => "hex data (e.g., 0a1b2c)".to_string(),
+            ValueType::Hex { bytes: Some(n) } => {
+                format!("hex data of {n} bytes ({} digits)", n * 2)
+            }
+            ValueType::Host {
+                ip: true,
+                hostname: false,
+            } => "an IP address (e.g., 10.0.0.1, ::1)".to_string(),
+            ValueType::Host {
+                ip: false,
+                hostname: true,
+            } => "a hostname (e.g., db.internal)".to_string(),
+            ValueType::Host { .. } => {
+                "an IP address or hostname (e.g., 10.0.0.1, db.internal)".to_string()
+            }
+            ValueType::SemVer { range: None } => {
+                "a semantic version (e.g., 1.2.3, 2.0.0-rc.1)".to_string()
+            }
+            ValueType::SemVer { range: Some(req) } => format!("a semantic version matching {req}"),
+            ValueType::Json { schema: None } => {
+                "JSON (e.g., {\"enabled\": true}, [1, 2])".to_string()
+            }
+            ValueType::Json { schema: Some(_) } => "JSON matching the field's schema".to_string(),
         }
     }
 }
 
+/// Parse a signed 64-bit integer such as `42` or `-10`.
+pub fn parse_int(value: &str) -> Option<i64> {
+    value.parse().ok()
+}
+
+/// Parse `true` or `false`, in any letter case.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Parse an ISO-8601 calendar date written as `YYYY-MM-DD`.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    if value.len() != 10 {
+        return None;
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+/// Parse an RFC 3339 timestamp such as `2024-01-15T10:00:00Z`.
+///
+/// The timezone (`Z` or an offset like `+02:00`) is required: a timestamp
+/// without one means a different instant on every machine that reads it.
+pub fn parse_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+/// Get the decoded length of standard base64 data, or `None` if the value
+/// is not valid base64.
+///
+/// The length must be a multiple of 4, using only `A-Z`, `a-z`, `0-9`, `+`,
+/// and `/`, with at most two `=` padding characters at the end.
+pub fn decoded_base64_len(value: &str) -> Option<usize> {
+    if !value.len().is_multiple_of(4) {
+        return None;
+    }
+    let data = value.trim_end_matches('=');
+    let padding = value.len() - data.len();
+    let valid = data
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+
+    (valid && padding <= 2).then(|| value.len() / 4 * 3 - padding)
+}
+
+/// Get the decoded length of hex data, or `None` if the value is not an even
+/// number of hex digits (in either case).
+pub fn decoded_hex_len(value: &str) -> Option<usize> {
+    let valid = value.len().is_multiple_of(2) && value.bytes().all(|b| b.is_ascii_hexdigit());
+    valid.then_some(value.len() / 2)
+}
+
+/// Check if a value is a hostname: dot-separated labels of 1 to 63 letters,
+/// digits, and hyphens, not starting or ending with a hyphen, 253 characters
+/// at most. A single trailing dot is allowed. The last label may not be all
+/// digits, so a malformed IPv4 address is not taken for a hostname.
+pub fn is_hostname(value: &str) -> bool {
+    let name = value.strip_suffix('.').unwrap_or(value);
+    let valid_label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(valid_label)
+        && !name
+            .rsplit('.')
+            .next()
+            .is_some_and(|last| last.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Parse the `allow` setting of a host field into which forms are accepted.
+fn parse_host_allow(allow: &[String], key: &str) -> Result<ValueType, SchemaError> {
+    let invalid = |reason: String| SchemaError::InvalidSetting("allow", key.to_string(), reason);
+    if allow.is_empty() {
+        return Err(invalid("list at least one of: ip, hostname".to_string()));
+    }
+
+    let (mut ip, mut hostname) = (false, false);
+    for kind in allow {
+        match kind.as_str() {
+            "ip" => ip = true,
+            "hostname" => hostname = true,
+            other => {
+                return Err(invalid(format!(
+                    "unknown kind '{other}' (expected: ip, hostname)"
+                )))
+            }
+        }
+    }
+    Ok(ValueType::Host { ip, hostname })
+}
+
+/// Size suffixes and their multipliers, SI (powers of 1000) and IEC
+/// (powers of 1024).
+pub const BYTE_SIZE_UNITS: &[(&str, u128)] = &[
+    ("B", 1),
+    ("KB", 1_000),
+    ("MB", 1_000_000),
+    ("GB", 1_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("PB", 1_000_000_000_000_000),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+    ("PiB", 1 << 50),
+];
+
+/// Parse a byte size such as `512MB`, `1.5GB`, or `1024KiB` into bytes.
+///
+/// Units are matched case-insensitively and may be separated from the
+/// number by whitespace. Bare numbers are only accepted with `allow_bare`.
+/// Sizes that don't come to a whole number of bytes are rejected.
+pub fn parse_byte_size(value: &str, allow_bare: bool) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = unit.trim_start();
+
+    let multiplier = if unit.is_empty() {
+        if !allow_bare {
+            return None;
+        }
+        1
+    } else {
+        BYTE_SIZE_UNITS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+            .map(|&(_, multiplier)| multiplier)?
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let scale = 10u128.checked_pow(fraction.len() as u32)?;
+    let mantissa: u128 = format!("{whole}{fraction}").parse().ok()?;
+    let scaled = mantissa.checked_mul(multiplier)?;
+
+    if scaled % scale != 0 {
+        return None;
+    }
+    u64::try_from(scaled / scale).ok()
+}
+
+/// A schema entry as written in YAML: either a bare type name or a mapping.
+// Only lives while a schema is parsed, so the size of `Spec` does not matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawField {
+    /// Shorthand form, e.g. `PORT: int`
+    Type(String),
+    /// Structured form, e.g. `PORT: {type: int, aliases: [OLD_PORT]}`
+    Spec {
+        #[serde(rename = "type", default)]
+        value_type: Option<String>,
+        #[serde(default)]
+        aliases: Vec<String>,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        forbid: Vec<String>,
+        #[serde(default)]
+        allow_bare: bool,
+        #[serde(default)]
+        bytes: Option<usize>,
+        #[serde(default)]
+        allow: Option<Vec<String>>,
+        #[serde(default)]
+        range: Option<String>,
+        #[serde(default)]
+        schema: Option<serde_yaml::Value>,
+        #[serde(default)]
+        default: Option<serde_yaml::Value>,
+        /// Any other settings, rejected so typos are not silently ignored
+        #[serde(flatten)]
+        unknown: BTreeMap<String, serde_yaml::Value>,
+    },
+    /// A key with no value at all, e.g. `PORT:`
+    Missing,
+}
+
+/// A single key definition in a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Expected value type
+    pub value_type: ValueType,
+    /// Deprecated key names accepted in place of the canonical key
+    pub aliases: Vec<String>,
+    /// Human-readable explanation of the key
+    pub description: Option<String>,
+    /// Placeholder values (case-insensitive) that are rejected
+    pub forbid: Vec<String>,
+    /// Schema file that declared this field, if it was loaded from one
+    pub source: Option<PathBuf>,
+    /// Value used when the key (and every alias) is absent
+    pub default: Option<String>,
+}
+
+impl Field {
+    /// Create a field with no aliases or description.
+    pub fn new(value_type: ValueType) -> Self {
+        Self {
+            value_type,
+            aliases: Vec::new(),
+            description: None,
+            forbid: Vec::new(),
+            source: None,
+            default: None,
+        }
+    }
+
+    /// Check if a value is one of the forbidden placeholders.
+    pub fn is_forbidden(&self, value: &str) -> bool {
+        self.forbid
+            .iter()
+            .any(|token| token.eq_ignore_ascii_case(value))
+    }
+
+    /// Find the value for this field in an env file.
+    ///
+    /// The canonical key wins; otherwise the first alias present is used.
+    /// Returns the env key that matched along with its value.
+    fn lookup<'a>(&'a self, key: &'a String, env: &'a EnvFile) -> Option<(&'a String, &'a String)> {
+        env.get(key).map(|value| (key, value)).or_else(|| {
+            self.aliases
+                .iter()
+                .find_map(|alias| env.get(alias).map(|value| (alias, value)))
+        })
+    }
+}
+
+/// Root key of the schema that holds group constraints, when its value is a
+/// list (a field's value never is).
+const GROUPS_KEY: &str = "groups";
+
+/// Reserved root key holding the schema format version.
+const VERSION_KEY: &str = "_version";
+
+/// Newest schema format version this binary understands. Schemas without a
+/// `_version` are read as version 1.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Remove the `_version` key from the schema root and check that this
+/// binary supports it.
+fn take_version(root: &mut serde_yaml::Value) -> Result<(), SchemaError> {
+    let Some(version) = root
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.remove(VERSION_KEY))
+    else {
+        return Ok(());
+    };
+
+    match version.as_u64() {
+        Some(0) | None => Err(SchemaError::InvalidVersion(
+            serde_yaml::to_string(&version)
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        )),
+        Some(version) if version > SCHEMA_VERSION => Err(SchemaError::UnsupportedVersion(version)),
+        Some(_) => Ok(()),
+    }
+}
+
+/// How the keys of a group must be set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupPolicy {
+    /// Every key is set, or none is
+    AllOrNone,
+}
+
+/// Keys constrained together, e.g.
+/// `{group: aws, keys: [AWS_KEY, AWS_SECRET], policy: all_or_none}`.
+///
+/// Members are exempt from the missing-key check; the policy decides
+/// whether their absence is an error.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Group {
+    /// Name used in messages
+    #[serde(rename = "group")]
+    pub name: String,
+    /// Member keys, each declared in the schema
+    pub keys: Vec<String>,
+    /// How the members must be set
+    pub policy: GroupPolicy,
+}
+
+/// A group whose policy is broken: some members are set and some are not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialGroup {
+    /// Name of the group
+    pub name: String,
+    /// Members that are set
+    pub present: Vec<String>,
+    /// Members that are not set
+    pub missing: Vec<String>,
+}
+
+/// Remove the `groups:` list from the schema root and parse it.
+fn take_groups(root: &mut serde_yaml::Value) -> Result<Vec<Group>, SchemaError> {
+    let serde_yaml::Value::Mapping(mapping) = root else {
+        return Ok(Vec::new());
+    };
+    if !matches!(
+        mapping.get(GROUPS_KEY),
+        Some(serde_yaml::Value::Sequence(_))
+    ) {
+        return Ok(Vec::new());
+    }
+
+    let groups = mapping.remove(GROUPS_KEY).expect("groups are present");
+    serde_yaml::from_value(groups).map_err(SchemaError::InvalidGroups)
+}
+
+/// Check if a schema key is a glob pattern like `FEATURE_*` rather than a
+/// key name.
+fn is_pattern(key: &str) -> bool {
+    key.contains(['*', '?'])
+}
+
 /// A parsed schema definition.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Schema {
-    /// Map of key names to their expected types
-    pub fields: BTreeMap<String, ValueType>,
+    /// Map of key names to their field definitions
+    pub fields: BTreeMap<String, Field>,
+    /// Map of glob patterns (e.g. `FEATURE_*`) to the field definition of
+    /// every undeclared key they match; such keys are optional
+    pub patterns: BTreeMap<String, Field>,
+    /// Constraints on sets of keys, in the order declared
+    pub groups: Vec<Group>,
 }
 
 impl Schema {
     /// Load a schema from a YAML file.
     pub fn from_path(path: &Path) -> Result<Self, SchemaError> {
         let content = fs::read_to_string(path)?;
-        Self::from_str(&content)
+        let mut schema = Self::from_str(&content)?;
+        for field in schema
+            .fields
+            .values_mut()
+            .chain(schema.patterns.values_mut())
+        {
+            field.source = Some(path.to_path_buf());
+        }
+        Ok(schema)
     }
 
     /// Parse a schema from a YAML string.
     pub fn from_str(content: &str) -> Result<Self, SchemaError> {
-        let raw: BTreeMap<String, String> = serde_yaml::from_str(content)?;
+        let (raw, groups): (BTreeMap<String, RawField>, _) = match parse_root(content)? {
+            Some(mut mapping) => {
+                take_version(&mut mapping)?;
+                let groups = take_groups(&mut mapping)?;
+                (serde_yaml::from_value(mapping)?, groups)
+            }
+            None => (BTreeMap::new(), Vec::new()),
+        };
         let mut fields = BTreeMap::new();
+        let mut patterns = BTreeMap::new();
+
+        for (key, raw_field) in raw {
+            let field = match raw_field {
+                RawField::Type(type_str) => Field::new(ValueType::from_str(
+                    &declared_type(Some(type_str), &key)?,
+                    &key,
+                )?),
+                RawField::Missing => return Err(SchemaError::MissingType(key)),
+                RawField::Spec {
+                    value_type,
+                    aliases,
+                    description,
+                    forbid,
+                    allow_bare,
+                    bytes,
+                    allow,
+                    range,
+                    schema,
+                    default,
+                    unknown,
+                } => {
+                    if let Some(setting) = unknown.into_keys().next() {
+                        return Err(SchemaError::UnknownSetting(setting, key));
+                    }
+                    let value_type = declared_type(value_type, &key)?;
+                    let value_type = match ValueType::from_str(&value_type, &key)? {
+                        ValueType::ByteSize { .. } => ValueType::ByteSize { allow_bare },
+                        ValueType::Base64 { .. } => ValueType::Base64 { bytes },
+                        ValueType::Hex { .. } => ValueType::Hex { bytes },
+                        _ if bytes.is_some() => {
+                            return Err(SchemaError::InapplicableSetting(
+                                "bytes",
+                                key,
+                                "base64, hex",
+                            ));
+                        }
+                        ValueType::Host { .. } => match allow {
+                            Some(allow) => parse_host_allow(&allow, &key)?,
+                            None => ValueType::Host {
+                                ip: true,
+                                hostname: true,
+                            },
+                        },
+                        _ if allow.is_some() => {
+                            return Err(SchemaError::InapplicableSetting("allow", key, "host"));
+                        }
+                        ValueType::SemVer { .. } => ValueType::SemVer {
+                            range: range
+                                .map(|text| {
+                                    VersionReq::parse(&text).map_err(|e| {
+                                        SchemaError::InvalidSetting(
+                                            "range",
+                                            key.clone(),
+                                            e.to_string(),
+                                        )
+                                    })
+                                })
+                                .transpose()?,
+                        },
+                        _ if range.is_some() => {
+                            return Err(SchemaError::InapplicableSetting("range", key, "semver"));
+                        }
+                        ValueType::Json { .. } => ValueType::Json {
+                            schema: schema
+                                .map(|yaml| {
+                                    JsonSchema::from_yaml(&yaml).map(Box::new).map_err(|e| {
+                                        SchemaError::InvalidSetting(
+                                            "schema",
+                                            key.clone(),
+                                            e.to_string(),
+                                        )
+                                    })
+                                })
+                                .transpose()?,
+                        },
+                        _ if schema.is_some() => {
+                            return Err(SchemaError::InapplicableSetting("schema", key, "json"));
+                        }
+                        other => other,
+                    };
+                    if is_pattern(&key) {
+                        if !aliases.is_empty() {
+                            return Err(SchemaError::PatternSetting("aliases", key));
+                        }
+                        if default.is_some() {
+                            return Err(SchemaError::PatternSetting("default", key));
+                        }
+                    }
+                    let default = default
+                        .map(|value| parse_default(&value, &value_type, &key))
+                        .transpose()?;
+                    Field {
+                        value_type,
+                        aliases,
+                        description,
+                        forbid,
+                        source: None,
+                        default,
+                    }
+                }
+            };
+            if is_pattern(&key) {
+                patterns.insert(key, field);
+            } else {
+                fields.insert(key, field);
+            }
+        }
+
+        for group in &groups {
+            if let Some(key) = group.keys.iter().find(|key| !fields.contains_key(*key)) {
+                return Err(SchemaError::UndeclaredGroupKey(
+                    group.name.clone(),
+                    key.clone(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            fields,
+            patterns,
+            groups,
+        })
+    }
+
+    /// Infer a schema from an env file, typing each key by its value.
+    pub fn infer(env: &EnvFile) -> Self {
+        let fields = env
+            .entries
+            .iter()
+            .map(|(key, value)| (key.clone(), Field::new(ValueType::infer(value))))
+            .collect();
+
+        Self {
+            fields,
+            patterns: BTreeMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Append a declaration for each new key to schema text, with the type
+    /// inferred from the key's value, e.g. `DEBUG: bool`.
+    ///
+    /// Keys the schema already declares, and repeats among `keys`, are
+    /// skipped. Existing content, comments included, is kept as written.
+    /// Returns the new text and the keys that were added.
+    pub fn append_inferred(
+        content: &str,
+        keys: &[(String, String)],
+    ) -> Result<(String, Vec<String>), SchemaError> {
+        let declared = Self::from_str(content)?;
+        let mut output = content.to_string();
+        let mut added: Vec<String> = Vec::new();
+
+        for (key, value) in keys {
+            if declared.fields.contains_key(key) || added.contains(key) {
+                continue;
+            }
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push('\n');
+            }
+            let name = serde_yaml::to_string(key)?;
+            output.push_str(&format!(
+                "{}: {}\n",
+                name.trim_end(),
+                ValueType::infer(value).name()
+            ));
+            added.push(key.clone());
+        }
+
+        Ok((output, added))
+    }
+
+    /// Merge another schema into this one; its fields and groups replace
+    /// same-named ones.
+    pub fn merge(&mut self, other: Schema) {
+        self.fields.extend(other.fields);
+        self.patterns.extend(other.patterns);
+        self.groups
+            .retain(|group| other.groups.iter().all(|new| new.name != group.name));
+        self.groups.extend(other.groups);
+    }
+
+    /// Check if a key belongs to a group, which makes it exempt from the
+    /// missing-key check.
+    fn in_group(&self, key: &str) -> bool {
+        self.groups
+            .iter()
+            .any(|group| group.keys.iter().any(|member| member == key))
+    }
 
-        for (key, type_str) in raw {
-            let value_type = ValueType::from_str(&type_str, &key)?;
-            fields.insert(key, value_type);
+    /// Get the schema file that declared a key (canonical or alias), but only
+    /// when fields come from more than one file.
+    pub fn origin(&self, key: &str) -> Option<&Path> {
+        let mut sources = self
+            .fields
+            .values()
+            .chain(self.patterns.values())
+            .filter_map(|field| field.source.as_deref());
+        let first = sources.next()?;
+        if sources.all(|source| source == first) {
+            return None;
         }
 
-        Ok(Self { fields })
+        self.fields
+            .get(key)
+            .or_else(|| {
+                self.fields
+                    .values()
+                    .find(|field| field.aliases.iter().any(|alias| alias == key))
+            })
+            .or_else(|| self.pattern_for(key).map(|(_, field)| field))
+            .and_then(|field| field.source.as_deref())
+    }
+
+    /// Get the field that applies to a key: its own declaration, or else
+    /// the pattern it matches.
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        self.fields
+            .get(key)
+            .or_else(|| self.pattern_for(key).map(|(_, field)| field))
+    }
+
+    /// Find the pattern that types a key the schema does not declare.
+    ///
+    /// Declared keys and aliases always win over patterns. When several
+    /// patterns match, the one with the most literal characters wins, and
+    /// of those the first in order.
+    pub fn pattern_for(&self, key: &str) -> Option<(&String, &Field)> {
+        if self.declares(key) {
+            return None;
+        }
+        let literal_len =
+            |pattern: &str| pattern.chars().filter(|c| !matches!(c, '*' | '?')).count();
+        self.patterns
+            .iter()
+            .filter(|(pattern, _)| filter::glob_matches(pattern, key))
+            .min_by_key(|(pattern, _)| std::cmp::Reverse(literal_len(pattern)))
+    }
+
+    /// Check if a key is declared in the schema, either directly or as an alias.
+    fn declares(&self, key: &str) -> bool {
+        self.fields.contains_key(key)
+            || self
+                .fields
+                .values()
+                .any(|field| field.aliases.iter().any(|alias| alias == key))
+    }
+}
+
+/// Read a field's `default` setting as an env value.
+///
+/// Scalars are written the way they would appear in an env file. The value
+/// must be valid for the field's type.
+fn parse_default(
+    value: &serde_yaml::Value,
+    value_type: &ValueType,
+    key: &str,
+) -> Result<String, SchemaError> {
+    let invalid = |reason: String| SchemaError::InvalidDefault(key.to_string(), reason);
+    let text = match value {
+        serde_yaml::Value::String(text) => text.clone(),
+        serde_yaml::Value::Number(number) => number.to_string(),
+        serde_yaml::Value::Bool(flag) => flag.to_string(),
+        _ => return Err(invalid("expected a string, number, or boolean".to_string())),
+    };
+
+    if !value_type.validate(&text) {
+        return Err(invalid(format!(
+            "'{text}' is not {}",
+            value_type.description()
+        )));
+    }
+    Ok(text)
+}
+
+/// Get a field's declared type name, rejecting a missing or blank one.
+fn declared_type(value_type: Option<String>, key: &str) -> Result<String, SchemaError> {
+    match value_type {
+        Some(name) if !name.trim().is_empty() => Ok(name),
+        _ => Err(SchemaError::MissingType(key.to_string())),
+    }
+}
+
+/// Parse the schema document and ensure its root is a mapping.
+///
+/// Returns `None` for an empty document.
+fn parse_root(content: &str) -> Result<Option<serde_yaml::Value>, SchemaError> {
+    let documents = serde_yaml::Deserializer::from_str(content)
+        .map(serde_yaml::Value::deserialize)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if documents.len() > 1 {
+        return Err(SchemaError::MultipleDocuments(documents.len()));
+    }
+
+    match documents.into_iter().next() {
+        None | Some(serde_yaml::Value::Null) => Ok(None),
+        Some(mapping @ serde_yaml::Value::Mapping(_)) => Ok(Some(mapping)),
+        Some(serde_yaml::Value::Sequence(_)) => Err(SchemaError::RootNotMapping("a list")),
+        Some(serde_yaml::Value::Tagged(_)) => Err(SchemaError::RootNotMapping("a tagged value")),
+        Some(_) => Err(SchemaError::RootNotMapping("a scalar")),
     }
 }
 
@@ -105,100 +1020,278 @@ pub struct ValidationResult {
     pub extra: Vec<String>,
     /// Keys with type validation errors (key, expected_type, actual_value)
     pub type_errors: Vec<(String, ValueType, String)>,
+    /// Deprecated aliases used in place of a canonical key (canonical, alias)
+    pub aliased: Vec<(String, String)>,
+    /// Keys set to a forbidden placeholder value (key, value)
+    pub forbidden: Vec<(String, String)>,
+    /// Groups whose policy is broken
+    pub partial_groups: Vec<PartialGroup>,
 }
 
 impl ValidationResult {
     /// Check if validation passed (no errors).
     /// Extra keys are warnings, not errors.
     pub fn is_valid(&self) -> bool {
-        self.missing.is_empty() && self.type_errors.is_empty()
+        self.error_count() == 0
+    }
+
+    /// Get the number of errors (missing keys, type errors, placeholders,
+    /// partially set groups).
+    pub fn error_count(&self) -> usize {
+        self.missing.len()
+            + self.type_errors.len()
+            + self.forbidden.len()
+            + self.partial_groups.len()
     }
 
     /// Check if there are any issues (errors or warnings).
     #[allow(dead_code)]
     pub fn has_issues(&self) -> bool {
-        !self.missing.is_empty() || !self.extra.is_empty() || !self.type_errors.is_empty()
+        !self.is_valid() || self.warning_count() > 0
+    }
+
+    /// Get the number of warnings (extra keys and deprecated aliases).
+    pub fn warning_count(&self) -> usize {
+        self.extra.len() + self.aliased.len()
     }
 }
 
-/// Validate an env file against a schema.
+/// Get the file validation runs against: `env` with the schema default
+/// appended for every field it does not set under its name or an alias.
+pub fn apply_defaults(schema: &Schema, env: &EnvFile) -> EnvFile {
+    let mut effective = env.clone();
+
+    for (key, field) in &schema.fields {
+        let Some(default) = &field.default else {
+            continue;
+        };
+        if field.lookup(key, env).is_none() {
+            effective.entries.insert(key.clone(), default.clone());
+            effective.lines.push(EnvLine::KeyValue {
+                key: key.clone(),
+                quoted_key: false,
+                value: default.clone(),
+                raw: default.clone(),
+                comment: None,
+                line: None,
+            });
+        }
+    }
+
+    effective
+}
+
+/// Validate an env file against a schema, collecting every problem.
 pub fn validate(schema: &Schema, env: &EnvFile) -> ValidationResult {
+    validate_with(schema, env, false)
+}
+
+/// Validate an env file against a schema.
+///
+/// With `fail_fast`, validation stops at the first error (missing key, type
+/// error, or placeholder value) and the result holds only that error.
+pub fn validate_with(schema: &Schema, env: &EnvFile, fail_fast: bool) -> ValidationResult {
     let mut missing = Vec::new();
     let mut extra = Vec::new();
     let mut type_errors = Vec::new();
+    let mut aliased = Vec::new();
+    let mut forbidden = Vec::new();
 
-    // Check for missing keys and type errors
-    for (key, expected_type) in &schema.fields {
-        match env.get(key) {
-            Some(value) => {
-                if !expected_type.validate(value) {
-                    type_errors.push((key.clone(), *expected_type, value.clone()));
+    // Check for missing keys, type errors, and placeholder values
+    for (key, field) in &schema.fields {
+        match field.lookup(key, env) {
+            Some((env_key, value)) => {
+                if env_key != key {
+                    aliased.push((key.clone(), env_key.clone()));
+                }
+
+                if !field.value_type.validate(value) {
+                    type_errors.push((env_key.clone(), field.value_type.clone(), value.clone()));
+                } else if field.is_forbidden(value) {
+                    forbidden.push((env_key.clone(), value.clone()));
                 }
             }
+            None if schema.in_group(key) => {}
             None => {
                 missing.push(key.clone());
             }
         }
+
+        let failed = !missing.is_empty() || !type_errors.is_empty() || !forbidden.is_empty();
+        if fail_fast && failed {
+            return ValidationResult {
+                missing,
+                extra,
+                type_errors,
+                aliased: Vec::new(),
+                forbidden,
+                partial_groups: Vec::new(),
+            };
+        }
     }
 
-    // Check for extra keys
-    for key in env.keys() {
-        if !schema.fields.contains_key(key) {
+    // Check for extra keys, and validate keys typed by a pattern
+    for (key, value) in &env.entries {
+        if schema.declares(key) {
+            continue;
+        }
+        let Some((_, field)) = schema.pattern_for(key) else {
             extra.push(key.clone());
+            continue;
+        };
+
+        if !field.value_type.validate(value) {
+            type_errors.push((key.clone(), field.value_type.clone(), value.clone()));
+        } else if field.is_forbidden(value) {
+            forbidden.push((key.clone(), value.clone()));
         }
     }
 
+    let partial_groups = schema
+        .groups
+        .iter()
+        .filter_map(|group| {
+            let (present, missing): (Vec<String>, Vec<String>) =
+                group.keys.iter().cloned().partition(|key| {
+                    schema
+                        .fields
+                        .get(key)
+                        .is_some_and(|field| field.lookup(key, env).is_some())
+                });
+            let partial = !present.is_empty() && !missing.is_empty();
+            partial.then(|| PartialGroup {
+                name: group.name.clone(),
+                present,
+                missing,
+            })
+        })
+        .collect();
+
     // Sort for deterministic output
     missing.sort();
     extra.sort();
     type_errors.sort_by(|a, b| a.0.cmp(&b.0));
+    aliased.sort();
+    forbidden.sort();
 
     ValidationResult {
         missing,
         extra,
         type_errors,
+        aliased,
+        forbidden,
+        partial_groups,
     }
 }
 
-/// Run the check command.
-pub fn run_check(schema_path: &Path, env_path: &Path) -> Result<bool, SchemaError> {
-    let schema = Schema::from_path(schema_path)?;
-    let env = EnvFile::from_path(env_path)?;
-    let result = validate(&schema, &env);
+/// Everything the schema says about a single key, plus how it fared.
+#[derive(Debug)]
+pub struct Explanation<'a> {
+    /// Canonical key name
+    pub key: &'a str,
+    /// Pattern whose definition applies, for a key declared only by one
+    pub pattern: Option<&'a str>,
+    /// Schema definition for the key
+    pub field: &'a Field,
+    /// Env key and value that matched, if any
+    pub found: Option<(&'a String, &'a String)>,
+}
 
-    // Print missing keys (errors)
-    for key in &result.missing {
-        println!("error: missing required key: {key}");
+impl Explanation<'_> {
+    /// Check if the key is present with a valid value.
+    ///
+    /// Keys typed by a pattern are optional, so they also pass when absent.
+    pub fn passed(&self) -> bool {
+        match self.found {
+            Some((_, value)) => {
+                self.field.value_type.validate(value) && !self.field.is_forbidden(value)
+            }
+            None => self.pattern.is_some(),
+        }
     }
 
-    // Print type errors
-    for (key, expected_type, actual_value) in &result.type_errors {
-        println!(
-            "error: key '{key}' has invalid value '{actual_value}' (expected {})",
-            expected_type.description()
-        );
-    }
+    /// Render the explanation as human-readable lines.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![format!("key: {}", self.key)];
+        if let Some(pattern) = self.pattern {
+            lines.push(format!("pattern: {pattern}"));
+        }
+        lines.push(format!(
+            "type: {} ({})",
+            self.field.value_type.name(),
+            self.field.value_type.description()
+        ));
 
-    // Print extra keys (warnings)
-    for key in &result.extra {
-        println!("warning: extra key not in schema: {key}");
-    }
+        if !self.field.aliases.is_empty() {
+            lines.push(format!("aliases: {}", self.field.aliases.join(", ")));
+        }
+        if let Some(description) = &self.field.description {
+            lines.push(format!("description: {description}"));
+        }
+        if !self.field.forbid.is_empty() {
+            lines.push(format!("forbid: {}", self.field.forbid.join(", ")));
+        }
+        if let Some(default) = &self.field.default {
+            lines.push(format!("default: {default}"));
+        }
 
-    // Summary
-    if result.is_valid() {
-        if result.extra.is_empty() {
-            println!("✓ validation passed");
-        } else {
-            println!("✓ validation passed with {} warning(s)", result.extra.len());
+        match self.found {
+            Some((env_key, value)) => {
+                if env_key != self.key {
+                    lines.push(format!(
+                        "value: {value} (from deprecated alias '{env_key}')"
+                    ));
+                } else {
+                    lines.push(format!("value: {value}"));
+                }
+                if self.passed() {
+                    lines.push("result: pass".to_string());
+                } else if self.field.is_forbidden(value) {
+                    lines.push("result: fail (placeholder value)".to_string());
+                } else {
+                    lines.push(format!(
+                        "result: fail (expected {})",
+                        self.field.value_type.description()
+                    ));
+                }
+            }
+            None if self.pattern.is_some() => {
+                lines.push("value: (missing)".to_string());
+                lines.push("result: pass (keys matching a pattern are optional)".to_string());
+            }
+            None => {
+                lines.push("value: (missing)".to_string());
+                lines.push("result: fail (missing required key)".to_string());
+            }
         }
-        Ok(true)
-    } else {
-        let error_count = result.missing.len() + result.type_errors.len();
-        println!("✗ validation failed with {error_count} error(s)");
-        Ok(false)
+
+        lines
     }
 }
 
+/// Explain how a single key is declared and validated.
+///
+/// Returns `None` if the key is neither declared in the schema nor matched
+/// by one of its patterns.
+pub fn explain<'a>(schema: &'a Schema, env: &'a EnvFile, key: &'a str) -> Option<Explanation<'a>> {
+    if let Some((key, field)) = schema.fields.get_key_value(key) {
+        return Some(Explanation {
+            key,
+            pattern: None,
+            field,
+            found: field.lookup(key, env),
+        });
+    }
+
+    let (pattern, field) = schema.pattern_for(key)?;
+    Some(Explanation {
+        key,
+        pattern: Some(pattern),
+        field,
+        found: env.entries.get_key_value(key),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,9 +1305,9 @@ DATABASE_URL: string
 "#;
         let schema = Schema::from_str(yaml).unwrap();
 
-        assert_eq!(schema.fields.get("PORT"), Some(&ValueType::Int));
-        assert_eq!(schema.fields.get("DEBUG"), Some(&ValueType::Bool));
-        assert_eq!(schema.fields.get("DATABASE_URL"), Some(&ValueType::String));
+        assert_eq!(schema.fields["PORT"].value_type, ValueType::Int);
+        assert_eq!(schema.fields["DEBUG"].value_type, ValueType::Bool);
+        assert_eq!(schema.fields["DATABASE_URL"].value_type, ValueType::String);
     }
 
     #[test]
@@ -225,8 +1318,8 @@ B: boolean
 "#;
         let schema = Schema::from_str(yaml).unwrap();
 
-        assert_eq!(schema.fields.get("A"), Some(&ValueType::Int));
-        assert_eq!(schema.fields.get("B"), Some(&ValueType::Bool));
+        assert_eq!(schema.fields["A"].value_type, ValueType::Int);
+        assert_eq!(schema.fields["B"].value_type, ValueType::Bool);
     }
 
     #[test]
@@ -306,4 +1399,799 @@ PORT: number
 
         assert!(result.is_valid());
     }
+
+    #[test]
+    fn test_schema_default() {
+        let schema = Schema::from_str(
+            "PORT: {type: int, default: 8080}\nDEBUG: {type: bool, default: false}\nNAME: string\n",
+        )
+        .unwrap();
+
+        assert_eq!(schema.fields["PORT"].default.as_deref(), Some("8080"));
+        assert_eq!(schema.fields["DEBUG"].default.as_deref(), Some("false"));
+        assert_eq!(schema.fields["NAME"].default, None);
+    }
+
+    #[test]
+    fn test_schema_default_must_match_type() {
+        let result = Schema::from_str("PORT: {type: int, default: http}\n");
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "invalid default for key 'PORT': 'http' is not an integer (e.g., 42, -10)"
+        );
+        assert!(matches!(
+            Schema::from_str("PORT: {type: int, default: [1]}\n"),
+            Err(SchemaError::InvalidDefault(..))
+        ));
+    }
+
+    #[test]
+    fn test_pattern_keys() {
+        let schema = Schema::from_str("NAME: string\n\"FEATURE_*\": bool\n").unwrap();
+        let env = EnvFile::from_str(
+            "NAME=app\nFEATURE_SEARCH=true\nFEATURE_BETA=false\nFEATURE_CHAT=maybe\nOTHER=1\n",
+        )
+        .unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(result.missing.is_empty());
+        assert_eq!(result.extra, vec!["OTHER"]);
+        assert_eq!(result.type_errors.len(), 1);
+        assert_eq!(result.type_errors[0].0, "FEATURE_CHAT");
+        // Patterns never make a key required
+        assert!(validate(&schema, &EnvFile::from_str("NAME=app").unwrap()).is_valid());
+    }
+
+    #[test]
+    fn test_exact_key_wins_over_pattern() {
+        let schema = Schema::from_str(
+            "FEATURE_LEVEL: int\n\"FEATURE_*\": bool\n\"FEATURE_MAX_*\": int\n\"*\": string\n",
+        )
+        .unwrap();
+        let env =
+            EnvFile::from_str("FEATURE_LEVEL=3\nFEATURE_MAX_USERS=10\nFEATURE_ON=true\n").unwrap();
+
+        assert!(validate(&schema, &env).is_valid());
+        assert_eq!(schema.pattern_for("FEATURE_LEVEL"), None);
+        assert_eq!(
+            schema.pattern_for("FEATURE_MAX_USERS").unwrap().0,
+            "FEATURE_MAX_*"
+        );
+        assert_eq!(schema.pattern_for("PORT").unwrap().0, "*");
+    }
+
+    #[test]
+    fn test_pattern_rejects_aliases_and_defaults() {
+        assert_eq!(
+            Schema::from_str("\"FEATURE_*\": {type: bool, default: false}\n")
+                .unwrap_err()
+                .to_string(),
+            "setting 'default' cannot be used on pattern key 'FEATURE_*'"
+        );
+        assert!(matches!(
+            Schema::from_str("\"FEATURE_*\": {type: bool, aliases: [FLAG_*]}\n"),
+            Err(SchemaError::PatternSetting("aliases", _))
+        ));
+    }
+
+    const AWS_SCHEMA: &str = "\
+NAME: string
+AWS_KEY: string
+AWS_SECRET: string
+AWS_REGION: string
+groups:
+  - {group: aws, keys: [AWS_KEY, AWS_SECRET, AWS_REGION], policy: all_or_none}
+";
+
+    #[test]
+    fn test_group_all_or_none() {
+        let schema = Schema::from_str(AWS_SCHEMA).unwrap();
+        let check = |content: &str| validate(&schema, &EnvFile::from_str(content).unwrap());
+
+        let all = check("NAME=app\nAWS_KEY=k\nAWS_SECRET=s\nAWS_REGION=eu-west-1\n");
+        assert!(all.is_valid());
+
+        let none = check("NAME=app\n");
+        assert!(none.is_valid());
+        assert!(none.missing.is_empty());
+
+        let partial = check("NAME=app\nAWS_KEY=k\nAWS_SECRET=s\n");
+        assert!(!partial.is_valid());
+        assert_eq!(
+            partial.partial_groups,
+            vec![PartialGroup {
+                name: "aws".to_string(),
+                present: vec!["AWS_KEY".to_string(), "AWS_SECRET".to_string()],
+                missing: vec!["AWS_REGION".to_string()],
+            }]
+        );
+        // Members outside the group are still required
+        assert_eq!(check("AWS_KEY=k").missing, vec!["NAME"]);
+    }
+
+    #[test]
+    fn test_group_errors() {
+        assert_eq!(
+            Schema::from_str(
+                "A: string\ngroups:\n  - {group: g, keys: [A, B], policy: all_or_none}\n"
+            )
+            .unwrap_err()
+            .to_string(),
+            "group 'g' lists key 'B', which the schema does not declare"
+        );
+        assert!(matches!(
+            Schema::from_str("A: string\ngroups:\n  - {group: g, keys: [A], policy: some}\n"),
+            Err(SchemaError::InvalidGroups(_))
+        ));
+        // A key named `groups` is still an ordinary field
+        let schema = Schema::from_str("groups: int\n").unwrap();
+        assert_eq!(schema.fields["groups"].value_type, ValueType::Int);
+    }
+
+    #[test]
+    fn test_schema_version_supported() {
+        let schema = Schema::from_str(
+            "_version: 1
+PORT: int
+",
+        )
+        .unwrap();
+        assert_eq!(schema.fields.len(), 1);
+        assert!(!schema.fields.contains_key("_version"));
+        assert_eq!(schema.fields["PORT"].value_type, ValueType::Int);
+    }
+
+    #[test]
+    fn test_schema_version_unsupported() {
+        let result = Schema::from_str(
+            "_version: 2
+PORT: int
+",
+        );
+        assert!(matches!(result, Err(SchemaError::UnsupportedVersion(2))));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "schema _version 2 is newer than this envcraft supports (up to 1); upgrade envcraft"
+        );
+
+        for version in ["0", "one", "1.5", "-1"] {
+            assert!(
+                matches!(
+                    Schema::from_str(&format!("_version: {version}\nPORT: int\n")),
+                    Err(SchemaError::InvalidVersion(_))
+                ),
+                "{version}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_version_absent() {
+        // Schemas written before versioning are read as version 1
+        let schema = Schema::from_str("PORT: int\n").unwrap();
+        assert_eq!(schema.fields.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_defaults_fills_missing_keys() {
+        let schema = Schema::from_str(
+            "PORT: {type: int, default: 8080}\nHOST: {type: string, default: localhost, aliases: [OLD_HOST]}\n",
+        )
+        .unwrap();
+        let env = EnvFile::from_str("OLD_HOST=example.com\n").unwrap();
+        let effective = apply_defaults(&schema, &env);
+
+        assert_eq!(effective.get("PORT"), Some(&"8080".to_string()));
+        assert!(!effective.contains_key("HOST"));
+        assert!(validate(&schema, &effective).missing.is_empty());
+    }
+
+    #[test]
+    fn test_schema_root_list_rejected() {
+        let result = Schema::from_str("- PORT\n- DEBUG\n");
+
+        assert!(matches!(result, Err(SchemaError::RootNotMapping("a list"))));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "schema root must be a mapping of key to type, found a list"
+        );
+    }
+
+    #[test]
+    fn test_schema_root_scalar_rejected() {
+        let result = Schema::from_str("just a string\n");
+
+        assert!(matches!(
+            result,
+            Err(SchemaError::RootNotMapping("a scalar"))
+        ));
+    }
+
+    #[test]
+    fn test_schema_multiple_documents_rejected() {
+        let result = Schema::from_str("PORT: int\n---\nDEBUG: bool\n");
+
+        assert!(matches!(result, Err(SchemaError::MultipleDocuments(2))));
+    }
+
+    #[test]
+    fn test_schema_key_without_type() {
+        let result = Schema::from_str("PORT:\nDEBUG: bool\n");
+
+        assert!(matches!(&result, Err(SchemaError::MissingType(key)) if key == "PORT"));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "no type specified for key 'PORT' (e.g. PORT: string)"
+        );
+        assert!(matches!(
+            Schema::from_str("PORT: ''\n"),
+            Err(SchemaError::MissingType(key)) if key == "PORT"
+        ));
+    }
+
+    #[test]
+    fn test_schema_spec_without_type() {
+        assert!(matches!(
+            Schema::from_str("PORT: {description: Listen port}\n"),
+            Err(SchemaError::MissingType(key)) if key == "PORT"
+        ));
+        assert!(matches!(
+            Schema::from_str("PORT: {type: null}\n"),
+            Err(SchemaError::MissingType(key)) if key == "PORT"
+        ));
+    }
+
+    #[test]
+    fn test_schema_empty_document() {
+        let schema = Schema::from_str("# nothing declared yet\n").unwrap();
+
+        assert!(schema.fields.is_empty());
+    }
+
+    #[test]
+    fn test_schema_anchors_resolved() {
+        let yaml = "PORT: &num int\nWORKERS: *num\n";
+        let schema = Schema::from_str(yaml).unwrap();
+
+        assert_eq!(schema.fields["WORKERS"].value_type, ValueType::Int);
+    }
+
+    #[test]
+    fn test_schema_structured_field() {
+        let yaml = r#"
+NEW_NAME: {type: int, aliases: [OLD_NAME]}
+"#;
+        let schema = Schema::from_str(yaml).unwrap();
+
+        assert_eq!(schema.fields["NEW_NAME"].value_type, ValueType::Int);
+        assert_eq!(schema.fields["NEW_NAME"].aliases, vec!["OLD_NAME"]);
+    }
+
+    #[test]
+    fn test_schema_unknown_setting() {
+        let result = Schema::from_str("PORT: {type: int, maxx: 10}");
+
+        match result {
+            Err(SchemaError::UnknownSetting(setting, key)) => {
+                assert_eq!(setting, "maxx");
+                assert_eq!(key, "PORT");
+            }
+            other => panic!("Expected UnknownSetting error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validation_alias_canonical_name() {
+        let schema = Schema::from_str("NEW_NAME: {type: string, aliases: [OLD_NAME]}").unwrap();
+        let env = EnvFile::from_str("NEW_NAME=value").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(result.is_valid());
+        assert!(result.aliased.is_empty());
+        assert!(result.extra.is_empty());
+    }
+
+    #[test]
+    fn test_validation_alias_used() {
+        let schema = Schema::from_str("NEW_NAME: {type: string, aliases: [OLD_NAME]}").unwrap();
+        let env = EnvFile::from_str("OLD_NAME=value").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(result.is_valid());
+        assert_eq!(
+            result.aliased,
+            vec![("NEW_NAME".to_string(), "OLD_NAME".to_string())]
+        );
+        assert!(result.extra.is_empty());
+        assert_eq!(result.warning_count(), 1);
+    }
+
+    #[test]
+    fn test_validation_alias_type_checked() {
+        let schema = Schema::from_str("PORT: {type: int, aliases: [OLD_PORT]}").unwrap();
+        let env = EnvFile::from_str("OLD_PORT=abc").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(!result.is_valid());
+        assert_eq!(result.type_errors[0].0, "OLD_PORT");
+    }
+
+    #[test]
+    fn test_validation_alias_neither_present() {
+        let schema = Schema::from_str("NEW_NAME: {type: string, aliases: [OLD_NAME]}").unwrap();
+        let env = EnvFile::from_str("OTHER=value").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(!result.is_valid());
+        assert_eq!(result.missing, vec!["NEW_NAME"]);
+        assert!(result.aliased.is_empty());
+    }
+
+    #[test]
+    fn test_value_type_equivalent() {
+        assert!(ValueType::Int.equivalent("8080", "08080"));
+        assert!(ValueType::Int.equivalent("+1", "1"));
+        assert!(!ValueType::Int.equivalent("8080", "8081"));
+        assert!(!ValueType::Int.equivalent("abc", "ABC"));
+        assert!(ValueType::Bool.equivalent("TRUE", "true"));
+        assert!(!ValueType::Bool.equivalent("true", "false"));
+        assert!(!ValueType::String.equivalent("a", "A"));
+    }
+
+    #[test]
+    fn test_value_type_fix() {
+        assert_eq!(ValueType::Bool.fix("yes"), Some("true".to_string()));
+        assert_eq!(ValueType::Bool.fix(" OFF "), Some("false".to_string()));
+        assert_eq!(ValueType::Bool.fix(" TRUE"), Some("TRUE".to_string()));
+        assert_eq!(ValueType::Int.fix(" 8080 "), Some("8080".to_string()));
+
+        // Valid values are left alone, and guesses are not made
+        assert_eq!(ValueType::Bool.fix("true"), None);
+        assert_eq!(ValueType::Bool.fix("maybe"), None);
+        assert_eq!(ValueType::Int.fix("eighty"), None);
+        assert_eq!(ValueType::String.fix(" padded "), None);
+    }
+
+    #[test]
+    fn test_value_type_infer() {
+        assert_eq!(ValueType::infer("8080"), ValueType::Int);
+        assert_eq!(ValueType::infer("-1"), ValueType::Int);
+        assert_eq!(ValueType::infer("TRUE"), ValueType::Bool);
+        assert_eq!(ValueType::infer("yes"), ValueType::String);
+        assert_eq!(ValueType::infer(""), ValueType::String);
+    }
+
+    #[test]
+    fn test_parse_byte_size_valid() {
+        assert_eq!(parse_byte_size("512MB", false), Some(512_000_000));
+        assert_eq!(parse_byte_size("1.5GB", false), Some(1_500_000_000));
+        assert_eq!(parse_byte_size("1024KiB", false), Some(1024 * 1024));
+        assert_eq!(parse_byte_size("2 gib", false), Some(2 << 30));
+        assert_eq!(parse_byte_size("100B", false), Some(100));
+    }
+
+    #[test]
+    fn test_parse_byte_size_invalid() {
+        assert_eq!(parse_byte_size("512XB", false), None);
+        assert_eq!(parse_byte_size("MB", false), None);
+        assert_eq!(parse_byte_size("1.2.3MB", false), None);
+        assert_eq!(parse_byte_size("-5MB", false), None);
+        assert_eq!(parse_byte_size("1.5B", false), None);
+    }
+
+    #[test]
+    fn test_parse_byte_size_bare_numbers() {
+        assert_eq!(parse_byte_size("4096", false), None);
+        assert_eq!(parse_byte_size("4096", true), Some(4096));
+    }
+
+    #[test]
+    fn test_date_values() {
+        assert!(ValueType::Date.validate("2024-01-15"));
+        assert!(ValueType::Date.validate("2024-02-29"));
+        assert!(!ValueType::Date.validate("2023-02-29"));
+        assert!(!ValueType::Date.validate("2024-1-15"));
+        assert!(!ValueType::Date.validate("2024-01-15T10:00:00Z"));
+        assert!(!ValueType::Date.validate("15/01/2024"));
+    }
+
+    #[test]
+    fn test_datetime_values() {
+        assert!(ValueType::DateTime.validate("2024-01-15T10:00:00Z"));
+        assert!(ValueType::DateTime.validate("2024-01-15T10:00:00.5+02:00"));
+        assert!(!ValueType::DateTime.validate("2024-01-15T10:00:00"));
+        assert!(!ValueType::DateTime.validate("2024-01-15"));
+        assert!(!ValueType::DateTime.validate("2024-01-15T25:00:00Z"));
+        assert!(ValueType::DateTime.equivalent("2024-01-15T10:00:00Z", "2024-01-15T12:00:00+02:00"));
+    }
+
+    #[test]
+    fn test_date_fields() {
+        let schema = Schema::from_str(
+            "RELEASE_DATE: date
+START_AT: datetime
+",
+        )
+        .unwrap();
+        let valid = EnvFile::from_str(
+            "RELEASE_DATE=2024-01-15
+START_AT=2024-01-15T10:00:00Z",
+        )
+        .unwrap();
+        let invalid = EnvFile::from_str(
+            "RELEASE_DATE=2024-13-01
+START_AT=2024-01-15 10:00",
+        )
+        .unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        assert_eq!(validate(&schema, &invalid).type_errors.len(), 2);
+    }
+
+    #[test]
+    fn test_bytesize_field() {
+        let schema =
+            Schema::from_str("MEMORY_LIMIT: bytesize\nCACHE: {type: bytesize, allow_bare: true}\n")
+                .unwrap();
+        let valid = EnvFile::from_str("MEMORY_LIMIT=512MB\nCACHE=4096").unwrap();
+        let invalid = EnvFile::from_str("MEMORY_LIMIT=512XB\nCACHE=4096").unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        assert_eq!(validate(&schema, &invalid).type_errors[0].0, "MEMORY_LIMIT");
+        assert!(schema.fields["MEMORY_LIMIT"]
+            .value_type
+            .equivalent("1KiB", "1024B"));
+    }
+
+    #[test]
+    fn test_base64_values() {
+        assert_eq!(decoded_base64_len("aGVsbG8="), Some(5));
+        assert_eq!(decoded_base64_len("aGVsbA=="), Some(4));
+        assert_eq!(decoded_base64_len("aGVs"), Some(3));
+        assert_eq!(decoded_base64_len("aGVsbG8"), None);
+        assert_eq!(decoded_base64_len("aGVsb==="), None);
+        assert_eq!(decoded_base64_len("aG=sbG8="), None);
+        assert_eq!(decoded_base64_len("aGVs-G8="), None);
+    }
+
+    #[test]
+    fn test_hex_values() {
+        assert_eq!(decoded_hex_len("deadBEEF"), Some(4));
+        assert_eq!(decoded_hex_len("abc"), None);
+        assert_eq!(decoded_hex_len("0x12"), None);
+        assert!(ValueType::Hex { bytes: None }.equivalent("DEADBEEF", "deadbeef"));
+    }
+
+    #[test]
+    fn test_encoded_fields_with_byte_length() {
+        let schema = Schema::from_str(
+            "SECRET: {type: base64, bytes: 4}\nKEY: {type: hex, bytes: 2}\nTOKEN: base64\n",
+        )
+        .unwrap();
+        let valid = EnvFile::from_str("SECRET=aGVsbA==\nKEY=beef\nTOKEN=aGVsbG8=").unwrap();
+        let invalid = EnvFile::from_str("SECRET=aGVsbG8=\nKEY=bee\nTOKEN=aGVsbG8").unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        let errors: Vec<_> = validate(&schema, &invalid)
+            .type_errors
+            .into_iter()
+            .map(|(key, _, _)| key)
+            .collect();
+        assert_eq!(errors, vec!["KEY", "SECRET", "TOKEN"]);
+        assert_eq!(
+            schema.fields["SECRET"].value_type.description(),
+            "base64 data decoding to 4 bytes"
+        );
+    }
+
+    #[test]
+    fn test_bytes_setting_only_for_encoded_types() {
+        let result = Schema::from_str("PORT: {type: int, bytes: 4}\n");
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "setting 'bytes' for key 'PORT' only applies to types: base64, hex"
+        );
+    }
+
+    #[test]
+    fn test_host_values() {
+        let host = ValueType::from_str("host", "BIND_ADDR").unwrap();
+
+        assert!(host.validate("192.168.1.10"));
+        assert!(host.validate("::1"));
+        assert!(host.validate("2001:db8::8a2e:370:7334"));
+        assert!(host.validate("db.internal"));
+        assert!(host.validate("localhost"));
+        assert!(host.validate("api-1.example.com."));
+        assert!(!host.validate("-bad-.example.com"));
+        assert!(!host.validate("exa mple.com"));
+        assert!(!host.validate("256.1.1.1"));
+        assert!(!host.validate("db..internal"));
+        assert!(!host.validate(""));
+        assert!(host.equivalent("DB.Internal", "db.internal"));
+        assert!(host.equivalent("::1", "0:0:0:0:0:0:0:1"));
+    }
+
+    #[test]
+    fn test_host_allow_restricts_kinds() {
+        let schema = Schema::from_str(
+            "BIND_ADDR: {type: host, allow: [ip]}\nDB_HOST: {type: host, allow: [hostname]}\n",
+        )
+        .unwrap();
+        let valid = EnvFile::from_str("BIND_ADDR=0.0.0.0\nDB_HOST=db.internal").unwrap();
+        let invalid = EnvFile::from_str("BIND_ADDR=localhost\nDB_HOST=10.0.0.5").unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        let errors: Vec<_> = validate(&schema, &invalid)
+            .type_errors
+            .into_iter()
+            .map(|(key, _, _)| key)
+            .collect();
+        assert_eq!(errors, vec!["BIND_ADDR", "DB_HOST"]);
+        assert_eq!(
+            schema.fields["BIND_ADDR"].value_type.description(),
+            "an IP address (e.g., 10.0.0.1, ::1)"
+        );
+    }
+
+    #[test]
+    fn test_host_allow_rejected() {
+        assert_eq!(
+            Schema::from_str("H: {type: host, allow: [ipv4]}\n")
+                .unwrap_err()
+                .to_string(),
+            "invalid setting 'allow' for key 'H': unknown kind 'ipv4' (expected: ip, hostname)"
+        );
+        assert!(matches!(
+            Schema::from_str("H: {type: host, allow: []}\n"),
+            Err(SchemaError::InvalidSetting("allow", key, _)) if key == "H"
+        ));
+        assert!(matches!(
+            Schema::from_str("PORT: {type: int, allow: [ip]}\n"),
+            Err(SchemaError::InapplicableSetting("allow", key, "host")) if key == "PORT"
+        ));
+    }
+
+    #[test]
+    fn test_semver_values() {
+        let version = ValueType::from_str("version", "APP_VERSION").unwrap();
+
+        assert_eq!(version, ValueType::SemVer { range: None });
+        assert!(version.validate("1.2.3"));
+        assert!(version.validate("1.2.3-rc.1"));
+        assert!(version.validate("1.0.0+build.5"));
+        assert!(!version.validate("1.2"));
+        assert!(!version.validate("v1.2.3"));
+        assert!(!version.validate("latest"));
+    }
+
+    #[test]
+    fn test_semver_range() {
+        let schema =
+            Schema::from_str("APP_VERSION: {type: semver, range: \">=1.0.0, <2.0.0\"}\n").unwrap();
+        let valid = EnvFile::from_str("APP_VERSION=1.4.0").unwrap();
+        let too_old = EnvFile::from_str("APP_VERSION=0.9.9").unwrap();
+        let not_version = EnvFile::from_str("APP_VERSION=latest").unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        assert!(!validate(&schema, &too_old).is_valid());
+        assert!(!validate(&schema, &not_version).is_valid());
+        assert_eq!(
+            schema.fields["APP_VERSION"].value_type.description(),
+            "a semantic version matching >=1.0.0, <2.0.0"
+        );
+    }
+
+    #[test]
+    fn test_semver_range_rejected() {
+        assert!(matches!(
+            Schema::from_str("V: {type: semver, range: \"about 1\"}\n"),
+            Err(SchemaError::InvalidSetting("range", key, _)) if key == "V"
+        ));
+        assert!(matches!(
+            Schema::from_str("V: {type: string, range: \">=1.0.0\"}\n"),
+            Err(SchemaError::InapplicableSetting("range", key, "semver")) if key == "V"
+        ));
+    }
+
+    #[test]
+    fn test_json_values() {
+        let schema = Schema::from_str("FLAGS: json\nHOSTS: json\n").unwrap();
+        let valid =
+            EnvFile::from_str("FLAGS={\"a\":true,\"b\":[1,2]}\nHOSTS='[\"a\", \"b\"]'\n").unwrap();
+        let invalid = EnvFile::from_str("FLAGS={a:true}\nHOSTS=[\"a\",]\n").unwrap();
+
+        assert_eq!(valid.get("HOSTS"), Some(&"[\"a\", \"b\"]".to_string()));
+        assert!(validate(&schema, &valid).is_valid());
+        assert_eq!(validate(&schema, &invalid).type_errors.len(), 2);
+        assert!(schema.fields["FLAGS"]
+            .value_type
+            .equivalent("{\"a\": 1, \"b\": 2}", "{\"b\":2,\"a\":1}"));
+    }
+
+    #[test]
+    fn test_json_nested_schema() {
+        let schema = Schema::from_str(
+            "FLAGS:\n  type: json\n  schema:\n    type: object\n    required: [enabled]\n    properties:\n      enabled: {type: boolean}\n",
+        )
+        .unwrap();
+        let valid = EnvFile::from_str("FLAGS={\"enabled\": true}").unwrap();
+        let missing = EnvFile::from_str("FLAGS={\"other\": 1}").unwrap();
+        let wrong_type = EnvFile::from_str("FLAGS={\"enabled\": \"yes\"}").unwrap();
+
+        assert!(validate(&schema, &valid).is_valid());
+        assert!(!validate(&schema, &missing).is_valid());
+        assert!(!validate(&schema, &wrong_type).is_valid());
+    }
+
+    #[test]
+    fn test_json_schema_setting_rejected() {
+        assert!(matches!(
+            Schema::from_str("F: {type: json, schema: {type: object, maxProperties: 2}}\n"),
+            Err(SchemaError::InvalidSetting("schema", key, _)) if key == "F"
+        ));
+        assert!(matches!(
+            Schema::from_str("F: {type: string, schema: {type: object}}\n"),
+            Err(SchemaError::InapplicableSetting("schema", key, "json")) if key == "F"
+        ));
+    }
+
+    #[test]
+    fn test_schema_origin_after_merge() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path().join("base.yml");
+        let extra = dir.path().join("extra.yml");
+        fs::write(&base, "PORT: int\nHOST: string\n").unwrap();
+        fs::write(&extra, "API_KEY: {type: string, aliases: [OLD_KEY]}\n").unwrap();
+
+        let mut schema = Schema::from_path(&base).unwrap();
+        assert_eq!(schema.origin("PORT"), None);
+
+        schema.merge(Schema::from_path(&extra).unwrap());
+        assert_eq!(schema.origin("PORT"), Some(base.as_path()));
+        assert_eq!(schema.origin("API_KEY"), Some(extra.as_path()));
+        assert_eq!(schema.origin("OLD_KEY"), Some(extra.as_path()));
+        assert_eq!(schema.origin("UNKNOWN"), None);
+    }
+
+    #[test]
+    fn test_schema_infer() {
+        let env = EnvFile::from_str("PORT=8080\nDEBUG=false\nNAME=app").unwrap();
+        let schema = Schema::infer(&env);
+
+        assert_eq!(schema.fields["PORT"].value_type, ValueType::Int);
+        assert_eq!(schema.fields["DEBUG"].value_type, ValueType::Bool);
+        assert_eq!(schema.fields["NAME"].value_type, ValueType::String);
+    }
+
+    #[test]
+    fn test_append_inferred() {
+        let content = "# Service settings\nPORT: int # listen port\n";
+        let keys = [
+            ("DEBUG", "true"),
+            ("PORT", "abc"),
+            ("NAME", "app"),
+            ("DEBUG", "x"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+
+        let (output, added) = Schema::append_inferred(content, &keys).unwrap();
+        assert_eq!(
+            output,
+            "# Service settings\nPORT: int # listen port\nDEBUG: bool\nNAME: string\n"
+        );
+        assert_eq!(added, ["DEBUG", "NAME"]);
+
+        let (output, _) = Schema::append_inferred("A: int", &keys[2..3]).unwrap();
+        assert_eq!(output, "A: int\nNAME: string\n");
+        let (output, added) = Schema::append_inferred(&output, &keys[2..3]).unwrap();
+        assert_eq!(output, "A: int\nNAME: string\n");
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn test_explain_passing_key() {
+        let schema = Schema::from_str("PORT: {type: int, description: HTTP listen port}").unwrap();
+        let env = EnvFile::from_str("PORT=8080").unwrap();
+        let explanation = explain(&schema, &env, "PORT").unwrap();
+        let lines = explanation.render();
+
+        assert!(explanation.passed());
+        assert!(lines.contains(&"type: int (an integer (e.g., 42, -10))".to_string()));
+        assert!(lines.contains(&"description: HTTP listen port".to_string()));
+        assert!(lines.contains(&"value: 8080".to_string()));
+        assert!(lines.contains(&"result: pass".to_string()));
+    }
+
+    #[test]
+    fn test_explain_default() {
+        let schema = Schema::from_str("PORT: {type: int, default: 8080}").unwrap();
+        let env = apply_defaults(&schema, &EnvFile::from_str("").unwrap());
+        let explanation = explain(&schema, &env, "PORT").unwrap();
+        let lines = explanation.render();
+
+        assert!(explanation.passed());
+        assert!(lines.contains(&"default: 8080".to_string()));
+        assert!(lines.contains(&"value: 8080".to_string()));
+        assert!(
+            !explain(&Schema::from_str("PORT: int").unwrap(), &env, "PORT")
+                .unwrap()
+                .render()
+                .iter()
+                .any(|line| line.starts_with("default:"))
+        );
+    }
+
+    #[test]
+    fn test_explain_failing_key() {
+        let schema = Schema::from_str("PORT: int").unwrap();
+        let env = EnvFile::from_str("PORT=eighty").unwrap();
+        let explanation = explain(&schema, &env, "PORT").unwrap();
+        let lines = explanation.render();
+
+        assert!(!explanation.passed());
+        assert!(lines.contains(&"type: int (an integer (e.g., 42, -10))".to_string()));
+        assert!(lines.contains(&"value: eighty".to_string()));
+        assert!(lines.contains(&"result: fail (expected an integer (e.g., 42, -10))".to_string()));
+    }
+
+    #[test]
+    fn test_explain_missing_key() {
+        let schema = Schema::from_str("PORT: int").unwrap();
+        let env = EnvFile::from_str("").unwrap();
+        let explanation = explain(&schema, &env, "PORT").unwrap();
+
+        assert!(!explanation.passed());
+        assert!(explanation
+            .render()
+            .contains(&"result: fail (missing required key)".to_string()));
+    }
+
+    #[test]
+    fn test_explain_undeclared_key() {
+        let schema = Schema::from_str("PORT: int").unwrap();
+        let env = EnvFile::from_str("OTHER=1").unwrap();
+
+        assert!(explain(&schema, &env, "OTHER").is_none());
+    }
+
+    #[test]
+    fn test_validate_forbidden_placeholder() {
+        let schema = Schema::from_str("API_KEY: {type: string, forbid: [changeme, TODO]}").unwrap();
+        let env = EnvFile::from_str("API_KEY=ChangeMe").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(!result.is_valid());
+        assert_eq!(
+            result.forbidden,
+            vec![("API_KEY".to_string(), "ChangeMe".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_validate_fail_fast_stops_at_first_error() {
+        let schema = Schema::from_str("A: int\nB: int\nC: bool\n").unwrap();
+        let env = EnvFile::from_str("A=one\nEXTRA=1\n").unwrap();
+
+        let all = validate(&schema, &env);
+        assert_eq!(all.error_count(), 3);
+        assert_eq!(all.extra, vec!["EXTRA"]);
+
+        let first = validate_with(&schema, &env, true);
+        assert_eq!(first.error_count(), 1);
+        assert_eq!(first.type_errors[0].0, "A");
+        assert!(first.extra.is_empty());
+    }
+
+    #[test]
+    fn test_validate_forbid_allows_real_value() {
+        let schema = Schema::from_str("API_KEY: {type: string, forbid: [changeme, TODO]}").unwrap();
+        let env = EnvFile::from_str("API_KEY=sk-live-123").unwrap();
+        let result = validate(&schema, &env);
+
+        assert!(result.is_valid());
+        assert!(result.forbidden.is_empty());
+    }
 }
@@ -0,0 +1,300 @@
+//! Export of envcraft schemas as JSON Schema documents.
+//!
+//! The exported document describes the JSON object `convert --to json`
+//! writes for a valid env file, so every value is a string and each type
+//! becomes `pattern`, `format`, or length keywords on strings. Constraints
+//! JSON Schema cannot express (aliases, semver ranges, case-insensitive
+//! placeholders) are approximated or left out; the README lists each one.
+
+use serde_json::{json, Map, Value};
+
+use crate::schema::{Field, Schema, ValueType, BYTE_SIZE_UNITS};
+
+/// JSON Schema dialect of the exported documents; `dependentRequired` and
+/// `contentSchema` need 2019-09 or later.
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Optional sign and digits; the range of a 64-bit integer is not checked.
+const INT_PATTERN: &str = "^[+-]?[0-9]+$";
+
+/// `true` or `false` in any letter case.
+const BOOL_PATTERN: &str = "^([Tt][Rr][Uu][Ee]|[Ff][Aa][Ll][Ss][Ee])$";
+
+/// `YYYY-MM-DD`; the `date` format checks that the day exists.
+const DATE_PATTERN: &str = "^[0-9]{4}-[0-9]{2}-[0-9]{2}$";
+
+/// Standard base64 with `=` padding.
+const BASE64_PATTERN: &str = "^([A-Za-z0-9+/]{4})*([A-Za-z0-9+/]{2}==|[A-Za-z0-9+/]{3}=)?$";
+
+/// An even number of hex digits in either case.
+const HEX_PATTERN: &str = "^([0-9A-Fa-f]{2})*$";
+
+/// A semantic version, with optional pre-release and build metadata.
+const SEMVER_PATTERN: &str =
+    "^(0|[1-9][0-9]*)\\.(0|[1-9][0-9]*)\\.(0|[1-9][0-9]*)(-[0-9A-Za-z.-]+)?(\\+[0-9A-Za-z.-]+)?$";
+
+/// Write a regex alternative that matches `text` in any ASCII letter case.
+fn any_case(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                format!("[{}{}]", c.to_ascii_uppercase(), c.to_ascii_lowercase())
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Build the pattern of a byte size: a number, then a unit in any case.
+fn byte_size_pattern(allow_bare: bool) -> String {
+    let units: Vec<String> = BYTE_SIZE_UNITS
+        .iter()
+        .map(|(name, _)| any_case(name))
+        .collect();
+    let unit = format!("\\s*({})", units.join("|"));
+    let unit = if allow_bare {
+        format!("({unit})?")
+    } else {
+        unit
+    };
+    format!("^[0-9]+(\\.[0-9]*)?{unit}$")
+}
+
+/// Translate a schema key pattern like `FEATURE_*` into an anchored regex.
+fn glob_regex(pattern: &str) -> String {
+    let body: String = pattern
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c if c.is_ascii_alphanumeric() || c == '_' => c.to_string(),
+            c => format!("\\{c}"),
+        })
+        .collect();
+    format!("^{body}$")
+}
+
+/// Get the keywords that restrict a string to the values a type accepts.
+fn type_keywords(value_type: &ValueType) -> Map<String, Value> {
+    let keywords = match value_type {
+        ValueType::String | ValueType::List => json!({}),
+        ValueType::Int => json!({"pattern": INT_PATTERN}),
+        ValueType::Bool => json!({"pattern": BOOL_PATTERN}),
+        ValueType::ByteSize { allow_bare } => {
+            json!({"pattern": byte_size_pattern(*allow_bare)})
+        }
+        ValueType::Date => json!({"pattern": DATE_PATTERN, "format": "date"}),
+        ValueType::DateTime => json!({"format": "date-time"}),
+        ValueType::Base64 { bytes } => {
+            let mut keywords = json!({"pattern": BASE64_PATTERN});
+            if let Some(bytes) = bytes {
+                let length = bytes.div_ceil(3) * 4;
+                keywords["minLength"] = json!(length);
+                keywords["maxLength"] = json!(length);
+            }
+            keywords
+        }
+        ValueType::Hex { bytes } => {
+            let mut keywords = json!({"pattern": HEX_PATTERN});
+            if let Some(bytes) = bytes {
+                keywords["minLength"] = json!(bytes * 2);
+                keywords["maxLength"] = json!(bytes * 2);
+            }
+            keywords
+        }
+        ValueType::Host { ip, hostname } => {
+            let mut formats = Vec::new();
+            if *ip {
+                formats.extend([json!({"format": "ipv4"}), json!({"format": "ipv6"})]);
+            }
+            if *hostname {
+                formats.push(json!({"format": "hostname"}));
+            }
+            json!({"anyOf": formats})
+        }
+        // A version range has no JSON Schema equivalent and is left out
+        ValueType::SemVer { .. } => json!({"pattern": SEMVER_PATTERN}),
+        ValueType::Json { schema } => {
+            let mut keywords = json!({"contentMediaType": "application/json"});
+            if let Some(schema) = schema {
+                keywords["contentSchema"] = json!(schema);
+            }
+            keywords
+        }
+    };
+
+    match keywords {
+        Value::Object(keywords) => keywords,
+        _ => unreachable!("keywords are built as objects"),
+    }
+}
+
+/// Build the schema of one field's value.
+fn field_schema(field: &Field) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("string"));
+    if let Some(description) = &field.description {
+        schema.insert("description".to_string(), json!(description));
+    }
+    schema.extend(type_keywords(&field.value_type));
+    // Placeholders are matched case-insensitively by envcraft, but `enum`
+    // only rejects the spellings listed
+    if !field.forbid.is_empty() {
+        schema.insert("not".to_string(), json!({"enum": field.forbid}));
+    }
+    if let Some(default) = &field.default {
+        schema.insert("default".to_string(), json!(default));
+    }
+    Value::Object(schema)
+}
+
+/// Convert a schema into a JSON Schema document for the JSON object of a
+/// valid env file.
+///
+/// Keys become `properties`, required unless they have a default or belong
+/// to a group; key patterns become `patternProperties`; all-or-none groups
+/// become `dependentRequired`. Undeclared keys stay allowed, since envcraft
+/// only warns about them.
+pub fn to_json_schema(schema: &Schema) -> Value {
+    let properties: Map<String, Value> = schema
+        .fields
+        .iter()
+        .map(|(key, field)| (key.clone(), field_schema(field)))
+        .collect();
+    let required: Vec<&String> = schema
+        .fields
+        .iter()
+        .filter(|(key, field)| {
+            field.default.is_none() && !schema.groups.iter().any(|group| group.keys.contains(key))
+        })
+        .map(|(key, _)| key)
+        .collect();
+
+    let mut document = json!({
+        "$schema": DIALECT,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+
+    if !schema.patterns.is_empty() {
+        let patterns: Map<String, Value> = schema
+            .patterns
+            .iter()
+            .map(|(pattern, field)| (glob_regex(pattern), field_schema(field)))
+            .collect();
+        document["patternProperties"] = Value::Object(patterns);
+    }
+
+    let mut dependent = Map::new();
+    for group in &schema.groups {
+        for key in &group.keys {
+            let others: Vec<&String> = group.keys.iter().filter(|other| *other != key).collect();
+            dependent.insert(key.clone(), json!(others));
+        }
+    }
+    if !dependent.is_empty() {
+        document["dependentRequired"] = Value::Object(dependent);
+    }
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_types_and_constraints() {
+        let schema = Schema::from_str(
+            "PORT: int\n\
+             DEBUG: bool\n\
+             TOKEN: {type: base64, bytes: 32}\n\
+             KEY_ID: {type: hex, bytes: 4}\n\
+             API_KEY: {type: string, forbid: [changeme, TODO], description: Service key}\n\
+             LOG_LEVEL: {type: string, default: info}\n",
+        )
+        .unwrap();
+        let document = to_json_schema(&schema);
+        let properties = &document["properties"];
+
+        assert_eq!(document["type"], "object");
+        assert_eq!(properties["PORT"]["type"], "string");
+        assert_eq!(properties["PORT"]["pattern"], INT_PATTERN);
+        assert_eq!(properties["DEBUG"]["pattern"], BOOL_PATTERN);
+        assert_eq!(properties["TOKEN"]["minLength"], 44);
+        assert_eq!(properties["TOKEN"]["maxLength"], 44);
+        assert_eq!(properties["KEY_ID"]["minLength"], 8);
+        assert_eq!(properties["KEY_ID"]["maxLength"], 8);
+        assert_eq!(
+            properties["API_KEY"]["not"],
+            json!({"enum": ["changeme", "TODO"]})
+        );
+        assert_eq!(properties["API_KEY"]["description"], "Service key");
+        assert_eq!(properties["LOG_LEVEL"]["default"], "info");
+        // Keys with a default may be left out
+        assert_eq!(
+            document["required"],
+            json!(["API_KEY", "DEBUG", "KEY_ID", "PORT", "TOKEN"])
+        );
+    }
+
+    #[test]
+    fn test_export_nested_json_enum() {
+        let schema =
+            Schema::from_str("MODE: {type: json, schema: {type: string, enum: [fast, safe]}}\n")
+                .unwrap();
+        let document = to_json_schema(&schema);
+
+        assert_eq!(
+            document["properties"]["MODE"],
+            json!({
+                "type": "string",
+                "contentMediaType": "application/json",
+                "contentSchema": {"type": "string", "enum": ["fast", "safe"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_export_patterns_and_groups() {
+        let schema = Schema::from_str(
+            "FEATURE_*: bool\n\
+             A: string\n\
+             B: string\n\
+             groups:\n  - {group: pair, keys: [A, B], policy: all_or_none}\n",
+        )
+        .unwrap();
+        let document = to_json_schema(&schema);
+
+        assert_eq!(
+            document["patternProperties"]["^FEATURE_.*$"]["pattern"],
+            BOOL_PATTERN
+        );
+        assert_eq!(document["required"], json!([]));
+        assert_eq!(
+            document["dependentRequired"],
+            json!({"A": ["B"], "B": ["A"]})
+        );
+    }
+
+    #[test]
+    fn test_patterns_match_like_the_validators() {
+        let pattern = |text: &str| regex::Regex::new(text).unwrap();
+
+        let int = pattern(INT_PATTERN);
+        assert!(int.is_match("-42") && int.is_match("+7") && !int.is_match("4.2"));
+        let bool = pattern(BOOL_PATTERN);
+        assert!(bool.is_match("TRUE") && bool.is_match("false") && !bool.is_match("yes"));
+        let size = pattern(&byte_size_pattern(false));
+        assert!(size.is_match("512MB") && size.is_match("1.5 gib") && !size.is_match("512"));
+        assert!(pattern(&byte_size_pattern(true)).is_match("512"));
+        let base64 = pattern(BASE64_PATTERN);
+        assert!(base64.is_match("aGVsbG8=") && !base64.is_match("aGVsbG8") && base64.is_match(""));
+        let semver = pattern(SEMVER_PATTERN);
+        assert!(semver.is_match("1.2.3-rc.1+build") && !semver.is_match("1.2"));
+        assert!(pattern(&glob_regex("A.B_?")).is_match("A.B_1"));
+        assert!(!pattern(&glob_regex("A.B_?")).is_match("AxB_1"));
+    }
+}
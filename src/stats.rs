@@ -0,0 +1,132 @@
+//! Summary statistics for a single .env file.
+//!
+//! Provides a read-only overview of keys, comments, blank lines,
+//! duplicates, and empty values.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::cli::OutputFormat;
+use crate::parser::{EnvFile, EnvLine, ParseError};
+
+/// Errors that can occur during stats operation.
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("failed to parse env file: {0}")]
+    ParseError(#[from] ParseError),
+
+    #[error("failed to serialize JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Counts gathered from a parsed env file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvStats {
+    /// Number of distinct keys
+    pub keys: usize,
+    /// Number of comment lines
+    pub comments: usize,
+    /// Number of blank lines
+    pub blank_lines: usize,
+    /// Number of keys defined more than once
+    pub duplicate_keys: usize,
+    /// Number of keys whose effective value is empty
+    pub empty_values: usize,
+}
+
+/// Compute statistics for an env file.
+pub fn stats(env: &EnvFile) -> EnvStats {
+    let mut comments = 0;
+    let mut blank_lines = 0;
+    let mut occurrences: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for line in &env.lines {
+        match line {
+            EnvLine::Comment(_) => comments += 1,
+            EnvLine::Blank => blank_lines += 1,
+            EnvLine::KeyValue { key, .. } => *occurrences.entry(key).or_insert(0) += 1,
+        }
+    }
+
+    EnvStats {
+        keys: env.entries.len(),
+        comments,
+        blank_lines,
+        duplicate_keys: occurrences.values().filter(|&&count| count > 1).count(),
+        empty_values: env
+            .entries
+            .values()
+            .filter(|value| value.is_empty())
+            .count(),
+    }
+}
+
+/// Run the stats command.
+pub fn run_stats(path: &Path, format: OutputFormat) -> Result<bool, StatsError> {
+    let env = EnvFile::from_path(path)?;
+    let result = stats(&env);
+
+    match format {
+        OutputFormat::Text => {
+            println!("keys: {}", result.keys);
+            println!("comments: {}", result.comments);
+            println!("blank lines: {}", result.blank_lines);
+            println!("duplicate keys: {}", result.duplicate_keys);
+            println!("empty values: {}", result.empty_values);
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_counts() {
+        let content = "# Comment\nA=1\n\nEMPTY=\nA=2\nB=3\n";
+        let env = EnvFile::from_str(content).unwrap();
+        let result = stats(&env);
+
+        assert_eq!(
+            result,
+            EnvStats {
+                keys: 3,
+                comments: 1,
+                blank_lines: 1,
+                duplicate_keys: 1,
+                empty_values: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_empty_file() {
+        let env = EnvFile::from_str("").unwrap();
+        let result = stats(&env);
+
+        assert_eq!(result.keys, 0);
+        assert_eq!(result.comments, 0);
+        assert_eq!(result.blank_lines, 0);
+        assert_eq!(result.duplicate_keys, 0);
+        assert_eq!(result.empty_values, 0);
+    }
+
+    #[test]
+    fn test_stats_json_fields() {
+        let env = EnvFile::from_str("A=1").unwrap();
+        let json = serde_json::to_string(&stats(&env)).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"keys":1,"comments":0,"blank_lines":0,"duplicate_keys":0,"empty_values":0}"#
+        );
+    }
+}
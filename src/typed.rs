@@ -0,0 +1,107 @@
+//! Typed access to env file values.
+//!
+//! The accessors parse values with the same rules schema validation uses,
+//! so a value that passes `check` for a type also parses here.
+
+use thiserror::Error;
+
+use crate::parser::EnvFile;
+use crate::schema::{parse_bool, parse_int, ValueType};
+
+/// A value that could not be parsed as the requested type.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("key '{key}' has invalid value '{value}' (expected {expected})")]
+pub struct TypedValueError {
+    /// Key whose value failed to parse
+    pub key: String,
+    /// The value as found in the file
+    pub value: String,
+    /// Description of the values the type accepts
+    pub expected: String,
+}
+
+impl EnvFile {
+    /// Parse a key's value with `parse`, or `None` if the key is not set.
+    fn get_parsed<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&str) -> Option<T>,
+        expected: String,
+    ) -> Option<Result<T, TypedValueError>> {
+        let value = self.get(key)?;
+        Some(parse(value).ok_or_else(|| TypedValueError {
+            key: key.to_string(),
+            value: value.clone(),
+            expected,
+        }))
+    }
+
+    /// Get a key's value as an integer, parsed like the schema `int` type.
+    #[allow(dead_code)]
+    pub fn get_int(&self, key: &str) -> Option<Result<i64, TypedValueError>> {
+        self.get_parsed(key, parse_int, ValueType::Int.description())
+    }
+
+    /// Get a key's value as a bool, parsed like the schema `bool` type.
+    #[allow(dead_code)]
+    pub fn get_bool(&self, key: &str) -> Option<Result<bool, TypedValueError>> {
+        self.get_parsed(key, parse_bool, ValueType::Bool.description())
+    }
+
+    /// Get a key's value as a finite floating-point number, e.g. `0.25` or
+    /// `-1e3`. Infinities and NaN are rejected.
+    #[allow(dead_code)]
+    pub fn get_float(&self, key: &str) -> Option<Result<f64, TypedValueError>> {
+        let parse = |value: &str| value.parse::<f64>().ok().filter(|n| n.is_finite());
+        self.get_parsed(key, parse, "a number (e.g., 0.25, -1e3)".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> EnvFile {
+        EnvFile::from_str(
+            "PORT=8080\nOFFSET=-10\nDEBUG=TRUE\nVERBOSE=false\nRATIO=0.25\nNAME=app\nHUGE=inf\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_int() {
+        let env = env();
+
+        assert_eq!(env.get_int("PORT"), Some(Ok(8080)));
+        assert_eq!(env.get_int("OFFSET"), Some(Ok(-10)));
+        assert_eq!(env.get_int("MISSING"), None);
+
+        let error = env.get_int("RATIO").unwrap().unwrap_err();
+        assert_eq!(error.value, "0.25");
+        assert_eq!(
+            error.to_string(),
+            "key 'RATIO' has invalid value '0.25' (expected an integer (e.g., 42, -10))"
+        );
+    }
+
+    #[test]
+    fn test_get_bool() {
+        let env = env();
+
+        assert_eq!(env.get_bool("DEBUG"), Some(Ok(true)));
+        assert_eq!(env.get_bool("VERBOSE"), Some(Ok(false)));
+        assert!(env.get_bool("PORT").unwrap().is_err());
+        assert_eq!(env.get_bool("MISSING"), None);
+    }
+
+    #[test]
+    fn test_get_float() {
+        let env = env();
+
+        assert_eq!(env.get_float("RATIO"), Some(Ok(0.25)));
+        assert_eq!(env.get_float("PORT"), Some(Ok(8080.0)));
+        assert!(env.get_float("NAME").unwrap().is_err());
+        assert!(env.get_float("HUGE").unwrap().is_err());
+        assert_eq!(env.get_float("MISSING"), None);
+    }
+}
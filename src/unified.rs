@@ -0,0 +1,168 @@
+//! Unified diffs of text, in the format of `diff -u` and `git diff`.
+//!
+//! Lines are matched with a longest common subsequence, so the result is
+//! deterministic but not always the shortest diff `git` would find.
+
+/// One step of the edit script turning the old lines into the new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// The old line at this index is kept, as the new line at the other
+    Equal(usize, usize),
+    /// The old line at this index is removed
+    Delete(usize),
+    /// The new line at this index is added
+    Insert(usize),
+}
+
+/// Compute an edit script from the longest common subsequence of lines.
+///
+/// Where several scripts are equally short, deletions come before
+/// insertions.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Op> {
+    // lcs[i][j] is the length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Equal(i, j));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(Op::Delete(i));
+            i += 1;
+        } else {
+            ops.push(Op::Insert(j));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Render a hunk range: `start,count`, with the count left out when it is 1.
+///
+/// An empty range starts at the line before it, as in `diff -u`.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+/// Build a unified diff from `old` to `new`, with `context` unchanged lines
+/// around each change.
+///
+/// Returns an empty string when the texts have the same lines.
+pub fn unified_diff(name1: &str, name2: &str, old: &str, new: &str, context: usize) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = edit_script(&old, &new);
+
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, Op::Equal(..)))
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context would touch or overlap into one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &index in &changes {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = format!("--- {name1}\n+++ {name2}\n");
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        // Lines before the hunk on each side
+        let (old_start, new_start) = ops[..start].iter().fold((0, 0), |(i, j), op| match op {
+            Op::Equal(..) => (i + 1, j + 1),
+            Op::Delete(_) => (i + 1, j),
+            Op::Insert(_) => (i, j + 1),
+        });
+        let old_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Delete(_)))
+            .count();
+
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        ));
+        for op in hunk {
+            let line = match *op {
+                Op::Equal(i, _) => format!(" {}", old[i]),
+                Op::Delete(i) => format!("-{}", old[i]),
+                Op::Insert(j) => format!("+{}", new[j]),
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_change() {
+        let old = "A=1\nB=2\nC=3\nD=4\nE=5\nF=6\n";
+        let new = "A=1\nB=2\nC=3\nD=40\nE=5\nF=6\n";
+
+        assert_eq!(
+            unified_diff("a.env", "b.env", old, new, 1),
+            "--- a.env\n+++ b.env\n@@ -3,3 +3,3 @@\n C=3\n-D=4\n+D=40\n E=5\n"
+        );
+    }
+
+    #[test]
+    fn test_separate_hunks_and_edges() {
+        let old = "A=1\nB=2\nC=3\nD=4\nE=5\n";
+        let new = "B=2\nC=3\nD=4\nE=5\nF=6\n";
+
+        assert_eq!(
+            unified_diff("old", "new", old, new, 0),
+            "--- old\n+++ new\n@@ -1 +0,0 @@\n-A=1\n@@ -5,0 +5 @@\n+F=6\n"
+        );
+        // With enough context the two changes share one hunk
+        assert_eq!(
+            unified_diff("old", "new", old, new, 3),
+            "--- old\n+++ new\n@@ -1,5 +1,5 @@\n-A=1\n B=2\n C=3\n D=4\n E=5\n+F=6\n"
+        );
+    }
+
+    #[test]
+    fn test_identical_and_empty() {
+        assert_eq!(unified_diff("a", "b", "A=1\n", "A=1\n", 3), "");
+        assert_eq!(
+            unified_diff("a", "b", "", "A=1\n", 3),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+A=1\n"
+        );
+    }
+}
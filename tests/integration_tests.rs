@@ -12,6 +12,7 @@ fn setup_test_files(files: &[(&str, &str)]) -> TempDir {
     let dir = TempDir::new().expect("Failed to create temp dir");
     for (name, content) in files {
         let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).expect("Failed to create test dir");
         fs::write(&path, content).expect("Failed to write test file");
     }
     dir
@@ -233,3 +234,2813 @@ fn test_help_flag() {
     assert!(stdout.contains("diff"));
     assert!(stdout.contains("format"));
 }
+
+#[test]
+fn test_diff_raw_shows_quoting_changes() {
+    let dir = setup_test_files(&[("a.env", "SECRET=\"abc\""), ("b.env", "SECRET=abc")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("identical"));
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--raw"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ SECRET: \"abc\" → abc"));
+}
+
+#[test]
+fn test_stats_text() {
+    let dir = setup_test_files(&[(".env", "# Comment\nA=1\n\nEMPTY=\nA=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["stats", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("keys: 2"));
+    assert!(stdout.contains("comments: 1"));
+    assert!(stdout.contains("blank lines: 1"));
+    assert!(stdout.contains("duplicate keys: 1"));
+    assert!(stdout.contains("empty values: 1"));
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_stats_json() {
+    let dir = setup_test_files(&[(".env", "# Comment\nA=1\n\nEMPTY=\nA=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["stats", ".env", "--format", "json"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"keys\": 2"));
+    assert!(stdout.contains("\"duplicate_keys\": 1"));
+    assert!(stdout.contains("\"empty_values\": 1"));
+}
+
+#[test]
+fn test_check_alias_warning() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "NEW_NAME: {type: string, aliases: [OLD_NAME]}",
+        ),
+        (".env", "OLD_NAME=value"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("deprecated key 'OLD_NAME' used, rename to 'NEW_NAME'"));
+    assert!(stdout.contains("validation passed with 1 warning(s)"));
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_format_in_place_backup() {
+    let original = "  lower_key = value  \n";
+    let dir = setup_test_files(&[(".env", original)]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--in-place", "--backup"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    let backup = fs::read_to_string(dir.path().join(".env.bak")).expect("Failed to read backup");
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(backup, original);
+    assert_eq!(content, "LOWER_KEY=value\n");
+}
+
+#[test]
+fn test_format_in_place_backup_suffix() {
+    let original = "key=value\n";
+    let dir = setup_test_files(&[(".env", original)]);
+
+    Command::new(envcraft_bin())
+        .args([
+            "format",
+            ".env",
+            "--in-place",
+            "--backup",
+            "--backup-suffix",
+            ".orig",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let backup = fs::read_to_string(dir.path().join(".env.orig")).expect("Failed to read backup");
+    assert_eq!(backup, original);
+}
+
+#[test]
+fn test_format_in_place_backup_failure_leaves_original() {
+    let original = "key=value\n";
+    let dir = setup_test_files(&[(".env", original)]);
+    // A directory in the way makes the backup copy fail
+    fs::create_dir(dir.path().join(".env.bak")).expect("Failed to create dir");
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--in-place", "--backup"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("failed to write backup file"));
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(content, original);
+}
+
+#[test]
+fn test_rename_key() {
+    let dir = setup_test_files(&[(".env", "# Database\nOLD_NAME=value\nOTHER=1\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["rename", ".env", "OLD_NAME", "NEW_NAME"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(content, "# Database\nNEW_NAME=value\nOTHER=1\n");
+}
+
+#[test]
+fn test_rename_collision_requires_force() {
+    let original = "OLD_NAME=old\nNEW_NAME=new\n";
+    let dir = setup_test_files(&[(".env", original)]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["rename", ".env", "OLD_NAME", "NEW_NAME"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("key already exists: NEW_NAME"));
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(content, original);
+
+    let output = Command::new(envcraft_bin())
+        .args(["rename", ".env", "OLD_NAME", "NEW_NAME", "--force"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(content, "NEW_NAME=old\n");
+}
+
+#[test]
+fn test_check_explain() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "PORT: {type: int, description: HTTP listen port}\nDEBUG: bool",
+        ),
+        (".env", "PORT=abc\nDEBUG=true"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--explain", "PORT"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("type: int"));
+    assert!(stdout.contains("description: HTTP listen port"));
+    assert!(stdout.contains("value: abc"));
+    assert!(stdout.contains("result: fail"));
+    assert!(!stdout.contains("DEBUG"));
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--explain", "DEBUG"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("type: bool"));
+    assert!(stdout.contains("value: true"));
+    assert!(stdout.contains("result: pass"));
+    assert!(output.status.success());
+}
+
+/// Run a git command in a directory with a fixed identity.
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=envcraft",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git")
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn test_diff_git_revision() {
+    let dir = setup_test_files(&[(".env", "PORT=80\nOLD=1\n")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", ".env"]);
+    git(dir.path(), &["commit", "-q", "-m", "first"]);
+    fs::write(dir.path().join(".env"), "PORT=8080\nOLD=1\n").expect("Failed to write");
+    git(dir.path(), &["commit", "-q", "-am", "second"]);
+    fs::write(dir.path().join(".env"), "PORT=8080\nNEW=2\n").expect("Failed to write");
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "--git", "HEAD~1:.env", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("~ PORT: 80 → 8080"));
+    assert!(stdout.contains("- OLD=1"));
+    assert!(stdout.contains("+ NEW=2"));
+}
+
+#[test]
+fn test_diff_git_missing_revision() {
+    let dir = setup_test_files(&[(".env", "PORT=80\n")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", ".env"]);
+    git(dir.path(), &["commit", "-q", "-m", "first"]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "--git", "HEAD~5:.env", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("failed to read 'HEAD~5:.env' from git"));
+}
+
+#[test]
+fn test_diff_git_not_a_repository() {
+    let dir = setup_test_files(&[(".env", "PORT=80\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "--git", "HEAD:.env", ".env"])
+        .current_dir(dir.path())
+        .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("not a git repository"));
+}
+
+#[test]
+fn test_format_check_missing_final_newline() {
+    let dir = setup_test_files(&[(".env", "A=1\nB=2")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--check"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("missing newline at end of file"));
+    assert!(!stdout.contains("not formatted"));
+    let content = fs::read_to_string(dir.path().join(".env")).expect("Failed to read file");
+    assert_eq!(content, "A=1\nB=2");
+}
+
+#[test]
+fn test_format_check_clean() {
+    let dir = setup_test_files(&[(".env", "A=1\nB=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--check"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("is formatted"));
+}
+
+#[test]
+fn test_format_check_unformatted() {
+    let dir = setup_test_files(&[(".env", "b=2\na=1\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--check"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("not formatted"));
+}
+
+#[test]
+fn test_lint_missing_final_newline() {
+    let dir = setup_test_files(&[(".env", "A=1\nB=2")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["lint", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains(".env:2: missing newline at end of file [missing-final-newline]"));
+}
+
+#[test]
+fn test_lint_clean() {
+    let dir = setup_test_files(&[(".env", "A=1\nB=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["lint", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("no issues found"));
+}
+
+#[test]
+fn test_diff_type_aware() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nDEBUG: bool"),
+        ("a.env", "PORT=8080\nDEBUG=TRUE\nNAME=app"),
+        ("b.env", "PORT=08080\nDEBUG=true\nNAME=other"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "diff",
+            "a.env",
+            "b.env",
+            "--schema",
+            "schema.yml",
+            "--type-aware",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("PORT"));
+    assert!(!stdout.contains("DEBUG"));
+    assert!(stdout.contains("~ NAME: app → other"));
+    assert!(stdout.contains("1 difference(s) found"));
+}
+
+#[test]
+fn test_check_multiple_files_summary_only() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int"),
+        ("a.env", "PORT=8080"),
+        ("b.env", "PORT=abc"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "a.env", "b.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout, "2 files, 1 failed\n");
+}
+
+#[test]
+fn test_check_directory_verbose() {
+    let dir = setup_test_files(&[("schema.yml", "PORT: int")]);
+    let envs = dir.path().join("envs");
+    fs::create_dir(&envs).expect("Failed to create dir");
+    fs::write(envs.join("dev.env"), "PORT=3000").expect("Failed to write");
+    fs::write(envs.join("prod.env"), "PORT=80").expect("Failed to write");
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "envs", "--verbose"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("checking envs/dev.env") || stdout.contains("checking envs\\dev.env"));
+    assert!(stdout.contains("prod.env"));
+    assert!(stdout.contains("✓ validation passed"));
+    assert!(stdout.contains("2 files, 0 failed"));
+}
+
+#[test]
+fn test_check_dialect() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nBASE_PORT: int"),
+        (".env", "export BASE_PORT=8000\nPORT=${BASE_PORT}\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--dialect", "posix"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--dialect", "node"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("key 'PORT' has invalid value '${BASE_PORT}'"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_reference() {
+    let dir = setup_test_files(&[
+        ("reference.env", "PORT=8080\nDEBUG=true\nNAME=app"),
+        ("good.env", "PORT=3000\nDEBUG=false\nNAME=other"),
+        ("missing.env", "PORT=3000\nDEBUG=false"),
+        ("mismatch.env", "PORT=auto\nDEBUG=false\nNAME=other"),
+    ]);
+
+    let run = |target: &str| {
+        Command::new(envcraft_bin())
+            .args(["check", "--reference", "reference.env", target])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    let output = run("good.env");
+    assert!(output.status.success());
+
+    let output = run("missing.env");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("missing required key: NAME"));
+
+    let output = run("mismatch.env");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("key 'PORT' has invalid value 'auto'"));
+}
+
+#[test]
+fn test_diff_jsonl() {
+    let dir = setup_test_files(&[
+        ("a.env", "A=1\nB=2\nC=3\n"),
+        ("b.env", "A=1\nB=changed\nD=4\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--format", "jsonl"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is valid JSON"))
+        .collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["change"], "changed");
+    assert_eq!(lines[0]["key"], "B");
+    assert_eq!(lines[0]["new_value"], "changed");
+    assert_eq!(lines[1]["change"], "removed");
+    assert_eq!(lines[2]["change"], "added");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_forbidden_placeholder() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "API_KEY: {type: string, forbid: [changeme, TODO, xxx]}\n",
+        ),
+        (".env", "API_KEY=TODO\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: key 'API_KEY' has placeholder value 'TODO'"));
+    assert!(stdout.contains("validation failed with 1 error(s)"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_format_sort_values_with_schema() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "ALLOWED_ORIGINS: list\nGREETING: string\n"),
+        (
+            ".env",
+            "ALLOWED_ORIGINS=c.com,a.com,b.com\nGREETING=hello,world\n",
+        ),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--sort-values", "--schema", "schema.yml"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "ALLOWED_ORIGINS=a.com,b.com,c.com\nGREETING=hello,world\n"
+    );
+    assert!(output.status.success());
+
+    // Without a schema, a string like `hello,world` could be split
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--sort-values"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("--sort-values needs --schema"), "{stderr}");
+}
+
+#[test]
+fn test_check_fail_fast() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "A: int\nB: int\nC: bool\n"),
+        (".env", "A=one\nB=two\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--fail-fast"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("error:").count(), 1);
+    assert!(stdout.contains("error: key 'A' has invalid value 'one'"));
+    assert!(stdout.contains("validation failed with 1 error(s)"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_fail_fast_stops_at_first_failing_file() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\n"),
+        ("a.env", "PORT=http\n"),
+        ("b.env", "PORT=80\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "a.env", "b.env", "--fail-fast"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("stopped at first failure: a.env"));
+    assert!(stdout.contains("1 files, 1 failed"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Serve a single HTTP response with `body` on a local port and return its URL.
+#[cfg(feature = "network")]
+fn serve_once(body: &'static str) -> String {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+
+    format!("http://127.0.0.1:{port}/.env")
+}
+
+#[cfg(feature = "network")]
+#[test]
+fn test_check_url_source() {
+    let dir = setup_test_files(&[("schema.yml", "PORT: int\n")]);
+    let url = serve_once("PORT=http\n");
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", &url])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: key 'PORT' has invalid value 'http'"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[cfg(feature = "network")]
+#[test]
+fn test_diff_url_source() {
+    let dir = setup_test_files(&[(".env", "PORT=80\n")]);
+    let url = serve_once("PORT=8080\n");
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", ".env", &url])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ PORT: 80 → 8080"));
+    assert!(output.status.success());
+}
+
+#[cfg(not(feature = "network"))]
+#[test]
+fn test_url_source_requires_network_feature() {
+    let dir = setup_test_files(&[("schema.yml", "PORT: int\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "https://example.com/.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("built without the `network` feature"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_diff_redact_hash() {
+    let dir = setup_test_files(&[
+        ("a.env", "SAME=secret\nCHANGED=old_secret\n"),
+        ("b.env", "SAME=secret\nCHANGED=new_secret\nADDED=secret\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact-hash"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("secret"));
+    let added = stdout.lines().find(|l| l.starts_with("+ ADDED=")).unwrap();
+    let changed = stdout
+        .lines()
+        .find(|l| l.starts_with("~ CHANGED:"))
+        .unwrap();
+    let (old, new) = changed["~ CHANGED: ".len()..].split_once(" → ").unwrap();
+    assert_ne!(old, new);
+    // Identical values hash identically across keys and files
+    assert_eq!(&added["+ ADDED=".len()..], "sha256:2bb80d53");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_init_creates_files() {
+    let dir = setup_test_files(&[]);
+
+    let output = Command::new(envcraft_bin())
+        .arg("init")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+
+    let check = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env.example"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&check.stdout);
+    assert!(stdout.contains("✓ validation passed"));
+    assert!(check.status.success());
+}
+
+#[test]
+fn test_init_defaults_only_from_schema() {
+    let dir = setup_test_files(&[(
+        "schema.yml",
+        "NAME: string\n\
+         PORT: {type: int, default: 8080}\n\
+         LOG_LEVEL: {type: string, default: info, description: Minimum level}\n",
+    )]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["init", "--schema", "schema.yml", "--defaults-only"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Minimum level\nLOG_LEVEL=info\nPORT=8080\n"
+    );
+    // Nothing is written next to the schema
+    assert!(!dir.path().join(".env.example").exists());
+
+    let output = Command::new(envcraft_bin())
+        .args(["init", "--schema", "schema.yml"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Minimum level\nLOG_LEVEL=info\nNAME=\nPORT=8080\n"
+    );
+}
+
+#[test]
+fn test_init_refuses_to_overwrite() {
+    let dir = setup_test_files(&[("schema.yml", "MINE: string\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .arg("init")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already exists (use --force to overwrite)"));
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("schema.yml")).unwrap(),
+        "MINE: string\n"
+    );
+    assert!(!dir.path().join(".env.example").exists());
+
+    let forced = Command::new(envcraft_bin())
+        .args(["init", "--force"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(forced.status.success());
+    assert!(fs::read_to_string(dir.path().join("schema.yml"))
+        .unwrap()
+        .contains("PORT: int"));
+}
+
+#[test]
+fn test_convert_nested_round_trip() {
+    let dir = setup_test_files(&[(".env", "DB.HOST=localhost\nDB.PORT=5432\nNAME=app\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["convert", ".env", "--to", "json", "--nest-on", "."])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["DB"]["HOST"], "localhost");
+    assert_eq!(json["DB"]["PORT"], "5432");
+    assert_eq!(json["NAME"], "app");
+    fs::write(dir.path().join("env.json"), &output.stdout).unwrap();
+
+    let back = Command::new(envcraft_bin())
+        .args(["convert", "env.json", "--to", "env", "--nest-on", "."])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(
+        String::from_utf8_lossy(&back.stdout),
+        "DB.HOST=localhost\nDB.PORT=5432\nNAME=app\n"
+    );
+    assert!(back.status.success());
+}
+
+#[test]
+fn test_keys_text() {
+    let dir = setup_test_files(&[(".env", "ZEBRA=1\nAPPLE=secret\nZEBRA=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["keys", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "APPLE\nZEBRA\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_keys_json() {
+    let dir = setup_test_files(&[(".env", "ZEBRA=1\nAPPLE=secret\nZEBRA=2\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["keys", ".env", "--format", "json"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let keys: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(keys, vec!["APPLE", "ZEBRA"]);
+}
+
+#[test]
+fn test_format_schema_preserves_string_values() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "ZIP: string\nAMOUNT: string\nHOSTS: list\n"),
+        (".env", "ZIP=01234\nAMOUNT=1_000,5\nHOSTS=b.com,a.com\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--schema", "schema.yml", "--sort-values"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "AMOUNT=1_000,5\nHOSTS=a.com,b.com\nZIP=01234\n"
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_allow_bare_keys() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "FEATURE_FLAG: string\nPORT: int\n"),
+        (".env", "FEATURE_FLAG\nPORT=80\n"),
+    ]);
+
+    let strict = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&strict.stderr);
+    assert!(stderr.contains("invalid line format at line 1"));
+    assert_eq!(strict.status.code(), Some(2));
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--allow-bare-keys"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ validation passed"));
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_merge_schema_names_origin() {
+    let dir = setup_test_files(&[
+        ("base.yml", "PORT: int\n"),
+        ("secrets.yml", "API_KEY: string\n"),
+        (".env", "PORT=80\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "base.yml", ".env", "--merge-schema", "secrets.yml"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: missing required key: API_KEY (declared in secrets.yml)"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+// Windows canonical paths carry a `\\?\` prefix the working directory lacks
+#[cfg(unix)]
+#[test]
+fn test_format_in_place_prints_relative_path() {
+    let dir = setup_test_files(&[(".env", "b=2\na=1\n")]);
+    let absolute = dir.path().canonicalize().unwrap().join(".env");
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", "--in-place"])
+        .arg(&absolute)
+        .current_dir(dir.path().canonicalize().unwrap())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "Formatted: .env\n");
+    assert!(output.status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_diff_as_patch_replays_onto_first_file() {
+    let dir = setup_test_files(&[
+        (
+            "a.env",
+            "# app\nKEEP=same\nCHANGED=old # note\nREMOVED=gone\n",
+        ),
+        (
+            "b.env",
+            "KEEP=same\nCHANGED=it's new\nADDED=$HOME and spaces\n",
+        ),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--as-patch"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+    let patch = String::from_utf8_lossy(&output.stdout).to_string();
+
+    let bin_dir = envcraft_bin().parent().unwrap().to_path_buf();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap());
+    let applied = Command::new("sh")
+        .args(["-e", "-c", &patch])
+        .env("PATH", path)
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run patch");
+    assert!(applied.status.success());
+
+    let patched = fs::read_to_string(dir.path().join("a.env")).unwrap();
+    let expected = fs::read_to_string(dir.path().join("b.env")).unwrap();
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success(), "{patched} != {expected}");
+    assert!(patched.starts_with("# app\n"));
+}
+
+/// Run `envcraft set .env KEY -` with `input` piped to stdin.
+fn set_from_stdin(dir: &TempDir, input: &[u8]) -> std::process::Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(envcraft_bin())
+        .args(["set", ".env", "TOKEN", "-"])
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run envcraft");
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().expect("Failed to run envcraft")
+}
+
+#[test]
+fn test_set_value_from_stdin() {
+    let dir = setup_test_files(&[(".env", "# app\nTOKEN=old\n")]);
+
+    let output = set_from_stdin(&dir, b"s3cret value\n");
+
+    assert!(output.status.success());
+    let content = fs::read_to_string(dir.path().join(".env")).unwrap();
+    assert_eq!(content, "# app\nTOKEN=s3cret value\n");
+}
+
+#[test]
+fn test_set_empty_stdin_sets_empty_value() {
+    let dir = setup_test_files(&[(".env", "TOKEN=old\n")]);
+
+    let output = set_from_stdin(&dir, b"");
+
+    assert!(output.status.success());
+    let content = fs::read_to_string(dir.path().join(".env")).unwrap();
+    assert_eq!(content, "TOKEN=\n");
+}
+
+#[test]
+fn test_set_keeps_byte_order_mark() {
+    let dir = setup_test_files(&[]);
+    let path = dir.path().join(".env");
+    fs::write(&path, b"\xEF\xBB\xBFPORT=8080\nHOST=a\n").unwrap();
+
+    let output = Command::new(envcraft_bin())
+        .args(["set", ".env", "PORT", "9"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFPORT=9\nHOST=a\n");
+}
+
+#[test]
+fn test_edit_rejects_non_utf8_file() {
+    let dir = setup_test_files(&[]);
+    let path = dir.path().join(".env");
+    fs::write(&path, b"CITY=M\xFCnchen\n").unwrap();
+
+    let output = Command::new(envcraft_bin())
+        .args(["unset", ".env", "CITY"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("is not valid UTF-8"), "{stderr}");
+    assert!(!stderr.contains("failed to write"), "{stderr}");
+    assert_eq!(fs::read(&path).unwrap(), b"CITY=M\xFCnchen\n");
+}
+
+#[test]
+fn test_config_file_defaults_and_cli_override() {
+    let dir = setup_test_files(&[
+        (
+            ".envcraft.toml",
+            "[diff]\nredact = true\nformat = \"jsonl\"\n",
+        ),
+        ("a.env", "TOKEN=old\n"),
+        ("b.env", "TOKEN=new\n"),
+    ]);
+    fs::create_dir(dir.path().join("nested")).unwrap();
+
+    let from_config = Command::new(envcraft_bin())
+        .args(["diff", "../a.env", "../b.env"])
+        .current_dir(dir.path().join("nested"))
+        .output()
+        .expect("Failed to run envcraft");
+    let overridden = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--format", "text"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(
+        String::from_utf8_lossy(&from_config.stdout),
+        "{\"change\":\"changed\",\"key\":\"TOKEN\"}\n"
+    );
+    let stdout = String::from_utf8_lossy(&overridden.stdout);
+    assert!(stdout.contains("TOKEN"));
+    assert!(!stdout.contains("new"));
+    assert!(!stdout.starts_with('{'));
+
+    // A flag the config turns on can be switched off again
+    let unredacted = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact=false"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(unredacted.status.success());
+    let stdout = String::from_utf8_lossy(&unredacted.stdout);
+    assert!(stdout.contains("\"old_value\":\"old\""), "{stdout}");
+}
+
+#[test]
+fn test_config_file_invalid_value() {
+    let dir = setup_test_files(&[(".envcraft.toml", "[diff]\nformat = \"xml\"\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(".envcraft.toml: invalid value for option 'format' of command 'diff'"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_check_resolve_includes() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "APP_NAME: string\nPORT: int\n"),
+        (".env", "#include shared/common.env\nAPP_NAME=web\n"),
+        ("shared/common.env", "PORT=8080\n"),
+    ]);
+
+    let without = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let with = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--resolve-includes"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(without.status.code(), Some(1));
+    assert!(with.status.success());
+}
+
+#[test]
+fn test_check_include_cycle_is_an_error() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\n"),
+        (".env", "#include other.env\n"),
+        ("other.env", "#include .env\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--resolve-includes"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("include cycle: .env -> other.env -> .env"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_assert_all_pass() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\nDEBUG=true\nAPI_KEY=abc\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["assert", ".env", "PORT:int", "DEBUG:bool", "API_KEY"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "✓ 3 assertion(s) passed\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_assert_type_failure() {
+    let dir = setup_test_files(&[(".env", "PORT=eighty\nDEBUG=true\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["assert", ".env", "PORT:int", "DEBUG:bool"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: key 'PORT' has invalid value 'eighty'"));
+    assert!(stdout.contains("✗ 1 of 2 assertion(s) failed"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_assert_missing_key() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["assert", ".env", "PORT:int", "DATABASE_URL"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: missing required key: DATABASE_URL"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_resolve_layers_with_mode() {
+    let dir = setup_test_files(&[
+        (".env", "API_URL=http://localhost\nDEBUG=false\nPORT=3000\n"),
+        (".env.local", "DEBUG=true\n"),
+        (
+            ".env.production",
+            "API_URL=https://api.example.com\nDEBUG=false\n",
+        ),
+        (".env.production.local", "PORT=8080\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["resolve", "--mode", "production"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "API_URL=https://api.example.com\nDEBUG=false\nPORT=8080\n"
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_diff_context_shows_neighbors() {
+    let dir = setup_test_files(&[
+        ("a.env", "APP=web\nDB_HOST=db\nPORT=80\nREGION=eu\nZONE=a\n"),
+        (
+            "b.env",
+            "APP=web\nDB_HOST=db\nPORT=8080\nREGION=eu\nZONE=a\n",
+        ),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--context", "1"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("  DB_HOST=db\n~ PORT: 80 → 8080\n  REGION=eu\n\n"));
+    assert!(!stdout.contains("APP"));
+    assert!(!stdout.contains("ZONE"));
+}
+
+#[test]
+fn test_check_consistency_passes() {
+    let dir = setup_test_files(&[
+        ("api.env", "CLUSTER_ID=prod-1\nPORT=8080\n"),
+        ("worker.env", "CLUSTER_ID=prod-1\nPORT=9090\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check-consistency", "api.env", "worker.env"])
+        .args(["--key", "CLUSTER_ID"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "✓ 1 key(s) consistent across 2 files\n");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_consistency_reports_divergence() {
+    let dir = setup_test_files(&[
+        ("api.env", "CLUSTER_ID=prod-1\n"),
+        ("worker.env", "CLUSTER_ID=prod-2\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check-consistency", "api.env", "worker.env"])
+        .args(["--key", "CLUSTER_ID"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "error: CLUSTER_ID differs across files:\n  api.env: prod-1\n  worker.env: prod-2\n"
+    ));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_severity_override_fails_on_extra_key() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\n"),
+        (".env", "PORT=8080\nUNUSED=1\n"),
+    ]);
+
+    let default = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let strict = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "-W", "extra-key=error"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(default.status.success());
+    let stdout = String::from_utf8_lossy(&strict.stdout);
+    assert!(stdout.contains("error: extra key not in schema: UNUSED"));
+    assert!(stdout.contains("✗ validation failed with 1 error(s)"));
+    assert_eq!(strict.status.code(), Some(1));
+}
+
+#[test]
+fn test_lint_severity_override_downgrades_rule() {
+    let dir = setup_test_files(&[(".env", "A=1")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["lint", ".env", "-W", "missing-final-newline=warning"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(".env:1: warning: missing newline at end of file"));
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_severity_rejects_unknown_rule() {
+    let output = Command::new(envcraft_bin())
+        .args(["lint", ".env", "-W", "extra-keys=error"])
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown rule 'extra-keys'"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_hash_ignores_layout() {
+    let dir = setup_test_files(&[
+        ("a.env", "# service\nPORT=8080\nHOST=localhost\n"),
+        ("b.env", "HOST='localhost'\n\nPORT=8080\n"),
+        ("c.env", "HOST=localhost\nPORT=8081\n"),
+    ]);
+
+    let hash = |file: &str| {
+        let output = Command::new(envcraft_bin())
+            .args(["hash", file])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(hash("a.env").starts_with("sha256:"));
+    assert_eq!(hash("a.env"), hash("b.env"));
+    assert_ne!(hash("a.env"), hash("c.env"));
+}
+
+#[test]
+fn test_lint_max_line_length() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\nDATABASE_URL=postgres://db/app\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["lint", ".env", "--max-line-length", "20"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout
+        .contains(".env:2: line for DATABASE_URL is 30 characters long (max 20) [line-too-long]"));
+    assert!(!stdout.contains(".env:1:"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_tolerant_reports_every_invalid_line() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nDEBUG: bool\n"),
+        (
+            ".env",
+            "PORT=8080\nBROKEN\nDEBUG=true\n=empty\nALSO BROKEN\n",
+        ),
+    ]);
+
+    let strict = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(strict.status.code(), Some(2));
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--tolerant"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: invalid line format at line 2, column 7: BROKEN"));
+    assert!(stdout.contains("error: invalid line format at line 4, column 1: =empty"));
+    assert!(stdout.contains("error: invalid line format at line 5"));
+    assert!(stdout.contains("✗ validation failed with 3 error(s)"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_format_normalize_keys_lower() {
+    let dir = setup_test_files(&[(".env", "Port=8080\nAPI_KEY=k\n\"MixedCase\"=1\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--normalize-keys", "lower"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\"MixedCase\"=1\napi_key=k\nport=8080\n"
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_format_on_duplicate() {
+    let dir = setup_test_files(&[(".env", "PORT=1\nHOST=h\nPORT=2\n")]);
+    let format_with = |policy: &str| {
+        Command::new(envcraft_bin())
+            .args(["format", ".env", "--on-duplicate", policy])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    let first = format_with("first");
+    assert_eq!(String::from_utf8_lossy(&first.stdout), "HOST=h\nPORT=1\n");
+    let last = format_with("last");
+    assert_eq!(String::from_utf8_lossy(&last.stdout), "HOST=h\nPORT=2\n");
+
+    let error = format_with("error");
+    assert_eq!(error.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&error.stderr).contains("duplicate key 'PORT' defined 2 times"));
+}
+
+#[test]
+fn test_check_summary_line() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nDEBUG: bool\nAPI_KEY: string\n"),
+        (".env", "PORT=http\nDEBUG=maybe\n"),
+        ("ok.env", "PORT=80\nDEBUG=true\nAPI_KEY=k\nEXTRA=1\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--summary-line"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().last(),
+        Some("SUMMARY missing=1 type_errors=2 extra=0 valid=false")
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "ok.env", "--summary-line"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().last(),
+        Some("SUMMARY missing=1 type_errors=2 extra=1 valid=false")
+    );
+}
+
+#[test]
+fn test_unset_key_with_comment() {
+    let dir = setup_test_files(&[(
+        ".env",
+        "# Database\nDB_HOST=h\n\n# Legacy flag\nOLD=1\nPORT=80\n",
+    )]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["unset", ".env", "OLD", "--with-comments"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".env")).unwrap(),
+        "# Database\nDB_HOST=h\n\nPORT=80\n"
+    );
+}
+
+#[test]
+fn test_unset_missing_key() {
+    let dir = setup_test_files(&[(".env", "PORT=80\n")]);
+    let unset = |extra: &[&str]| {
+        Command::new(envcraft_bin())
+            .args(["unset", ".env", "OLD"])
+            .args(extra)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    let output = unset(&[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("OLD is not set in .env; nothing to remove"));
+
+    let output = unset(&["--strict"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("key not found: OLD"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".env")).unwrap(),
+        "PORT=80\n"
+    );
+}
+
+#[test]
+fn test_check_input_encoding() {
+    let dir = setup_test_files(&[("schema.yml", "CITY: string\nPORT: int\n")]);
+    fs::write(dir.path().join("latin1.env"), b"CITY=M\xfcnchen\nPORT=80\n").unwrap();
+    let utf16: Vec<u8> = "CITY=München\nPORT=80\n"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    fs::write(dir.path().join("utf16.env"), utf16).unwrap();
+
+    for (file, encoding) in [("latin1.env", "latin1"), ("utf16.env", "utf-16")] {
+        let output = Command::new(envcraft_bin())
+            .args(["check", "schema.yml", file, "--input-encoding", encoding])
+            .args(["--print-effective"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("CITY=München"), "{file}: {stdout}");
+        assert!(output.status.success());
+    }
+
+    let strict = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "latin1.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(strict.status.code(), Some(2));
+}
+
+#[test]
+fn test_edit_dry_run() {
+    let original = "# Server\nPORT=8080\n# Legacy\nOLD_HOST=h\n";
+    let dir = setup_test_files(&[(".env", original)]);
+    let run = |args: &[&str]| {
+        let output = Command::new(envcraft_bin())
+            .args(args)
+            .arg("--dry-run")
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert_eq!(
+        run(&["set", ".env", "PORT", "9090"]),
+        "- PORT=8080\n+ PORT=9090\ndry run: .env not modified\n"
+    );
+    assert_eq!(
+        run(&["unset", ".env", "OLD_HOST", "--with-comments"]),
+        "- # Legacy\n- OLD_HOST=h\ndry run: .env not modified\n"
+    );
+    assert_eq!(
+        run(&["rename", ".env", "OLD_HOST", "HOST"]),
+        "- OLD_HOST=h\n+ HOST=h\ndry run: .env not modified\n"
+    );
+    assert_eq!(
+        run(&["set", ".env", "PORT", "8080"]),
+        "dry run: no changes to .env\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".env")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn test_diff_show_unchanged() {
+    let dir = setup_test_files(&[
+        ("a.env", "APP=web\nPORT=80\nZONE=a\n"),
+        ("b.env", "APP=web\nPORT=8080\nZONE=a\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--show-unchanged"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("= APP=web\n~ PORT: 80 → 8080\n= ZONE=a\n\n"));
+    assert!(stdout.contains("1 difference(s) found"));
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--show-unchanged", "--redact"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("= APP\n~ PORT\n= ZONE\n"));
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("= "));
+    assert!(!stdout.contains("APP"));
+}
+
+#[test]
+fn test_convert_json_compact_by_default() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\nAPP=web\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["convert", ".env", "--to", "json"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"APP\":\"web\",\"PORT\":\"8080\"}\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["convert", ".env", "--to", "json", "--pretty"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\n  \"APP\": \"web\",\n  \"PORT\": \"8080\"\n}\n"
+    );
+}
+
+#[test]
+fn test_check_warns_on_differing_duplicate() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\n"),
+        (".env", "PORT=80\nPORT=8080\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "warning: key 'PORT' is defined 2 times with differing values (lines 1, 2); the value from line 2 is validated"
+    ));
+    assert!(stdout.contains("✓ validation passed with 1 warning(s)"));
+    assert!(output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "-W", "duplicate-key=error"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_format_sort_stable() {
+    let dir = setup_test_files(&[(".env", "db_host=b\nAPI=a\nDB_HOST=c\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--normalize-keys", "preserve"])
+        .args(["--sort", "stable"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "API=a\ndb_host=b\nDB_HOST=c\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--normalize-keys", "preserve"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "API=a\nDB_HOST=c\ndb_host=b\n"
+    );
+}
+
+#[test]
+fn test_check_detect_commented() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nHOST: string\n"),
+        (".env", "# PORT=8080\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--detect-commented"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: missing required key: PORT (present but commented out)\n"));
+    assert!(stdout.contains("error: missing required key: HOST\n"));
+    assert!(!output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("error: missing required key: PORT\n"));
+    assert!(!stdout.contains("commented"));
+}
+
+#[test]
+fn test_filter_expression_selects_keys() {
+    let dir = setup_test_files(&[
+        ("a.env", "DB_HOST=a\nDB_SECRET=x\nPORT=80\n"),
+        ("b.env", "DB_HOST=b\nDB_SECRET=y\nPORT=8080\n"),
+    ]);
+    let filter = r#"startswith("DB_") and not endswith("_SECRET")"#;
+
+    let output = Command::new(envcraft_bin())
+        .args(["keys", "a.env", "--filter", filter])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "DB_HOST\n");
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--filter", filter])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ DB_HOST: a → b"));
+    assert!(!stdout.contains("PORT"));
+    assert!(!stdout.contains("SECRET"));
+    assert!(stdout.contains("1 difference(s) found"));
+
+    let output = Command::new(envcraft_bin())
+        .args(["keys", "a.env", "--filter", "startswith(DB_)"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("expected a quoted string at column 12")
+    );
+}
+
+#[test]
+fn test_check_write_schema() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "# App settings\nPORT: int\n"),
+        (".env", "PORT=8080\nDEBUG=true\nNAME=app\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--write-schema"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("warning: extra key not in schema: DEBUG"));
+    assert!(stdout.contains("added 2 key(s) to schema schema.yml: DEBUG, NAME"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("schema.yml")).unwrap(),
+        "# App settings\nPORT: int\nDEBUG: bool\nNAME: string\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--write-schema"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ validation passed\n"));
+    assert!(stdout.contains("schema schema.yml already declares every key"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("schema.yml")).unwrap(),
+        "# App settings\nPORT: int\nDEBUG: bool\nNAME: string\n"
+    );
+}
+
+#[test]
+fn test_diff_matrix_three_files() {
+    let dir = setup_test_files(&[
+        ("a.env", "APP=web\nDB_HOST=db1\nTRACING=true\n"),
+        ("b.env", "APP=web\nDB_HOST=db2\n"),
+        ("c.env", "APP=web\nDB_HOST=db2\nTRACING=true\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "c.env", "--matrix"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "  KEY      a.env  b.env    c.env\n\
+         = APP      web    web      web\n\
+         ~ DB_HOST  db1    db2      db2\n\
+         ~ TRACING  true   (unset)  true\n\
+         \n\
+         2 of 3 key(s) inconsistent across 3 files\n"
+    );
+    assert!(output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "c.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_format_blank_lines() {
+    let dir = setup_test_files(&[(
+        ".env",
+        "PORT=8080\n# Public URL\nAPI_URL=x\nDEBUG=false\n\n",
+    )]);
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "format",
+            ".env",
+            "--blank-lines",
+            "1",
+            "--no-trailing-blank",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Public URL\nAPI_URL=x\n\nDEBUG=false\n\nPORT=8080\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--blank-lines", "0"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Public URL\nAPI_URL=x\nDEBUG=false\nPORT=8080\n\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["format", ".env", "--blank-lines", "2"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_diff_redact_key_pattern() {
+    let dir = setup_test_files(&[
+        ("a.env", "PORT=80\nDB_SECRET=hunter2\n"),
+        ("b.env", "PORT=8080\nDB_SECRET=swordfish\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact-key", "*_SECRET"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ DB_SECRET\n"));
+    assert!(stdout.contains("~ PORT: 80 → 8080\n"));
+    assert!(!stdout.contains("hunter2"));
+    assert!(!stdout.contains("swordfish"));
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "diff",
+            "a.env",
+            "b.env",
+            "--redact-key",
+            "*_SECRET",
+            "--redact-hash",
+        ])
+        .args(["--format", "jsonl"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""new_value":"sha256:"#));
+    assert!(stdout.contains(r#""new_value":"8080""#));
+    assert!(!stdout.contains("swordfish"));
+}
+
+/// Run `envcraft check` with `args` and `input` piped to stdin.
+fn check_with_stdin(dir: &TempDir, args: &[&str], input: &[u8]) -> std::process::Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(envcraft_bin())
+        .arg("check")
+        .args(args)
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run envcraft");
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().expect("Failed to run envcraft")
+}
+
+#[test]
+fn test_check_schema_from_stdin() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\n")]);
+
+    let output = check_with_stdin(&dir, &["-", ".env"], b"PORT: int\n");
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = check_with_stdin(&dir, &["-", ".env"], b"PORT: int\nHOST: string\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("missing required key: HOST"), "{stdout}");
+}
+
+#[test]
+fn test_check_env_from_stdin() {
+    let dir = setup_test_files(&[("schema.yml", "PORT: int\n")]);
+
+    let output = check_with_stdin(&dir, &["schema.yml", "-"], b"PORT=http\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("key 'PORT' has invalid value 'http'"),
+        "{stdout}"
+    );
+
+    let output = check_with_stdin(&dir, &["-", "-"], b"PORT=80\n");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("only one input can be read from stdin"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_check_stdin_size_limit_and_encoding() {
+    let dir = setup_test_files(&[("schema.yml", "CITY: string\n")]);
+
+    let output = check_with_stdin(
+        &dir,
+        &["schema.yml", "-", "--max-file-size", "4"],
+        b"CITY=a\n",
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("stdin is 7 bytes, over the --max-file-size limit of 4 bytes"),
+        "{stderr}"
+    );
+
+    let latin1 = b"CITY=M\xFCnchen\n";
+    let output = check_with_stdin(&dir, &["schema.yml", "-"], latin1);
+    assert_eq!(output.status.code(), Some(2));
+    let args = [
+        "schema.yml",
+        "-",
+        "--input-encoding",
+        "latin1",
+        "--print-effective",
+    ];
+    let output = check_with_stdin(&dir, &args, latin1);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("CITY=München"), "{stdout}");
+}
+
+#[test]
+fn test_check_strict_escapes() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "GREETING: string"),
+        (".env", "GREETING=\"hi\\qthere\"\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--dialect", "node"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--dialect", "node"])
+        .arg("--strict-escapes")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("unknown escape sequence '\\q' at line 1, column 13"),
+        "{stderr}"
+    );
+
+    // The default dialect has no escapes to check against
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--strict-escapes"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("--strict-escapes needs --dialect node or posix"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_diff_unified() {
+    let dir = setup_test_files(&[
+        ("a.env", "# app\nPORT=80\nHOST=a\nDEBUG=false\n"),
+        ("b.env", "# app\nDEBUG=false\nHOST=a\nPORT=8080\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--unified"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout,
+        "--- a.env\n+++ b.env\n@@ -1,4 +1,4 @@\n # app\n DEBUG=false\n HOST=a\n-PORT=80\n+PORT=8080\n"
+    );
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--unified=0"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("@@ -4 +4 @@\n-PORT=80\n+PORT=8080\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_check_input_limits() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "A: int\nB: int\nC: int"),
+        (".env", "A=1\nB=2\nC=3\n"),
+    ]);
+    let check = |args: &[&str]| {
+        Command::new(envcraft_bin())
+            .args(["check", "schema.yml", ".env"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    assert!(check(&["--max-keys", "3", "--max-file-size", "12"])
+        .status
+        .success());
+
+    let output = check(&["--max-keys", "2"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("more than 2 keys defined (limit exceeded at line 3)"),
+        "{stderr}"
+    );
+
+    let output = check(&["--max-file-size", "11"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("is 12 bytes, over the --max-file-size limit of 11 bytes"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_check_pattern_keys() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "APP_NAME: string\n\"FEATURE_*\": bool\n"),
+        (
+            ".env",
+            "APP_NAME=web\nFEATURE_SEARCH=true\nFEATURE_CHAT=false\nFEATURE_BETA=soon\nDEBUG=1\n",
+        ),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("key 'FEATURE_BETA' has invalid value 'soon'"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("FEATURE_SEARCH"), "{stdout}");
+    assert!(!stdout.contains("FEATURE_CHAT"), "{stdout}");
+    assert!(stdout.contains("DEBUG"), "{stdout}");
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--explain", "FEATURE_SEARCH"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("pattern: FEATURE_*\n"), "{stdout}");
+}
+
+#[test]
+fn test_check_values_from() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "PORT: int\nAPI_KEY: {type: string, forbid: [changeme]}\nDB_PASSWORD: string\n",
+        ),
+        ("template.env", "PORT=8080\nAPI_KEY=changeme\n"),
+        ("secrets.env", "API_KEY=s3cret\nDB_PASSWORD=hunter2\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "template.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", "template.env"])
+        .args(["--values-from", "secrets.env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+}
+
+#[test]
+fn test_diff_fold_case_values() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "DEBUG: bool\nNAME: string"),
+        ("a.env", "DEBUG=TRUE\nNAME=app"),
+        ("b.env", "DEBUG=true\nNAME=App"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--schema", "schema.yml"])
+        .arg("--fold-case-values")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("DEBUG"), "{stdout}");
+    assert!(stdout.contains("~ NAME: app → App"), "{stdout}");
+    assert!(stdout.contains("1 difference(s) found"));
+}
+
+#[test]
+fn test_check_partial_group() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "AWS_KEY: string\nAWS_SECRET: string\nAWS_REGION: string\ngroups:\n  - {group: aws, keys: [AWS_KEY, AWS_SECRET, AWS_REGION], policy: all_or_none}\n",
+        ),
+        ("none.env", "OTHER=1\n"),
+        ("partial.env", "AWS_KEY=k\nAWS_REGION=eu-west-1\n"),
+    ]);
+    let check = |file: &str| {
+        Command::new(envcraft_bin())
+            .args(["check", "schema.yml", file, "-W", "extra-key=allow"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    assert!(check("none.env").status.success());
+
+    let output = check("partial.env");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains(
+            "group 'aws' must be set in full or not at all: AWS_KEY, AWS_REGION set, AWS_SECRET missing"
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_diff_mask_style() {
+    let dir = setup_test_files(&[
+        ("a.env", "PORT=80\nDB_SECRET=hunter2\n"),
+        ("b.env", "PORT=8080\nDB_SECRET=swordfish\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact-key", "*_SECRET"])
+        .args(["--mask-style", "length"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ DB_SECRET: ******* → *********\n"));
+    assert!(stdout.contains("~ PORT: 80 → 8080\n"));
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact"])
+        .args(["--mask-style", "fixed", "--mask-char", "x"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~ DB_SECRET: xxxxxxxx → xxxxxxxx\n"));
+    assert!(stdout.contains("~ PORT: xxxxxxxx → xxxxxxxx\n"));
+
+    // Masking needs something to redact
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--mask-style", "fixed"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_check_strict_quotes() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "GREETING: string"),
+        (".env", "GREETING=\"hello\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--strict-quotes"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("unmatched quote \" at line 1, column 10"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_check_fix() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "DEBUG: bool\nPORT: int\nNAME: string\n"),
+        (".env", "# app\nDEBUG=yes\nPORT=\" 8080 \"\nNAME=app\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--fix"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("fixed DEBUG in .env: yes → true"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("fixed PORT in .env:  8080  → 8080"),
+        "{stdout}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".env")).unwrap(),
+        "# app\nDEBUG=true\nPORT=8080\nNAME=app\n"
+    );
+}
+
+#[test]
+fn test_check_fix_leaves_unfixable_errors() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "DEBUG: bool\nPORT: int\n"),
+        (".env", "DEBUG=off\nPORT=eighty\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--fix"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("fixed DEBUG in .env: off → false"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("key 'PORT' has invalid value 'eighty'"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("key 'DEBUG'"), "{stdout}");
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".env")).unwrap(),
+        "DEBUG=false\nPORT=eighty\n"
+    );
+}
+
+#[test]
+fn test_check_fix_keeps_byte_order_mark() {
+    let dir = setup_test_files(&[("schema.yml", "DEBUG: bool\n")]);
+    let path = dir.path().join(".env");
+    fs::write(&path, b"\xEF\xBB\xBFDEBUG=yes\n").unwrap();
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--fix"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("fixed DEBUG in .env: yes → true"),
+        "{stdout}"
+    );
+    assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFDEBUG=true\n");
+}
+
+#[test]
+fn test_check_fix_refuses_other_encodings() {
+    let dir = setup_test_files(&[("schema.yml", "DEBUG: bool\nCITY: string\n")]);
+    let path = dir.path().join("l1.env");
+    fs::write(&path, b"CITY=M\xFCnchen\nDEBUG=yes\n").unwrap();
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "check",
+            "schema.yml",
+            "l1.env",
+            "--input-encoding",
+            "latin1",
+        ])
+        .arg("--fix")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("--fix only rewrites UTF-8 files; convert l1.env to UTF-8 first"),
+        "{stderr}"
+    );
+    assert_eq!(fs::read(&path).unwrap(), b"CITY=M\xFCnchen\nDEBUG=yes\n");
+}
+
+#[test]
+fn test_check_fix_skips_included_keys() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "DEBUG: bool\nVERBOSE: bool\n"),
+        ("m.env", "#include inc.env\nVERBOSE=on\n"),
+        ("inc.env", "DEBUG=yes\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "check",
+            "schema.yml",
+            "m.env",
+            "--resolve-includes",
+            "--fix",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("fixed VERBOSE in m.env: on → true"),
+        "{stdout}"
+    );
+    // The included value is reported, not copied into the including file
+    assert!(!stdout.contains("fixed DEBUG"), "{stdout}");
+    assert!(
+        stdout.contains("key 'DEBUG' has invalid value 'yes'"),
+        "{stdout}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("m.env")).unwrap(),
+        "#include inc.env\nVERBOSE=true\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("inc.env")).unwrap(),
+        "DEBUG=yes\n"
+    );
+}
+
+#[test]
+fn test_convert_docker_env() {
+    let dir = setup_test_files(&[(
+        ".env",
+        "NAME=\"my app\"\nCERT=\"line one\\nline two\"\nPORT=8080\n",
+    )]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["convert", ".env", "--to", "docker-env", "--dialect", "node"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success());
+    assert_eq!(stdout, "NAME=my app\nPORT=8080\n");
+    assert!(
+        stderr.contains("warning: skipping CERT: docker env files cannot hold multiline values"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_convert_json_schema() {
+    let dir = setup_test_files(&[(
+        "schema.yml",
+        "PORT: int\nAPI_KEY: {type: string, forbid: [changeme]}\nTOKEN: {type: hex, bytes: 16}\n",
+    )]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["convert", "schema.yml", "--to", "json-schema"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let properties = &document["properties"];
+    assert_eq!(properties["PORT"]["pattern"], "^[+-]?[0-9]+$");
+    assert_eq!(
+        properties["API_KEY"]["not"],
+        serde_json::json!({"enum": ["changeme"]})
+    );
+    assert_eq!(properties["TOKEN"]["minLength"], 32);
+    assert_eq!(properties["TOKEN"]["maxLength"], 32);
+    assert_eq!(
+        document["required"],
+        serde_json::json!(["API_KEY", "PORT", "TOKEN"])
+    );
+}
+
+/// Files for the determinism test: CRLF line endings, unsorted keys, a
+/// duplicate, Unicode, comments, and values that need quoting.
+const DETERMINISM_FILES: &[(&str, &str)] = &[
+    (
+        "schema.yml",
+        "PORT: int\nDEBUG: bool\nCITY: string\nAPI_KEY: {type: string, forbid: [changeme]}\nFEATURE_*: bool\n",
+    ),
+    (
+        ".env",
+        "# app\r\nZETA=last\r\nPORT=8080\r\nCITY=München\r\nDEBUG=yes\r\nAPI_KEY=changeme\r\nFEATURE_B=true\r\nFEATURE_A=false\r\nPADDED=\" x \"\r\nZETA=again\r\nURL=http://$HOST/api\r\n",
+    ),
+    (
+        ".env.local",
+        "PORT=9090\nCITY=Zürich\nNEW=1\nFEATURE_A=true\nAPI_KEY=secret\n",
+    ),
+];
+
+#[test]
+fn test_output_is_deterministic_across_runs_and_directories() {
+    let commands: &[&[&str]] = &[
+        &["check", "schema.yml", ".env"],
+        &["check", "schema.yml", ".env", ".env.local", "-v"],
+        &[
+            "check",
+            "schema.yml",
+            ".env",
+            "--report-file",
+            "report.json",
+        ],
+        &["diff", ".env", ".env.local"],
+        &["diff", ".env", ".env.local", "--format", "jsonl"],
+        &["diff", ".env", ".env.local", "--redact-hash"],
+        &["diff", ".env", ".env.local", "--matrix"],
+        &["diff", ".env", ".env.local", "--unified"],
+        &["diff", ".env", ".env.local", "--as-patch"],
+        &["format", ".env"],
+        &["format", ".env.local", "--export", "add"],
+        &["stats", ".env"],
+        &["stats", ".env", "--format", "json"],
+        &["hash", ".env"],
+        &["keys", ".env"],
+        &["lint", ".env"],
+        &["convert", ".env", "--to", "json"],
+        &["convert", ".env", "--to", "docker-env"],
+        &["convert", "schema.yml", "--to", "json-schema"],
+        &["resolve", "."],
+        &["check-consistency", "--key", "PORT", ".env", ".env.local"],
+        &["assert", ".env", "PORT:int", "DEBUG:bool"],
+    ];
+    // Identical trees at different paths must give identical output
+    let dirs = [
+        setup_test_files(DETERMINISM_FILES),
+        setup_test_files(DETERMINISM_FILES),
+    ];
+
+    for args in commands {
+        let mut runs = Vec::new();
+        for dir in dirs.iter().chain(&dirs) {
+            let output = Command::new(envcraft_bin())
+                .args(*args)
+                .current_dir(dir.path())
+                .output()
+                .expect("Failed to run envcraft");
+            // The report written by --report-file is output too
+            let report = fs::read(dir.path().join("report.json")).unwrap_or_default();
+            runs.push((output.status.code(), output.stdout, output.stderr, report));
+        }
+
+        let (code, stdout, stderr, _) = &runs[0];
+        assert_ne!(
+            *code,
+            Some(2),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(stderr)
+        );
+        assert!(
+            !stdout.contains(&b'\r'),
+            "{args:?} wrote a CR line ending: {}",
+            String::from_utf8_lossy(stdout)
+        );
+        for run in &runs[1..] {
+            assert_eq!(run, &runs[0], "{args:?} output differs between runs");
+        }
+    }
+}
+
+#[test]
+fn test_check_emit_missing() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nAPI_KEY: string\nDEBUG: bool\n"),
+        (".env", "PORT=8080\nSECRET=hunter2\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--emit-missing"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "API_KEY=\nDEBUG=\n");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_diff_against_environment() {
+    let dir = setup_test_files(&[(".env", "PORT=8080\nDEBUG=false\nAPI_KEY=abc\n")]);
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(envcraft_bin())
+            .args(["diff", ".env", "--env"])
+            .args(extra)
+            .env_clear()
+            .env("PORT", "9090")
+            .env("DEBUG", "false")
+            .env("EXTRA_VAR", "1")
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("- API_KEY=abc"));
+    assert!(stdout.contains("+ EXTRA_VAR=1"));
+    assert!(stdout.contains("~ PORT: 8080 → 9090"));
+    assert!(!stdout.contains("DEBUG"));
+
+    let stdout = run(&["--file-keys-only"]);
+    assert!(stdout.contains("- API_KEY=abc"));
+    assert!(!stdout.contains("EXTRA_VAR"));
+    assert!(stdout.contains("2 difference(s) found"));
+}
+
+#[test]
+fn test_use_schema_then_check_by_name() {
+    let dir = setup_test_files(&[
+        ("shared/schema.yml", "PORT: int\n"),
+        ("api/.env", "PORT=abc\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["use-schema", "billing", "shared/schema.yml"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+    assert!(output.status.success());
+    assert!(dir.path().join(".envcraft/schemas/billing.yml").is_file());
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "@billing", ".env"])
+        .current_dir(dir.path().join("api"))
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("key 'PORT' has invalid value 'abc'"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_print_effective_with_default() {
+    let dir = setup_test_files(&[
+        (
+            "schema.yml",
+            "PORT: {type: int, default: 8080}\nAPI_KEY: string\n",
+        ),
+        (".env", "API_KEY=secret\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["check", "schema.yml", ".env", "--print-effective"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "API_KEY=secret\nPORT=8080  # default\n\n✓ validation passed\n"
+    );
+    assert!(output.status.success());
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "check",
+            "schema.yml",
+            ".env",
+            "--print-effective",
+            "--redact",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("API_KEY\nPORT  # default\n"));
+    assert!(!stdout.contains("secret"));
+}
+
+#[test]
+fn test_diff_multiline_value_block() {
+    let dir = setup_test_files(&[(".env", "GREETING=hello\n")]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", ".env", "--env"])
+        .env_clear()
+        .env("GREETING", "hello\nworld")
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("~ GREETING:\n    hello\n  + world\n"));
+}
+
+#[test]
+fn test_format_check_ignore_comments() {
+    let dir = setup_test_files(&[
+        (
+            "comments.env",
+            "#  hand-formatted header\n\n\nA=1\n#note\nB=2",
+        ),
+        ("keys.env", "# header\nb=2\nA=1\n"),
+    ]);
+
+    let check = |file: &str| {
+        Command::new(envcraft_bin())
+            .args(["format", file, "--check", "--ignore-comments"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run envcraft")
+    };
+
+    let output = check("comments.env");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✓ comments.env is formatted (ignoring comments)"));
+    assert!(output.status.success());
+
+    let output = check("keys.env");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✗ keys.env would be reformatted"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_report_file() {
+    let dir = setup_test_files(&[
+        ("schema.yml", "PORT: int\nAPI_KEY: string\n"),
+        (".env", "PORT=abc\nEXTRA=1\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args([
+            "check",
+            "schema.yml",
+            ".env",
+            "--report-file",
+            "results.json",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✗ validation failed with 2 error(s)"));
+    assert_eq!(output.status.code(), Some(1));
+
+    let report = fs::read_to_string(dir.path().join("results.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["passed"], false);
+    assert_eq!(report["files"][0]["path"], ".env");
+    let diagnostics = report["files"][0]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(diagnostics[0]["rule"], "missing-key");
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert_eq!(diagnostics[2]["rule"], "extra-key");
+    assert_eq!(diagnostics[2]["severity"], "warning");
+}
+
+#[test]
+fn test_diff_report_file() {
+    let dir = setup_test_files(&[
+        ("a.env", "PORT=8080\nSECRET=old\n"),
+        ("b.env", "PORT=9090\nSECRET=new\nDEBUG=true\n"),
+    ]);
+
+    let output = Command::new(envcraft_bin())
+        .args(["diff", "a.env", "b.env", "--redact"])
+        .args(["--report-file", "diff.json"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run envcraft");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("3 difference(s) found"));
+
+    let report = fs::read_to_string(dir.path().join("diff.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["file1"], "a.env");
+    assert_eq!(report["file2"], "b.env");
+    assert_eq!(report["count"], 3);
+    assert_eq!(
+        report["differences"][0],
+        serde_json::json!({"change": "added", "key": "DEBUG"})
+    );
+    assert!(!report.to_string().contains("new"));
+}